@@ -39,12 +39,26 @@ pub enum Error {
 
     #[error("Convert PathBuf to String Error")]
     ConvertPathBufToString,
+
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("{0}")]
+    Conflict(String),
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
+        let status = match self {
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
         (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            status,
             Json(WrappingResponse::<()>::error(self.to_string())),
         )
             .into_response()
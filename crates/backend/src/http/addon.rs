@@ -1,27 +1,33 @@
+use std::collections::{HashMap, HashSet};
+
 use axum::{
     extract::{self, Path, State},
     routing::{get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
 use database::{
-    AddonCompiledModel, AddonCompiledPage, AddonCompiledWidget, AddonDashboardPage,
-    AddonInstanceModel, AddonModel, AddonPermissionModel, AddonTemplatePageContentModel,
-    AddonTemplatePageModel, AddonWidgetContent, AddonWidgetNoDataModel,
-    AddonWidgetPanelContentModel, AddonWidgetPanelNoDataModel, NewAddonCompiledModel,
-    NewAddonCompiledPage, NewAddonCompiledWidget, NewAddonInstanceModel, NewAddonTemplatePageModel,
+    find_missing_permissions, is_permission_granted, AddonAutomationModel, AddonCapability,
+    AddonCompiledModel, AddonCompiledPage, AddonCompiledWidget, AddonDashboardBundleModel,
+    AddonDashboardPage, AddonExtensionModel, AddonInstallSettingsModel, AddonInstanceGrantModel,
+    AddonInstanceModel, AddonModel, AddonPermissionModel, AddonPricingPlanModel,
+    AddonSitePluginModel, AddonTemplatePageContentModel, AddonTemplatePageModel,
+    AddonWidgetContent, AddonWidgetNoDataModel, AddonWidgetPanelContentModel,
+    AddonWidgetPanelNoDataModel, ExtensionKind, ExtensionUsage, NewAddonCompiledModel,
+    NewAddonCompiledPage, NewAddonCompiledWidget, NewAddonInstallSessionModel,
+    NewAddonInstanceGrantModel, NewAddonInstanceModel, NewAddonTemplatePageModel,
     NewAddonWidgetContent, NewAddonWidgetPanelContentModel, SchemaModel, VisslCodeAddonModel,
-    VisslCodeAddonPanelModel, WidgetModel,
+    VisslCodeAddonPanelModel, WebhookEvent, WebsiteWidgetSettingsModel, WidgetModel,
 };
 use eyre::ContextCompat;
 use lazy_static::lazy_static;
-use local_common::{DashboardPageInfo, MemberModel, WebsiteModel};
+use local_common::{AddonWidgetId, DashboardPageInfo, MemberModel, WebsiteModel};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use sqlx::{Connection, SqliteConnection, SqlitePool};
 use time::format_description;
 use uuid::Uuid;
 use webby_addon_common::{
-    InstallResponse, MemberPartial, MemberUuid, RegisterNewJson, WebsitePartial, WebsiteUuid,
+    InstallResponse, MemberPartial, RegisterNewJson, WebsitePartial, WebsiteUuid,
 };
 use webby_api::{ListResponse, WrappingResponse};
 use webby_global_common::{
@@ -34,7 +40,20 @@ use webby_storage::{
 };
 
 use crate::{
-    http::{query_active_addon_list, website::CompiledAddonWidgetInfo},
+    http::{
+        auth::AuthMember,
+        automation::{spawn_automation_event, AutomationEvent},
+        billing::{
+            cancel_subscription, find_install_plan, subscribe_instance, BillingProvider,
+            SharedBillingProvider,
+        },
+        extension::{
+            find_compiled_usage, find_draft_usage, require_extension, verify_extension_usage,
+        },
+        install_settings, query_active_addon_list,
+        webhook::queue_webhook_event,
+        website::CompiledAddonWidgetInfo,
+    },
     Result,
 };
 
@@ -88,6 +107,7 @@ pub struct PublishAddonJson {
 pub async fn publish_addon(
     extract::State(db): extract::State<SqlitePool>,
     Path(addon_id): Path<AddonUuid>,
+    member: AuthMember,
 
     Json(PublishAddonJson { draft, version }): Json<PublishAddonJson>,
 ) -> Result<JsonResponse<&'static str>> {
@@ -100,6 +120,18 @@ pub async fn publish_addon(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::Publish, &mut acq)
+        .await?;
+
+    let extension = AddonExtensionModel::find_one_draft_by_addon_id(addon.id, &mut acq)
+        .await?
+        .context("Addon Extension Manifest not found")?;
+
+    let usage = find_draft_usage(addon.id, &mut acq).await?;
+
+    verify_extension_usage(&extension.extends, usage)?;
+
     let widgets = AddonWidgetContent::find_by_addon_id(addon.id, &mut acq).await?;
     let panels = AddonWidgetPanelContentModel::find_by_addon_id(addon.id, &mut acq).await?;
 
@@ -147,6 +179,12 @@ pub async fn publish_addon(
         items
     };
 
+    let permissions = AddonPermissionModel::find_by_addon_id(addon.id, &mut acq)
+        .await?
+        .into_iter()
+        .map(|v| v.perm.to_string())
+        .collect::<Vec<_>>();
+
     let type_of = if draft {
         database::AddonPublishType::Draft
     } else {
@@ -177,6 +215,7 @@ pub async fn publish_addon(
                 settings: webby_storage::widget::CompiledAddonSettings {},
                 type_of,
                 version: version.clone(),
+                permissions,
             }
             .insert(trx)
             .await?;
@@ -299,10 +338,29 @@ pub async fn publish_addon(
                 .await?;
             }
 
+            AddonAutomationModel::publish_drafts(addon.id, compiled.pk, trx).await?;
+            AddonExtensionModel::publish_draft(addon.id, compiled.pk, usage, trx).await?;
+            AddonInstallSettingsModel::publish_draft(addon.id, compiled.pk, trx).await?;
+            AddonDashboardBundleModel::publish_draft(addon.id, compiled.pk, trx).await?;
+            AddonSitePluginModel::publish_drafts(addon.id, compiled.pk, trx).await?;
+
             addon.version = version;
 
             addon.update(trx).await?;
 
+            if !draft {
+                queue_webhook_event(
+                    addon.id,
+                    WebhookEvent::AddonPublished,
+                    serde_json::json!({
+                        "addonId": addon.guid,
+                        "version": addon.version,
+                    }),
+                    trx,
+                )
+                .await?;
+            }
+
             eyre::Ok(())
         })
     })
@@ -314,48 +372,105 @@ pub async fn publish_addon(
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AddonInstall {
+    /// The member and website are looked up through the authenticated member.
     website_id: WebsiteUuid,
-    member_id: MemberUuid,
 
-    // TODO: Both of these are said Models'
-    member: MemberModel,
-    website: WebsiteModel,
+    /// Required once the addon has pricing plans, unless its' only plan is free.
+    plan_id: Option<Uuid>,
+    seats: Option<i32>,
+
+    /// Permissions the member consented to on the install screen.
+    #[serde(default)]
+    permissions: Vec<String>,
+}
+
+/// Errors unless every permission the version requests is granted or being consented to.
+fn consent_error(
+    granted: &[String],
+    consented: &[String],
+    compiled: &AddonCompiledModel,
+) -> Result<()> {
+    let missing = find_missing_permissions(&[granted, consented].concat(), &compiled.permissions);
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(eyre::eyre!(
+            "Consent is needed for the permissions: {}",
+            missing.join(", ")
+        ))?
+    }
 }
 
 pub async fn website_addon_install(
     State(db): State<SqlitePool>,
     Path(addon_uuid): Path<AddonUuid>,
+    Extension(billing): Extension<SharedBillingProvider>,
+    member: AuthMember,
     Json(value): Json<AddonInstall>,
 ) -> Result<JsonResponse<AddonInstallResponse>> {
+    let website = member.find_owned_website(*value.website_id).await?;
+
     let mut acq = db.acquire().await?;
 
     let addon = AddonModel::find_one_by_guid(*addon_uuid, &mut acq)
         .await?
         .context("Addon not found")?;
 
-    // TODO: Get an optional current version of the addon instance if it exists. So we can update it instead of installing a new one.
     let active_instances = query_active_addon_list(value.website_id, &mut acq).await?;
 
-    // Get newest version
-    let mut compiled = AddonCompiledModel::get_all(addon.id, 0, 1, &mut acq).await?;
-
-    let Some(compiled) = compiled.pop() else {
+    // Get newest published version, drafts are never installed.
+    let Some(compiled) = AddonCompiledModel::find_latest_published(addon.id, &mut acq).await?
+    else {
         return Err(eyre::eyre!("Addon doesn't exist"))?;
     };
 
+    let extension = AddonExtensionModel::find_one_by_compiled_id(compiled.pk, &mut acq)
+        .await?
+        .context("Addon Extension Manifest not found")?;
+
+    verify_extension_usage(
+        &extension.extends,
+        find_compiled_usage(addon.id, compiled.pk, &mut acq).await?,
+    )?;
+
     // Check if we have an active instance of the addon
     if let Some(instance) = active_instances.iter().find(|v| v.addon.guid == addon.guid) {
         if instance.instance_version != compiled.version {
-            // TODO: We need a way to revert changes if something goes wrong. ---- OR EVEN BETTER, execute the if's below in the transaction.
             // We have an active instance, but the version is different.
-            // We should remove addon pages
+            let instance_guid = instance.instance_guid;
+
+            let instance = AddonInstanceModel::find_by_uuid(*instance_guid, &mut acq)
+                .await?
+                .context("Addon Instance not found")?;
+
+            // Newly requested permissions have to be consented to before upgrading.
+            consent_error(
+                &AddonInstanceGrantModel::find_permissions_by_instance_id(instance.id, &mut acq)
+                    .await?,
+                &value.permissions,
+                &compiled,
+            )?;
+
+            let diff = acq
+                .transaction(|trx| {
+                    Box::pin(async move {
+                        AddonInstanceGrantModel::set_for_instance(
+                            instance.id,
+                            compiled.permissions.0.clone(),
+                            trx,
+                        )
+                        .await?;
 
-            warn!(
-                "Addon updating not implemented yet. Instance: {}, Version: {}",
-                instance.instance_guid, instance.instance_version
-            );
+                        upgrade_addon_instance(*instance_guid, addon_uuid, compiled, trx).await
+                    })
+                })
+                .await?;
 
-            return Ok(Json(WrappingResponse::error("NOT IMPLEMENTED YET")));
+            return Ok(Json(WrappingResponse::okay(AddonInstallResponse {
+                instance_uuid: instance_guid,
+                new_pages: serde_json::to_value(diff)?,
+            })));
         } else {
             // We have an active instance, and the version is the same.
             // We can skip the installation process.
@@ -363,16 +478,52 @@ pub async fn website_addon_install(
         }
     }
 
-    let instance = user_install_addon(*addon_uuid, value, compiled.version, &mut acq).await?;
+    consent_error(&[], &value.permissions, &compiled)?;
 
-    #[derive(Serialize)]
-    struct PublicPage {
-        type_of: webby_api::WebsitePageType,
-        addon_uuid: AddonUuid,
-        path: String,
-        display_name: String,
-        data: webby_storage::DisplayStore,
-    }
+    // The member installing is the one paying.
+    let payer = member.find_member().await?;
+
+    let plan = find_install_plan(
+        addon.id,
+        value.plan_id,
+        payer.stripe_customer_id.as_deref(),
+        &mut acq,
+    )
+    .await?;
+
+    let instance = user_install_addon(
+        *addon_uuid,
+        value,
+        &payer,
+        &website,
+        &compiled,
+        plan.as_ref(),
+        &*billing,
+        &mut acq,
+    )
+    .await?;
+
+    queue_webhook_event(
+        addon.id,
+        WebhookEvent::InstanceInstalled,
+        serde_json::json!({
+            "instanceId": instance.public_id,
+            "websiteId": instance.website_uuid,
+            "version": instance.version,
+        }),
+        &mut acq,
+    )
+    .await?;
+
+    spawn_automation_event(
+        db.clone(),
+        AutomationEvent::InstanceInstalled {
+            addon_id: addon.id,
+            instance_id: instance.public_id,
+            website_id: instance.website_uuid,
+            version: instance.version.clone(),
+        },
+    );
 
     let widget_pages = AddonCompiledPage::find_by_compiled_id(compiled.pk, &mut acq).await?;
 
@@ -387,22 +538,218 @@ pub async fn website_addon_install(
         new_pages: serde_json::to_value(
             widget_pages
                 .into_iter()
-                .map(|p| PublicPage {
-                    type_of: p.type_of,
-                    addon_uuid,
-                    path: p.path,
-                    display_name: p.display_name,
-                    data: p.data.0,
-                })
+                .map(|p| PublicPage::new(p, addon_uuid))
                 .collect::<Vec<PublicPage>>(),
         )?,
     })))
 }
 
+#[derive(Serialize)]
+pub(super) struct PublicPage {
+    type_of: webby_api::WebsitePageType,
+    addon_uuid: AddonUuid,
+    path: String,
+    display_name: String,
+    data: webby_storage::DisplayStore,
+}
+
+impl PublicPage {
+    pub(super) fn new(page: AddonCompiledPage, addon_uuid: AddonUuid) -> Self {
+        Self {
+            type_of: page.type_of,
+            addon_uuid,
+            path: page.path,
+            display_name: page.display_name,
+            data: page.data.0,
+        }
+    }
+}
+
+/// The changes between two compiled versions of an addon.
+///
+/// Pages are matched by their path, widgets by their widget id. Both are compared using the hash
+/// which was computed when the version was published.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddonUpgradeDiff {
+    from_version: String,
+    to_version: String,
+
+    added: Vec<PublicPage>,
+    changed: Vec<PublicPage>,
+    /// Paths of the pages which no longer exist
+    removed: Vec<String>,
+
+    added_widgets: Vec<AddonWidgetPublicId>,
+    changed_widgets: Vec<AddonWidgetPublicId>,
+    removed_widgets: Vec<AddonWidgetPublicId>,
+}
+
+/// Upgrades an installed instance to the `compiled` version.
+///
+/// Should be called inside of a transaction so a failure will revert every change.
+async fn upgrade_addon_instance(
+    instance_guid: Uuid,
+    addon_uuid: AddonUuid,
+    compiled: AddonCompiledModel,
+    db: &mut SqliteConnection,
+) -> Result<AddonUpgradeDiff> {
+    let mut instance = AddonInstanceModel::find_by_uuid(instance_guid, db)
+        .await?
+        .context("Addon Instance not found")?;
+
+    // The previous version may not exist anymore (or the instance was never given one.)
+    // In that case everything in the new version is counted as added.
+    let previous = AddonCompiledModel::find_one_by_addon_uuid_and_version(
+        instance.addon_id,
+        &instance.version,
+        db,
+    )
+    .await?;
+
+    let (old_pages, old_widgets) = if let Some(previous) = previous.as_ref() {
+        (
+            AddonCompiledPage::find_by_compiled_id(previous.pk, db).await?,
+            AddonCompiledWidget::find_by_compiled_id(previous.pk, db).await?,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let new_pages = AddonCompiledPage::find_by_compiled_id(compiled.pk, db).await?;
+    let new_widgets = AddonCompiledWidget::find_by_compiled_id(compiled.pk, db).await?;
+
+    // Pages
+    let mut old_pages = old_pages
+        .into_iter()
+        .map(|p| (p.path.clone(), p.hash))
+        .collect::<HashMap<_, _>>();
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+
+    for page in new_pages {
+        match old_pages.remove(&page.path) {
+            Some(hash) if hash == page.hash => (),
+            Some(_) => changed.push(PublicPage::new(page, addon_uuid)),
+            None => added.push(PublicPage::new(page, addon_uuid)),
+        }
+    }
+
+    let removed = old_pages.into_keys().collect::<Vec<_>>();
+
+    // Widgets
+    let mut old_widgets = old_widgets
+        .into_iter()
+        .map(|w| (w.widget_id, w.hash))
+        .collect::<HashMap<_, _>>();
+
+    let mut added_widgets = Vec::new();
+    let mut changed_widgets = Vec::new();
+    let mut widget_variables = HashMap::new();
+
+    for widget in &new_widgets {
+        match old_widgets.remove(&widget.widget_id) {
+            Some(hash) if hash == widget.hash => (),
+            Some(_) => changed_widgets.push(widget.widget_id),
+            None => added_widgets.push(widget.widget_id),
+        }
+
+        widget_variables.insert(
+            widget.widget_id,
+            widget
+                .settings
+                .variables
+                .iter()
+                .map(|v| v.name().to_string())
+                .collect::<HashSet<_>>(),
+        );
+    }
+
+    let removed_widgets = old_widgets.into_keys().collect::<Vec<_>>();
+
+    // Migrate the website's widget overrides.
+    // - Removed widgets have their overrides deleted.
+    // - Changed widgets keep only the overrides for variables which still exist.
+    for mut settings in WebsiteWidgetSettingsModel::find_all_by_website_id_and_addon_id(
+        instance.website_id,
+        instance.addon_id,
+        db,
+    )
+    .await?
+    {
+        if removed_widgets.contains(&settings.addon_widget_id) {
+            WebsiteWidgetSettingsModel::delete(settings.pk, settings.website_id, db).await?;
+            continue;
+        }
+
+        if !changed_widgets.contains(&settings.addon_widget_id) {
+            continue;
+        }
+
+        let Some(variables) = widget_variables.get(&settings.addon_widget_id) else {
+            continue;
+        };
+
+        let serde_json::Value::Object(existing) = &mut settings.settings.0 else {
+            continue;
+        };
+
+        let prev_len = existing.len();
+
+        existing.retain(|key, _| variables.contains(key));
+
+        if existing.is_empty() {
+            WebsiteWidgetSettingsModel::delete(settings.pk, settings.website_id, db).await?;
+        } else if existing.len() != prev_len {
+            settings.update(db).await?;
+        }
+    }
+
+    install_settings::migrate_instance_settings(&mut instance, &compiled, db).await?;
+
+    let from_version = std::mem::replace(&mut instance.version, compiled.version.clone());
+
+    instance.update(db).await?;
+
+    // Convert the widget ids into their public ids
+    let mut public_ids = HashMap::new();
+
+    for widget in WidgetModel::find_by_addon_id(instance.addon_id, db).await? {
+        public_ids.insert(widget.widget_id, widget.public_id);
+    }
+
+    let to_public = |ids: Vec<AddonWidgetId>| {
+        ids.into_iter()
+            .filter_map(|id| public_ids.get(&id).copied())
+            .collect::<Vec<_>>()
+    };
+
+    Ok(AddonUpgradeDiff {
+        from_version,
+        to_version: compiled.version,
+        added,
+        changed,
+        removed,
+        added_widgets: to_public(added_widgets),
+        changed_widgets: to_public(changed_widgets),
+        removed_widgets: to_public(removed_widgets),
+    })
+}
+
+/// Creates the instance, subscribes it to the plan and registers it with the addon.
+///
+/// The addon is only told about the instance once the subscription was created. `member` and
+/// `website` have to come from the identity provider, never the request.
+#[allow(clippy::too_many_arguments)]
 pub async fn user_install_addon(
     guid: Uuid,
     value: AddonInstall,
-    version: String,
+    member: &MemberModel,
+    website: &WebsiteModel,
+    compiled: &AddonCompiledModel,
+    plan: Option<&AddonPricingPlanModel>,
+    billing: &dyn BillingProvider,
     db: &mut SqliteConnection,
 ) -> Result<AddonInstanceModel> {
     let Some(addon) = AddonModel::find_one_by_guid(guid, db).await? else {
@@ -410,86 +757,66 @@ pub async fn user_install_addon(
     };
 
     // TODO: Check if website already has addon installed
-    // TODO: Ensure member_id is owner of website or has admin
-
-    // TODO: Utilize perms
-    let _perms = AddonPermissionModel::find_by_scope_addon_id(addon.id, "member", db).await?;
 
     // 1. Insert Website Addon
     let mut inst = NewAddonInstanceModel {
         addon_id: addon.id,
-        website_id: value.website.pk,
-        website_uuid: *value.website_id,
-        version,
+        website_id: website.pk,
+        website_uuid: website.id,
+        version: compiled.version.clone(),
     }
     .insert(db)
     .await?;
 
-    if let Some(url) = addon.action_url {
-        // 2. Send install request
-        let resp = CLIENT
-            .post(format!("{url}/registration"))
-            .json(&RegisterNewJson {
-                instance_id: inst.public_id,
-                version: inst.version.clone(),
-
-                owner_id: value.member_id,
-                website_id: value.website_id,
-
-                // TODO: Use Permissions
-                member: MemberPartial {
-                    uuid: value.member.id.into(),
-                    role: value.member.role,
-                    display_name: value.member.display_name,
-                    tag: Some(value.member.tag),
-                    email: Some(value.member.email),
-                    created_at: value.member.created_at,
-                    updated_at: value.member.updated_at,
-                },
-                website: WebsitePartial {
-                    public_id: value.website.id.into(),
-                    name: value.website.name,
-                    url: value.website.url,
-                    theme_id: value.website.theme_id,
-                    created_at: value.website.created_at,
-                    updated_at: value.website.updated_at,
-                },
-            })
-            .send()
-            .await?;
+    // Only what the version requests is granted, even if the member consented to more.
+    NewAddonInstanceGrantModel {
+        instance_id: inst.id,
+        permissions: compiled.permissions.0.clone(),
+    }
+    .insert(db)
+    .await?;
 
-        // TODO: Create Addon Template Pages & Widget info in main program
-        // TODO: Its' possible for the registration to succeed but WrappingResponse wil be an Error
+    let mut subscription = None;
 
-        if resp.status().is_success() {
-            // 3. Get Response - Can have multiple resolutions.
-            //  - Could want to redirect the user to finish on another site.
-            //  - Could be finished now
-            //  - Could be step 1 and require multiple setup requests & permission steps.
-            let resp: WrappingResponse<InstallResponse> = resp.json().await?;
+    if let Some(plan) = plan {
+        match subscribe_instance(
+            &inst,
+            plan,
+            member.stripe_customer_id.as_deref(),
+            value.seats.unwrap_or(1),
+            billing,
+            db,
+        )
+        .await
+        {
+            Ok(v) => subscription = Some(v),
+            Err(e) => {
+                inst.delete(db).await?;
 
-            match resp {
-                WrappingResponse::Resp(InstallResponse::Complete) => {
-                    inst.is_setup = true;
-                    inst.update(db).await?;
-                }
+                return Err(e);
+            }
+        }
+    }
 
-                WrappingResponse::Resp(InstallResponse::Redirect(_url)) => {
-                    // TODO
+    if let Some(url) = addon.action_url {
+        // 2. Send install request
+        if let Err(e) = register_addon_instance(&url, &mut inst, member, website, db).await {
+            if let Some(mut sub) = subscription {
+                if let Err(e) = cancel_subscription(&mut sub, billing, db).await {
+                    error!(
+                        "Failed to cancel the subscription of instance {}: {e}",
+                        inst.public_id
+                    );
                 }
-
-                WrappingResponse::Error(e) => return Err(eyre::eyre!("{}", e))?,
             }
 
-            Ok(inst)
-        } else {
             // TODO: Remove once registration is fully working
             inst.delete(db).await?;
 
-            let resp = resp.text().await?;
-
-            Err(eyre::eyre!("Addon Install Failed: {resp}"))?
+            return Err(e);
         }
+
+        Ok(inst)
     } else {
         inst.is_setup = true;
         inst.update(db).await?;
@@ -498,6 +825,90 @@ pub async fn user_install_addon(
     }
 }
 
+/// What the addon is sent about the member. Details are left out unless their permission is granted.
+pub fn member_partial(member: &MemberModel, granted: &[String]) -> MemberPartial {
+    MemberPartial {
+        uuid: member.id.into(),
+        role: member.role,
+        display_name: member.display_name.clone(),
+        tag: is_permission_granted(granted, "member.info.read.name").then(|| member.tag.clone()),
+        email: is_permission_granted(granted, "member.info.read.email")
+            .then(|| member.email.clone()),
+        created_at: member.created_at,
+        updated_at: member.updated_at,
+    }
+}
+
+/// Sends the registration request for the instance to the addon.
+///
+/// Also used to resume an install which was never completed.
+pub async fn register_addon_instance(
+    url: &str,
+    inst: &mut AddonInstanceModel,
+    member: &MemberModel,
+    website: &WebsiteModel,
+    db: &mut SqliteConnection,
+) -> Result<()> {
+    let granted = AddonInstanceGrantModel::find_permissions_by_instance_id(inst.id, db).await?;
+
+    let resp = CLIENT
+        .post(format!("{url}/registration"))
+        .json(&RegisterNewJson {
+            instance_id: inst.public_id,
+            version: inst.version.clone(),
+
+            owner_id: member.id.into(),
+            website_id: website.id.into(),
+
+            member: member_partial(member, &granted),
+            website: WebsitePartial {
+                public_id: website.id.into(),
+                name: website.name.clone(),
+                url: website.url.clone(),
+                theme_id: website.theme_id,
+                created_at: website.created_at,
+                updated_at: website.updated_at,
+            },
+        })
+        .send()
+        .await?;
+
+    // TODO: Create Addon Template Pages & Widget info in main program
+
+    if resp.status().is_success() {
+        // 3. Get Response - Can have multiple resolutions.
+        //  - Could want to redirect the user to finish on another site.
+        //  - Could be finished now
+        //  - Could be step 1 and require multiple setup requests & permission steps.
+        let resp: WrappingResponse<InstallResponse> = resp.json().await?;
+
+        match resp {
+            WrappingResponse::Resp(InstallResponse::Complete) => {
+                inst.is_setup = true;
+                inst.update(db).await?;
+            }
+
+            WrappingResponse::Resp(InstallResponse::Redirect(url)) => {
+                // Setup is finished once the addon calls the completion callback with the session state.
+                NewAddonInstallSessionModel {
+                    instance_id: inst.id,
+                    redirect_url: url,
+                }
+                .insert(db)
+                .await?;
+            }
+
+            WrappingResponse::Error(e) => return Err(eyre::eyre!("{}", e))?,
+        }
+
+        Ok(())
+    } else {
+        let resp = resp.text().await?;
+
+        Err(eyre::eyre!("Addon Install Failed: {resp}"))?
+    }
+}
+
 async fn get_addon_overview(
     Path(guid): Path<Uuid>,
     State(db): State<SqlitePool>,
@@ -512,9 +923,27 @@ async fn get_addon_overview(
     let published = AddonCompiledModel::get_all(addon.id, 0, 10, &mut acq).await?;
     let dash_pages = AddonDashboardPage::find_by_id(addon.id, &mut acq).await?;
     let template_pages = AddonTemplatePageModel::find_by_addon_id(addon.id, &mut acq).await?;
+    let extension = AddonExtensionModel::find_one_draft_by_addon_id(addon.id, &mut acq).await?;
 
-    let schemas = SchemaModel::find_by_addon_id(addon.id, &mut acq)
-        .await?
+    let site_plugins = AddonSitePluginModel::find_drafts_by_addon_id(addon.id, &mut acq).await?;
+
+    let schemas = SchemaModel::find_by_addon_id(addon.id, &mut acq).await?;
+
+    let undeclared = ExtensionUsage {
+        widgets: widgets.len(),
+        site_pages: template_pages.len(),
+        dashboard_pages: dash_pages.len(),
+        cms_collections: schemas.len(),
+        site_plugins: site_plugins.len(),
+    }
+    .undeclared(
+        extension
+            .as_ref()
+            .map(|v| v.extends.as_slice())
+            .unwrap_or_default(),
+    );
+
+    let schemas = schemas
         .into_iter()
         .map(|schema| webby_api::PublicSchema {
             schema_id: schema.name,
@@ -541,7 +970,10 @@ async fn get_addon_overview(
         "sitePages": template_pages,
         "dashboardPages": dash_pages.into_iter().map(|p| p.into()).collect::<Vec<DashboardPageInfo>>(),
         "dataGUIs": [],
-        "schemas": schemas
+        "schemas": schemas,
+        "sitePlugins": site_plugins,
+        "extension": extension,
+        "undeclared": undeclared,
     }))))
 }
 
@@ -553,6 +985,7 @@ pub struct AddonItemJson {
 pub async fn create_addon_item(
     Path(addon_id): Path<AddonUuid>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Json(AddonItemJson { item }): Json<AddonItemJson>,
 ) -> Result<JsonResponse<&'static str>> {
     let mut acq = db.acquire().await?;
@@ -561,6 +994,16 @@ pub async fn create_addon_item(
         return Err(eyre::eyre!("Addon not found"))?;
     };
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditDesign, &mut acq)
+        .await?;
+
+    match item.as_str() {
+        "widget" => require_extension(addon.id, ExtensionKind::Widget, &mut acq).await?,
+        "templatePage" => require_extension(addon.id, ExtensionKind::SitePage, &mut acq).await?,
+        _ => (),
+    }
+
     if item == "widget" {
         acq.transaction(|txn| {
             Box::pin(async move {
@@ -700,11 +1143,22 @@ pub async fn get_widget_data(
 pub async fn update_widget(
     Path((addon_id, widget_id)): Path<(AddonUuid, AddonWidgetPublicId)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Json(update): Json<webby_api::UpdateWidget>,
 ) -> Result<JsonResponse<&'static str>> {
     let mut acq = db.acquire().await?;
 
-    let Some(mut found) = AddonWidgetContent::find_one_by_public_id(widget_id, &mut acq).await?
+    let addon = AddonModel::find_one_by_guid(*addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::EditDesign, &mut acq)
+        .await?;
+
+    let Some(mut found) = AddonWidgetContent::find_one_by_public_id(widget_id, &mut acq)
+        .await?
+        .filter(|w| w.addon_id == addon.id)
     else {
         return Err(eyre::eyre!("Widget doesn't exist"))?;
     };
@@ -753,6 +1207,7 @@ pub async fn get_widget_panel_list(
 pub async fn create_website_panel(
     Path((addon_id, widget_id)): Path<(AddonUuid, AddonWidgetPublicId)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonResponse<AddonWidgetPanelContentModel>> {
     let mut acq = db.acquire().await?;
 
@@ -760,6 +1215,10 @@ pub async fn create_website_panel(
         return Err(eyre::eyre!("Addon not found"))?;
     };
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditDesign, &mut acq)
+        .await?;
+
     let widget = AddonWidgetContent::find_one_by_public_id_no_data(widget_id, &mut acq)
         .await?
         .context("Widget doesn't exist")?;
@@ -800,12 +1259,22 @@ pub async fn update_widget_panel_data(
         AddonWidgetPanelPublicId,
     )>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Json(store): Json<webby_api::UpdateWidgetPanel>,
 ) -> Result<JsonResponse<&'static str>> {
     let mut acq = db.acquire().await?;
 
+    let addon = AddonModel::find_one_by_guid(*addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::EditDesign, &mut acq)
+        .await?;
+
     let mut found = AddonWidgetPanelContentModel::find_one_by_public_id(panel_id, &mut acq)
         .await?
+        .filter(|p| p.addon_id == addon.id)
         .context("Panel doesn't exist")?;
 
     if let Some(store) = store.contents {
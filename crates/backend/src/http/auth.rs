@@ -0,0 +1,556 @@
+//! Resolves the member calling the API.
+//!
+//! Members and websites live in the main program. Requests are expected to carry the
+//! member's access token as a bearer token which the [`IdentityProvider`] resolves.
+
+use std::sync::Arc;
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts, Extension};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use database::{AddonCapability, AddonCollaboratorModel, AddonModel, AddonRole};
+use eyre::ContextCompat;
+use local_common::{MemberId, MemberModel, WebsiteModel};
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+use webby_api::WrappingResponse;
+
+use crate::{Error, Result};
+
+use super::CLIENT;
+
+#[async_trait]
+pub trait IdentityProvider: Send + Sync {
+    /// Returns the member the access token belongs to.
+    async fn find_member_by_token(&self, token: &str) -> Result<Option<MemberIdentity>>;
+
+    /// The full member, including their billing details.
+    async fn find_member(&self, member: &MemberIdentity) -> Result<Option<MemberModel>>;
+
+    /// Returns the website if the member owns it.
+    async fn find_owned_website(
+        &self,
+        member: &MemberIdentity,
+        website: Uuid,
+    ) -> Result<Option<WebsiteModel>>;
+}
+
+pub type SharedIdentityProvider = Arc<dyn IdentityProvider>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberIdentity {
+    pub pk: MemberId,
+    pub uuid: Uuid,
+    pub token: String,
+}
+
+/// Asks the main program who the token belongs to.
+pub struct WebbyIdentityProvider {
+    url: String,
+}
+
+impl WebbyIdentityProvider {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("WEBBY_API_URL")
+                .unwrap_or_else(|_| String::from("http://127.0.0.1:5940/api")),
+        )
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for WebbyIdentityProvider {
+    async fn find_member_by_token(&self, token: &str) -> Result<Option<MemberIdentity>> {
+        let resp = CLIENT
+            .get(format!("{}/member", self.url))
+            .bearer_auth(token)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            return Ok(None);
+        }
+
+        match resp.json::<WrappingResponse<MemberModel>>().await? {
+            WrappingResponse::Resp(member) => Ok(Some(MemberIdentity {
+                pk: member.pk,
+                uuid: member.id,
+                token: token.to_string(),
+            })),
+            WrappingResponse::Error(_) => Ok(None),
+        }
+    }
+
+    async fn find_member(&self, member: &MemberIdentity) -> Result<Option<MemberModel>> {
+        let resp = CLIENT
+            .get(format!("{}/member", self.url))
+            .bearer_auth(&member.token)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            return Ok(None);
+        }
+
+        match resp.json::<WrappingResponse<MemberModel>>().await? {
+            WrappingResponse::Resp(found) if found.id == member.uuid => Ok(Some(found)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn find_owned_website(
+        &self,
+        member: &MemberIdentity,
+        website: Uuid,
+    ) -> Result<Option<WebsiteModel>> {
+        let resp = CLIENT
+            .get(format!("{}/website/{website}", self.url))
+            .bearer_auth(&member.token)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            return Ok(None);
+        }
+
+        match resp.json::<WrappingResponse<WebsiteModel>>().await? {
+            WrappingResponse::Resp(found) if found.id == website && found.owner_id == member.pk => {
+                Ok(Some(found))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The member calling the route. Rejects the request if it isn't authenticated.
+pub struct AuthMember {
+    identity: MemberIdentity,
+    provider: SharedIdentityProvider,
+}
+
+impl AuthMember {
+    pub fn pk(&self) -> MemberId {
+        self.identity.pk
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.identity.uuid
+    }
+
+    /// Asks the main program for the member. Billing details always come from here, never the request.
+    pub async fn find_member(&self) -> Result<MemberModel> {
+        Ok(self
+            .provider
+            .find_member(&self.identity)
+            .await?
+            .context("Member not found")?)
+    }
+
+    /// Errors unless the member's role on the addon has the capability.
+    pub async fn addon_access_error(
+        &self,
+        addon: &AddonModel,
+        capability: AddonCapability,
+        db: &mut SqliteConnection,
+    ) -> Result<AddonRole> {
+        match AddonCollaboratorModel::find_role(addon, self.identity.uuid, db).await? {
+            Some(role) if role.can(capability) => Ok(role),
+            _ => Err(Error::Forbidden),
+        }
+    }
+
+    /// Finds the addon, erroring unless the member's role on it has the capability.
+    pub async fn find_owned_addon(
+        &self,
+        addon_id: Uuid,
+        capability: AddonCapability,
+        db: &mut SqliteConnection,
+    ) -> Result<AddonModel> {
+        let addon = AddonModel::find_one_by_guid(addon_id, db)
+            .await?
+            .context("Addon not found")?;
+
+        self.addon_access_error(&addon, capability, db).await?;
+
+        Ok(addon)
+    }
+
+    pub async fn website_access_error(&self, website: Uuid) -> Result<()> {
+        self.find_owned_website(website).await.map(|_| ())
+    }
+
+    /// Asks the main program for the website, erroring unless the member owns it.
+    pub async fn find_owned_website(&self, website: Uuid) -> Result<WebsiteModel> {
+        self.provider
+            .find_owned_website(&self.identity, website)
+            .await?
+            .ok_or(Error::Forbidden)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthMember
+where
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Ok(Extension(provider)) =
+            Extension::<SharedIdentityProvider>::from_request_parts(parts, state).await
+        else {
+            error!("Identity extension missing. Is the auth layer installed?");
+            return Err(Error::Unauthorized);
+        };
+
+        let Ok(TypedHeader(Authorization(bearer))) =
+            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await
+        else {
+            return Err(Error::Unauthorized);
+        };
+
+        let Some(identity) = provider.find_member_by_token(bearer.token()).await? else {
+            return Err(Error::Unauthorized);
+        };
+
+        Ok(Self { identity, provider })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        Router,
+    };
+    use database::{AddonInstanceModel, NewAddonCollaboratorModel, NewAddonInstanceModel};
+    use local_common::WebsiteId;
+    use sqlx::SqlitePool;
+    use time::OffsetDateTime;
+    use tower::ServiceExt;
+
+    use super::{super::tests::test_addon, *};
+
+    const OWNER_TOKEN: &str = "owner-token";
+    const OTHER_TOKEN: &str = "other-token";
+
+    /// Identity provider which knows a fixed set of tokens and website owners.
+    #[derive(Default)]
+    struct FakeIdentityProvider {
+        members: HashMap<String, MemberIdentity>,
+        websites: HashSet<(Uuid, Uuid)>,
+    }
+
+    impl FakeIdentityProvider {
+        fn with_member(mut self, token: &str, pk: i32) -> (Self, Uuid) {
+            let uuid = Uuid::new_v4();
+
+            self.members.insert(
+                token.to_string(),
+                MemberIdentity {
+                    pk: MemberId::from(pk),
+                    uuid,
+                    token: token.to_string(),
+                },
+            );
+
+            (self, uuid)
+        }
+
+        fn with_website(mut self, member: Uuid, website: Uuid) -> Self {
+            self.websites.insert((member, website));
+            self
+        }
+    }
+
+    #[async_trait]
+    impl IdentityProvider for FakeIdentityProvider {
+        async fn find_member_by_token(&self, token: &str) -> Result<Option<MemberIdentity>> {
+            Ok(self.members.get(token).cloned())
+        }
+
+        async fn find_member(&self, member: &MemberIdentity) -> Result<Option<MemberModel>> {
+            let now = OffsetDateTime::now_utc();
+
+            Ok(Some(MemberModel {
+                pk: member.pk,
+                id: member.uuid,
+                role: 0,
+                tag: String::new(),
+                display_name: String::new(),
+                email: String::new(),
+                password: String::new(),
+                first_name: String::new(),
+                last_name: String::new(),
+                stripe_customer_id: None,
+                created_at: now,
+                updated_at: now,
+            }))
+        }
+
+        async fn find_owned_website(
+            &self,
+            member: &MemberIdentity,
+            website: Uuid,
+        ) -> Result<Option<WebsiteModel>> {
+            let now = OffsetDateTime::now_utc();
+
+            Ok(self
+                .websites
+                .contains(&(member.uuid, website))
+                .then(|| WebsiteModel {
+                    pk: WebsiteId::from(1),
+                    id: website,
+                    owner_id: member.pk,
+                    name: String::from("Test"),
+                    url: None,
+                    theme_id: 0,
+                    created_at: now,
+                    updated_at: now,
+                }))
+        }
+    }
+
+    struct Harness {
+        app: Router,
+        pool: SqlitePool,
+        addon: Uuid,
+        website: Uuid,
+        other: Uuid,
+    }
+
+    async fn setup() -> Harness {
+        let (provider, owner) = FakeIdentityProvider::default().with_member(OWNER_TOKEN, 1);
+        let (provider, other) = provider.with_member(OTHER_TOKEN, 2);
+
+        let website = Uuid::new_v4();
+        let provider = provider.with_website(owner, website);
+
+        let pool = database::init_memory().await.unwrap();
+        let mut acq = pool.acquire().await.unwrap();
+
+        let addon = test_addon(owner, "test").insert(&mut acq).await.unwrap();
+
+        NewAddonInstanceModel {
+            addon_id: addon.id,
+            website_id: WebsiteId::from(1),
+            website_uuid: website,
+            version: String::from("latest"),
+        }
+        .insert(&mut acq)
+        .await
+        .unwrap();
+
+        let provider: SharedIdentityProvider = Arc::new(provider);
+
+        Harness {
+            app: super::super::routes()
+                .layer(Extension(provider))
+                .with_state(pool.clone()),
+            pool,
+            addon: addon.guid,
+            website,
+            other,
+        }
+    }
+
+    async fn send(app: &Router, method: Method, uri: String, token: Option<&str>) -> StatusCode {
+        let mut req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json");
+
+        if let Some(token) = token {
+            req = req.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+
+        let body = Body::from(r#"{"item":"none"}"#);
+
+        app.clone()
+            .oneshot(req.body(body).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn addon_edit_requires_owner() {
+        let Harness { app, addon, .. } = setup().await;
+        let uri = format!("/addon/{addon}/item");
+
+        assert_eq!(
+            send(&app, Method::POST, uri.clone(), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&app, Method::POST, uri.clone(), Some("unknown")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&app, Method::POST, uri.clone(), Some(OTHER_TOKEN)).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(&app, Method::POST, uri, Some(OWNER_TOKEN)).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn addon_edit_follows_collaborator_role() {
+        let Harness {
+            app,
+            pool,
+            addon,
+            other,
+            ..
+        } = setup().await;
+        let uri = format!("/addon/{addon}/item");
+
+        let mut acq = pool.acquire().await.unwrap();
+        let model = AddonModel::find_one_by_guid(addon, &mut acq)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut collaborator = NewAddonCollaboratorModel {
+            addon_id: model.id,
+            member_uuid: other,
+            role: AddonRole::Designer,
+            invited_by: model.member_uuid,
+        }
+        .insert(&mut acq)
+        .await
+        .unwrap();
+
+        // Not accepted yet.
+        assert_eq!(
+            send(&app, Method::POST, uri.clone(), Some(OTHER_TOKEN)).await,
+            StatusCode::FORBIDDEN
+        );
+
+        collaborator.accepted_at = Some(OffsetDateTime::now_utc());
+        collaborator.update(&mut acq).await.unwrap();
+
+        assert_eq!(
+            send(&app, Method::POST, uri.clone(), Some(OTHER_TOKEN)).await,
+            StatusCode::OK
+        );
+
+        collaborator.role = AddonRole::Viewer;
+        collaborator.update(&mut acq).await.unwrap();
+
+        assert_eq!(
+            send(&app, Method::POST, uri, Some(OTHER_TOKEN)).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn instance_requires_website_owner() {
+        let Harness {
+            app,
+            addon,
+            website,
+            ..
+        } = setup().await;
+        let uri = format!("/addon/{addon}/instance/{website}");
+
+        assert_eq!(
+            send(&app, Method::GET, uri.clone(), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&app, Method::GET, uri.clone(), Some(OTHER_TOKEN)).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(&app, Method::GET, uri, Some(OWNER_TOKEN)).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_rejected_while_installed() {
+        let Harness {
+            app,
+            pool,
+            addon,
+            website,
+            ..
+        } = setup().await;
+        let uri = format!("/addon/{addon}");
+
+        assert_eq!(
+            send(&app, Method::DELETE, uri.clone(), Some(OWNER_TOKEN)).await,
+            StatusCode::CONFLICT
+        );
+
+        let mut acq = pool.acquire().await.unwrap();
+        let model = AddonModel::find_one_by_guid(addon, &mut acq)
+            .await
+            .unwrap()
+            .unwrap();
+
+        AddonInstanceModel::find_by_addon_website_id(model.id, website, &mut acq)
+            .await
+            .unwrap()
+            .unwrap()
+            .soft_delete(None, &mut acq)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            send(&app, Method::DELETE, uri, Some(OWNER_TOKEN)).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn addon_data_requires_collaborator() {
+        let Harness {
+            app,
+            addon,
+            website,
+            ..
+        } = setup().await;
+
+        for uri in [
+            format!("/addon/{addon}/schemas"),
+            format!("/addon/{addon}/template/data"),
+        ] {
+            assert_eq!(
+                send(&app, Method::GET, uri.clone(), None).await,
+                StatusCode::UNAUTHORIZED
+            );
+            assert_eq!(
+                send(&app, Method::GET, uri.clone(), Some(OTHER_TOKEN)).await,
+                StatusCode::FORBIDDEN
+            );
+            assert_eq!(
+                send(&app, Method::GET, uri, Some(OWNER_TOKEN)).await,
+                StatusCode::OK
+            );
+        }
+
+        let uri = format!("/dashboard-pages/{website}");
+
+        assert_eq!(
+            send(&app, Method::GET, uri.clone(), Some(OTHER_TOKEN)).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(&app, Method::GET, uri, Some(OWNER_TOKEN)).await,
+            StatusCode::OK
+        );
+    }
+}
@@ -0,0 +1,769 @@
+//! Automations which ship with an addon.
+//!
+//! Automations are authored as drafts and copied into the compiled version when the addon is
+//! published. CMS row and schedule triggers run against the addons' current version, install
+//! triggers against the version which was installed.
+
+use std::{cmp::Ordering, collections::HashMap, time::Duration};
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use database::{
+    AddonAutomationModel, AddonCapability, AddonCompiledModel, AddonModel, AutomationAction,
+    AutomationTrigger, CmsRowEvent, NewAddonAutomationModel, SchemaDataFieldUpdate,
+    SchemaDataModel, SchemaModel, WebhookEvent, MIN_SCHEDULE_INTERVAL_MINUTES,
+};
+use eyre::ContextCompat;
+use local_common::AddonId;
+use serde::Deserialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+use webby_global_common::{
+    filter::{Filter, FilterConditionType, FilterValue},
+    schema::SchematicFieldKey,
+    value::SimpleValue,
+};
+
+use crate::Result;
+
+use super::{auth::AuthMember, map_to_field_value, webhook::queue_webhook_event, CLIENT};
+
+const ACTION_TIMEOUT: Duration = Duration::from_secs(10);
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route("/", get(get_automation_list).post(create_automation))
+        .route(
+            "/:automation",
+            get(get_automation)
+                .post(update_automation)
+                .delete(delete_automation),
+        )
+}
+
+pub enum AutomationEvent {
+    CmsRow {
+        addon_id: AddonId,
+        collection: String,
+        event: CmsRowEvent,
+        row_ids: Vec<Uuid>,
+    },
+
+    InstanceInstalled {
+        addon_id: AddonId,
+        instance_id: Uuid,
+        website_id: Uuid,
+        version: String,
+    },
+}
+
+/// Runs the automations listening for the event in the background.
+pub fn spawn_automation_event(db: SqlitePool, event: AutomationEvent) {
+    tokio::spawn(async move {
+        if let Err(e) = run_automation_event(&db, event).await {
+            error!("Automation Error: {e}");
+        }
+    });
+}
+
+/// Runs scheduled automations in the background.
+pub fn spawn_automation_scheduler(pool: SqlitePool) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = process_due_automations(&pool).await {
+                error!("Automation Scheduler Error: {e}");
+            }
+
+            tokio::time::sleep(SCHEDULER_INTERVAL).await;
+        }
+    });
+}
+
+/// Runs every scheduled automation which is due. Returns how many were ran.
+///
+/// No connection is held while actions call out so slow Action URLs don't starve the pool.
+pub async fn process_due_automations(db: &SqlitePool) -> Result<usize> {
+    let due = AddonAutomationModel::find_due_scheduled(50, &mut *db.acquire().await?).await?;
+    let count = due.len();
+
+    for mut automation in due {
+        let now = OffsetDateTime::now_utc();
+
+        let payload = serde_json::json!({
+            "scheduledAt": automation.next_run_at,
+        });
+
+        // Advanced first so a failing automation isn't picked up again on every tick.
+        automation.last_run_at = Some(now);
+        automation.next_run_at = automation.triggered_by.next_run_at(now);
+
+        let addon = {
+            let mut acq = db.acquire().await?;
+
+            automation.update(&mut acq).await?;
+
+            AddonModel::find_one_by_id(automation.addon_id, &mut acq).await
+        };
+
+        match addon {
+            Ok(Some(addon)) => run_actions(&addon, &automation, None, payload, db).await,
+            Ok(None) => (),
+            Err(e) => error!("Automation {}: {e}", automation.public_id),
+        }
+    }
+
+    Ok(count)
+}
+
+pub async fn run_automation_event(db: &SqlitePool, event: AutomationEvent) -> Result<()> {
+    match event {
+        AutomationEvent::CmsRow {
+            addon_id,
+            collection,
+            event,
+            row_ids,
+        } => {
+            let mut acq = db.acquire().await?;
+
+            let addon = AddonModel::find_one_by_id(addon_id, &mut acq)
+                .await?
+                .context("Addon not found")?;
+
+            let automations = find_active_automations(&addon, &addon.version, &mut acq)
+                .await?
+                .into_iter()
+                .filter(|auto| match &auto.triggered_by.0 {
+                    AutomationTrigger::CmsRow {
+                        collection: name,
+                        events,
+                    } => *name == collection && events.contains(&event),
+                    _ => false,
+                })
+                .collect::<Vec<_>>();
+
+            if automations.is_empty() {
+                return Ok(());
+            }
+
+            let schema = SchemaModel::find_one_by_public_id(addon.id, &collection, &mut acq)
+                .await?
+                .context("Schema not found")?;
+
+            drop(acq);
+
+            for row_id in row_ids {
+                let row =
+                    SchemaDataModel::find_by_public_id(row_id, &mut *db.acquire().await?).await?;
+
+                let fields = match row {
+                    Some(row) => map_to_field_value(&schema, row, None)?,
+                    None => HashMap::new(),
+                };
+
+                let payload = serde_json::json!({
+                    "collection": collection,
+                    "event": event,
+                    "rowId": row_id,
+                });
+
+                for automation in &automations {
+                    if conditions_match(&automation.conditions, &fields) {
+                        run_actions(
+                            &addon,
+                            automation,
+                            Some((&schema, row_id)),
+                            payload.clone(),
+                            db,
+                        )
+                        .await;
+                    }
+                }
+            }
+        }
+
+        AutomationEvent::InstanceInstalled {
+            addon_id,
+            instance_id,
+            website_id,
+            version,
+        } => {
+            let mut acq = db.acquire().await?;
+
+            let addon = AddonModel::find_one_by_id(addon_id, &mut acq)
+                .await?
+                .context("Addon not found")?;
+
+            // TODO: Remove once instances are no longer created with the "latest" version.
+            let version = if version == "latest" {
+                addon.version.clone()
+            } else {
+                version
+            };
+
+            let payload = serde_json::json!({
+                "instanceId": instance_id,
+                "websiteId": website_id,
+                "version": version,
+            });
+
+            let automations = find_active_automations(&addon, &version, &mut acq).await?;
+
+            drop(acq);
+
+            for automation in automations {
+                if automation.triggered_by.0 == AutomationTrigger::InstanceInstalled {
+                    run_actions(&addon, &automation, None, payload.clone(), db).await;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn find_active_automations(
+    addon: &AddonModel,
+    version: &str,
+    db: &mut SqliteConnection,
+) -> Result<Vec<AddonAutomationModel>> {
+    let Some(compiled) =
+        AddonCompiledModel::find_one_by_addon_uuid_and_version(addon.id, version, db).await?
+    else {
+        return Ok(Vec::new());
+    };
+
+    Ok(AddonAutomationModel::find_by_compiled_id(compiled.pk, db)
+        .await?
+        .into_iter()
+        .filter(|v| v.is_enabled)
+        .collect())
+}
+
+/// Failing actions are logged and don't stop the remaining actions from running.
+async fn run_actions(
+    addon: &AddonModel,
+    automation: &AddonAutomationModel,
+    row: Option<(&SchemaModel, Uuid)>,
+    payload: serde_json::Value,
+    db: &SqlitePool,
+) {
+    let body = serde_json::json!({
+        "automationId": automation.public_id,
+        "name": automation.name,
+        "data": payload,
+    });
+
+    for action in automation.actions.iter() {
+        if let Err(e) = run_action(addon, automation, action, row, &body, db).await {
+            error!("Automation {} action failed: {e}", automation.public_id);
+        }
+    }
+}
+
+async fn run_action(
+    addon: &AddonModel,
+    automation: &AddonAutomationModel,
+    action: &AutomationAction,
+    row: Option<(&SchemaModel, Uuid)>,
+    body: &serde_json::Value,
+    db: &SqlitePool,
+) -> Result<()> {
+    match action {
+        AutomationAction::CallActionUrl { path } => {
+            let Some(url) = addon.action_url.as_deref() else {
+                warn!(
+                    "Automation {} has no Action URL to call",
+                    automation.public_id
+                );
+                return Ok(());
+            };
+
+            let path = path.as_deref().unwrap_or("automation");
+
+            let resp = CLIENT
+                .post(format!("{url}/{}", path.trim_start_matches('/')))
+                .timeout(ACTION_TIMEOUT)
+                .json(body)
+                .send()
+                .await?;
+
+            if !resp.status().is_success() {
+                warn!(
+                    "Automation {} Action URL responded with {}",
+                    automation.public_id,
+                    resp.status()
+                );
+            }
+        }
+
+        // Doesn't emit any events. Otherwise an automation could trigger itself.
+        AutomationAction::UpdateRow { field, value } => {
+            let Some((schema, row_id)) = row else {
+                return Ok(());
+            };
+
+            let schema_field = schema
+                .fields
+                .get(&SchematicFieldKey::Other(field.clone()))
+                .with_context(|| format!("Field {field} not found"))?;
+
+            let mut acq = db.acquire().await?;
+
+            let Some(schema_data) = SchemaDataFieldUpdate::find_data_field_by_uuid(
+                row_id,
+                schema_field.field_type,
+                &mut acq,
+            )
+            .await?
+            else {
+                return Ok(());
+            };
+
+            schema_data
+                .update(
+                    field.clone(),
+                    value
+                        .clone()
+                        .map(|v| schema_field.field_type.parse_value(v))
+                        .transpose()?,
+                    &mut acq,
+                )
+                .await?;
+        }
+
+        AutomationAction::SendWebhook => {
+            queue_webhook_event(
+                addon.id,
+                WebhookEvent::AutomationTriggered,
+                body.clone(),
+                &mut *db.acquire().await?,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn conditions_match(
+    conditions: &[Filter],
+    fields: &HashMap<SchematicFieldKey, SimpleValue>,
+) -> bool {
+    conditions.iter().all(|filter| {
+        let value = fields
+            .iter()
+            .find(|(key, _)| key.as_str() == filter.name)
+            .map(|(_, value)| value);
+
+        condition_matches(filter, value)
+    })
+}
+
+/// Mirrors how [`SchemaDataModel::find_by`] compares the filter in SQL.
+fn condition_matches(filter: &Filter, value: Option<&SimpleValue>) -> bool {
+    let Some(value) = value else {
+        return matches!(
+            filter.cond,
+            FilterConditionType::Neq | FilterConditionType::Dnc
+        );
+    };
+
+    let row_number = match value {
+        SimpleValue::Number(v) => Some(v.convert_f64()),
+        _ => None,
+    };
+
+    let row_text = match value {
+        SimpleValue::Text(v) => v.clone(),
+        SimpleValue::Number(v) => v.convert_f64().to_string(),
+        SimpleValue::Boolean(v) => v.to_string(),
+        v => serde_json::to_string(v).unwrap_or_default(),
+    };
+
+    let filter_text = filter.value.to_string();
+
+    match &filter.cond {
+        FilterConditionType::Cont => row_text
+            .to_lowercase()
+            .contains(&filter_text.to_lowercase()),
+        FilterConditionType::Dnc => !row_text
+            .to_lowercase()
+            .contains(&filter_text.to_lowercase()),
+
+        FilterConditionType::Between => match (&filter.value, row_number) {
+            (FilterValue::Range((min, max)), Some(v)) => {
+                v >= min.convert_f64() && v <= max.convert_f64()
+            }
+            _ => false,
+        },
+
+        cond => {
+            let filter_number = match &filter.value {
+                FilterValue::Number(v) => Some(v.convert_f64()),
+                FilterValue::Text(v) => v.parse::<f64>().ok(),
+                _ => None,
+            };
+
+            let ordering = match (row_number, filter_number) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => Some(row_text.cmp(&filter_text)),
+            };
+
+            let Some(ordering) = ordering else {
+                return false;
+            };
+
+            match cond {
+                FilterConditionType::Eq => ordering == Ordering::Equal,
+                FilterConditionType::Neq => ordering != Ordering::Equal,
+                FilterConditionType::Gte => ordering != Ordering::Less,
+                FilterConditionType::Gt => ordering == Ordering::Greater,
+                FilterConditionType::Lte => ordering != Ordering::Greater,
+                FilterConditionType::Lt => ordering == Ordering::Less,
+                _ => false,
+            }
+        }
+    }
+}
+
+async fn validate_automation(
+    addon_id: AddonId,
+    trigger: &AutomationTrigger,
+    actions: &[AutomationAction],
+    db: &mut SqliteConnection,
+) -> Result<()> {
+    let schema = match trigger {
+        AutomationTrigger::CmsRow { collection, events } => {
+            if events.is_empty() {
+                return Err(eyre::eyre!("Missing CMS Row Events"))?;
+            }
+
+            Some(
+                SchemaModel::find_one_by_public_id(addon_id, collection, db)
+                    .await?
+                    .context("Schema not found")?,
+            )
+        }
+
+        &AutomationTrigger::Schedule { interval_minutes } => {
+            if interval_minutes < MIN_SCHEDULE_INTERVAL_MINUTES {
+                return Err(eyre::eyre!(
+                    "Schedule interval must be at least {MIN_SCHEDULE_INTERVAL_MINUTES} minutes"
+                ))?;
+            }
+
+            None
+        }
+
+        AutomationTrigger::InstanceInstalled => None,
+    };
+
+    for action in actions {
+        if let AutomationAction::UpdateRow { field, .. } = action {
+            let Some(schema) = schema.as_ref() else {
+                return Err(eyre::eyre!("Update Row requires a CMS Row trigger"))?;
+            };
+
+            if !schema
+                .fields
+                .contains_key(&SchematicFieldKey::Other(field.clone()))
+            {
+                return Err(eyre::eyre!("Schema Field not found"))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct AutomationListQuery {
+    /// Returns the automations of the compiled version instead of the drafts.
+    pub version: Option<String>,
+}
+
+async fn get_automation_list(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Query(AutomationListQuery { version }): Query<AutomationListQuery>,
+) -> Result<JsonResponse<Vec<AddonAutomationModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    let items = if let Some(version) = version {
+        let compiled =
+            AddonCompiledModel::find_one_by_addon_uuid_and_version(addon.id, &version, &mut acq)
+                .await?
+                .context("Version not found")?;
+
+        AddonAutomationModel::find_by_compiled_id(compiled.pk, &mut acq).await?
+    } else {
+        AddonAutomationModel::find_drafts_by_addon_id(addon.id, &mut acq).await?
+    };
+
+    Ok(Json(WrappingResponse::okay(items)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationJson {
+    pub name: String,
+    pub trigger: AutomationTrigger,
+    #[serde(default)]
+    pub conditions: Vec<Filter>,
+    pub actions: Vec<AutomationAction>,
+    pub is_enabled: Option<bool>,
+}
+
+async fn create_automation(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(AutomationJson {
+        name,
+        trigger,
+        conditions,
+        actions,
+        is_enabled,
+    }): Json<AutomationJson>,
+) -> Result<JsonResponse<AddonAutomationModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    validate_automation(addon.id, &trigger, &actions, &mut acq).await?;
+
+    let automation = NewAddonAutomationModel {
+        addon_id: addon.id,
+        compiled_id: None,
+        name,
+        triggered_by: trigger,
+        conditions,
+        actions,
+        is_enabled: is_enabled.unwrap_or(true),
+    }
+    .insert(&mut acq)
+    .await?;
+
+    Ok(Json(WrappingResponse::okay(automation)))
+}
+
+async fn get_automation(
+    Path((addon_id, automation_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<AddonAutomationModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    let automation =
+        AddonAutomationModel::find_one_draft_by_public_id(addon.id, automation_id, &mut acq)
+            .await?
+            .context("Automation not found")?;
+
+    Ok(Json(WrappingResponse::okay(automation)))
+}
+
+async fn update_automation(
+    Path((addon_id, automation_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(AutomationJson {
+        name,
+        trigger,
+        conditions,
+        actions,
+        is_enabled,
+    }): Json<AutomationJson>,
+) -> Result<JsonResponse<AddonAutomationModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    let mut automation =
+        AddonAutomationModel::find_one_draft_by_public_id(addon.id, automation_id, &mut acq)
+            .await?
+            .context("Automation not found")?;
+
+    validate_automation(addon.id, &trigger, &actions, &mut acq).await?;
+
+    automation.name = name;
+    automation.triggered_by.0 = trigger;
+    automation.conditions.0 = conditions;
+    automation.actions.0 = actions;
+
+    if let Some(is_enabled) = is_enabled {
+        automation.is_enabled = is_enabled;
+    }
+
+    automation.update(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(automation)))
+}
+
+async fn delete_automation(
+    Path((addon_id, automation_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    let automation =
+        AddonAutomationModel::find_one_draft_by_public_id(addon.id, automation_id, &mut acq)
+            .await?
+            .context("Automation not found")?;
+
+    automation.delete(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+#[cfg(test)]
+mod tests {
+    use webby_global_common::value::Number;
+
+    use super::*;
+
+    fn filter(name: &str, cond: FilterConditionType, value: FilterValue) -> Filter {
+        Filter {
+            name: name.to_string(),
+            cond,
+            value,
+        }
+    }
+
+    fn fields() -> HashMap<SchematicFieldKey, SimpleValue> {
+        HashMap::from([
+            (
+                SchematicFieldKey::Other(String::from("title")),
+                SimpleValue::Text(String::from("Hello World")),
+            ),
+            (
+                SchematicFieldKey::Other(String::from("count")),
+                SimpleValue::Number(Number::Integer(5)),
+            ),
+        ])
+    }
+
+    #[test]
+    fn compares_text() {
+        let title = fields()
+            .remove(&SchematicFieldKey::Other(String::from("title")))
+            .unwrap();
+
+        let text = |v: &str| FilterValue::Text(v.to_string());
+
+        assert!(condition_matches(
+            &filter("title", FilterConditionType::Eq, text("Hello World")),
+            Some(&title)
+        ));
+        assert!(condition_matches(
+            &filter("title", FilterConditionType::Cont, text("world")),
+            Some(&title)
+        ));
+        assert!(!condition_matches(
+            &filter("title", FilterConditionType::Dnc, text("hello")),
+            Some(&title)
+        ));
+        assert!(condition_matches(
+            &filter("title", FilterConditionType::Neq, text("Other")),
+            Some(&title)
+        ));
+    }
+
+    #[test]
+    fn compares_numbers() {
+        let count = SimpleValue::Number(Number::Integer(5));
+
+        let number = |v| FilterValue::Number(Number::Integer(v));
+
+        assert!(condition_matches(
+            &filter("count", FilterConditionType::Gt, number(4)),
+            Some(&count)
+        ));
+        assert!(condition_matches(
+            &filter("count", FilterConditionType::Lte, number(5)),
+            Some(&count)
+        ));
+        assert!(!condition_matches(
+            &filter("count", FilterConditionType::Lt, number(5)),
+            Some(&count)
+        ));
+        // Compared as a number, not as text.
+        assert!(condition_matches(
+            &filter(
+                "count",
+                FilterConditionType::Lt,
+                FilterValue::Text(String::from("10"))
+            ),
+            Some(&count)
+        ));
+        assert!(condition_matches(
+            &filter(
+                "count",
+                FilterConditionType::Between,
+                FilterValue::Range((Number::Integer(1), Number::Integer(5)))
+            ),
+            Some(&count)
+        ));
+    }
+
+    #[test]
+    fn missing_values_only_match_negations() {
+        let text = FilterValue::Text(String::from("a"));
+
+        assert!(condition_matches(
+            &filter("other", FilterConditionType::Neq, text.clone()),
+            None
+        ));
+        assert!(condition_matches(
+            &filter("other", FilterConditionType::Dnc, text.clone()),
+            None
+        ));
+        assert!(!condition_matches(
+            &filter("other", FilterConditionType::Eq, text),
+            None
+        ));
+    }
+
+    #[test]
+    fn every_condition_has_to_match() {
+        let fields = fields();
+
+        let title = filter(
+            "title",
+            FilterConditionType::Cont,
+            FilterValue::Text(String::from("hello")),
+        );
+        let count = filter(
+            "count",
+            FilterConditionType::Gte,
+            FilterValue::Number(Number::Integer(6)),
+        );
+
+        assert!(conditions_match(&[], &fields));
+        assert!(conditions_match(std::slice::from_ref(&title), &fields));
+        assert!(!conditions_match(&[title, count], &fields));
+    }
+}
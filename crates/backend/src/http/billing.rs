@@ -0,0 +1,879 @@
+//! Pricing plans of an addon and the subscription of every instance.
+//!
+//! Free plans are handled locally. Paid plans are created through the [`BillingProvider`] using
+//! the `stripe_customer_id` of the member installing the addon.
+
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{Path, State},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use database::{
+    AddonCapability, AddonInstanceModel, AddonModel, AddonPricingPlanModel, AddonSubscriptionModel,
+    NewAddonPricingPlanModel, NewAddonSubscriptionModel, PricingType, SubscriptionStatus,
+};
+use eyre::ContextCompat;
+use local_common::AddonId;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+use webby_global_common::id::AddonInstanceUuid;
+
+use crate::Result;
+
+use super::{auth::AuthMember, CLIENT};
+
+const SYNC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+#[async_trait]
+pub trait BillingProvider: Send + Sync {
+    async fn create_subscription(
+        &self,
+        value: NewProviderSubscription<'_>,
+    ) -> Result<ProviderSubscription>;
+
+    async fn cancel_subscription(&self, id: &str) -> Result<ProviderSubscription>;
+
+    async fn find_subscription(&self, id: &str) -> Result<Option<ProviderSubscription>>;
+}
+
+pub type SharedBillingProvider = Arc<dyn BillingProvider>;
+
+pub struct NewProviderSubscription<'a> {
+    pub customer_id: &'a str,
+    pub plan: &'a AddonPricingPlanModel,
+    pub seats: i32,
+    pub instance_id: AddonInstanceUuid,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderSubscription {
+    pub id: String,
+    pub status: SubscriptionStatus,
+    pub trial_ends_at: Option<OffsetDateTime>,
+    pub current_period_end: Option<OffsetDateTime>,
+}
+
+/// Bills through Stripe. Recurring plans are Stripe subscriptions, one-time plans are invoices.
+pub struct StripeBillingProvider {
+    secret_key: Option<String>,
+}
+
+impl StripeBillingProvider {
+    const URL: &'static str = "https://api.stripe.com/v1";
+
+    pub fn from_env() -> Self {
+        let secret_key = std::env::var("STRIPE_SECRET_KEY").ok();
+
+        if secret_key.is_none() {
+            warn!("STRIPE_SECRET_KEY is not set. Paid addon plans can't be installed.");
+        }
+
+        Self { secret_key }
+    }
+
+    fn secret_key(&self) -> Result<&str> {
+        Ok(self
+            .secret_key
+            .as_deref()
+            .context("Billing isn't configured")?)
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<Option<StripeObject>> {
+        let resp = req.basic_auth(self.secret_key()?, Some("")).send().await?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !resp.status().is_success() {
+            return Err(eyre::eyre!("Stripe Error: {}", resp.text().await?))?;
+        }
+
+        Ok(Some(resp.json().await?))
+    }
+}
+
+#[derive(Deserialize)]
+struct StripeObject {
+    id: String,
+    object: String,
+    status: Option<String>,
+    trial_end: Option<i64>,
+    current_period_end: Option<i64>,
+}
+
+impl From<StripeObject> for ProviderSubscription {
+    fn from(value: StripeObject) -> Self {
+        let status = match (value.object.as_str(), value.status.as_deref()) {
+            ("invoice", Some("paid")) => SubscriptionStatus::Active,
+            ("invoice", Some("void" | "uncollectible")) => SubscriptionStatus::Cancelled,
+            ("invoice", _) => SubscriptionStatus::PastDue,
+
+            (_, Some("trialing")) => SubscriptionStatus::Trialing,
+            (_, Some("active")) => SubscriptionStatus::Active,
+            (_, Some("canceled" | "incomplete_expired")) => SubscriptionStatus::Cancelled,
+            _ => SubscriptionStatus::PastDue,
+        };
+
+        Self {
+            id: value.id,
+            status,
+            trial_ends_at: value
+                .trial_end
+                .and_then(|v| OffsetDateTime::from_unix_timestamp(v).ok()),
+            current_period_end: value
+                .current_period_end
+                .and_then(|v| OffsetDateTime::from_unix_timestamp(v).ok()),
+        }
+    }
+}
+
+#[async_trait]
+impl BillingProvider for StripeBillingProvider {
+    async fn create_subscription(
+        &self,
+        value: NewProviderSubscription<'_>,
+    ) -> Result<ProviderSubscription> {
+        let price = value
+            .plan
+            .provider_price_id
+            .as_deref()
+            .context("Pricing Plan isn't linked to a Stripe Price")?;
+
+        let seats = value.seats.to_string();
+        let instance_id = value.instance_id.to_string();
+
+        let object = if value.plan.is_recurring() {
+            let trial_days = value.plan.trial_days.to_string();
+
+            let mut form = vec![
+                ("customer", value.customer_id),
+                ("items[0][price]", price),
+                ("items[0][quantity]", seats.as_str()),
+                ("metadata[instance_id]", instance_id.as_str()),
+            ];
+
+            if value.plan.trial_days > 0 {
+                form.push(("trial_period_days", trial_days.as_str()));
+            }
+
+            self.send(
+                CLIENT
+                    .post(format!("{}/subscriptions", Self::URL))
+                    .form(&form),
+            )
+            .await?
+        } else {
+            self.send(CLIENT.post(format!("{}/invoiceitems", Self::URL)).form(&[
+                ("customer", value.customer_id),
+                ("price", price),
+                ("quantity", seats.as_str()),
+            ]))
+            .await?;
+
+            self.send(CLIENT.post(format!("{}/invoices", Self::URL)).form(&[
+                ("customer", value.customer_id),
+                ("auto_advance", "true"),
+                ("pending_invoice_items_behavior", "include"),
+                ("metadata[instance_id]", instance_id.as_str()),
+            ]))
+            .await?
+        };
+
+        Ok(object.context("Stripe returned no object")?.into())
+    }
+
+    async fn cancel_subscription(&self, id: &str) -> Result<ProviderSubscription> {
+        let req = if id.starts_with("in_") {
+            CLIENT.post(format!("{}/invoices/{id}/void", Self::URL))
+        } else {
+            CLIENT.delete(format!("{}/subscriptions/{id}", Self::URL))
+        };
+
+        Ok(self
+            .send(req)
+            .await?
+            .context("Stripe Subscription not found")?
+            .into())
+    }
+
+    async fn find_subscription(&self, id: &str) -> Result<Option<ProviderSubscription>> {
+        let path = if id.starts_with("in_") {
+            "invoices"
+        } else {
+            "subscriptions"
+        };
+
+        Ok(self
+            .send(CLIENT.get(format!("{}/{path}/{id}", Self::URL)))
+            .await?
+            .map(|v| v.into()))
+    }
+}
+
+/// The plan the instance is installed with. None if the addon doesn't have any pricing plans.
+pub async fn find_install_plan(
+    addon_id: AddonId,
+    plan_id: Option<Uuid>,
+    customer_id: Option<&str>,
+    db: &mut SqliteConnection,
+) -> Result<Option<AddonPricingPlanModel>> {
+    let mut plans = AddonPricingPlanModel::find_active_by_addon_id(addon_id, db).await?;
+
+    if plans.is_empty() {
+        return Ok(None);
+    }
+
+    let plan = match plan_id {
+        Some(plan_id) => plans
+            .into_iter()
+            .find(|v| v.public_id == plan_id)
+            .context("Pricing Plan not found")?,
+
+        // Only choose for the member if it's the single free plan.
+        None if plans.len() == 1 && plans[0].is_free() => plans.remove(0),
+        None => return Err(eyre::eyre!("Missing Pricing Plan"))?,
+    };
+
+    if !plan.is_free() && customer_id.is_none() {
+        return Err(eyre::eyre!("Missing Billing Customer"))?;
+    }
+
+    Ok(Some(plan))
+}
+
+pub async fn subscribe_instance(
+    instance: &AddonInstanceModel,
+    plan: &AddonPricingPlanModel,
+    customer_id: Option<&str>,
+    seats: i32,
+    billing: &dyn BillingProvider,
+    db: &mut SqliteConnection,
+) -> Result<AddonSubscriptionModel> {
+    let seats = if plan.is_per_seat { seats.max(1) } else { 1 };
+
+    if plan.is_free() {
+        return Ok(NewAddonSubscriptionModel {
+            instance_id: instance.id,
+            plan_id: plan.id,
+            status: SubscriptionStatus::Active,
+            seats,
+            provider_subscription_id: None,
+            trial_ends_at: None,
+            current_period_end: None,
+        }
+        .insert(db)
+        .await?);
+    }
+
+    let value = billing
+        .create_subscription(NewProviderSubscription {
+            customer_id: customer_id.context("Missing Billing Customer")?,
+            plan,
+            seats,
+            instance_id: instance.public_id,
+        })
+        .await?;
+
+    Ok(NewAddonSubscriptionModel {
+        instance_id: instance.id,
+        plan_id: plan.id,
+        status: value.status,
+        seats,
+        provider_subscription_id: Some(value.id),
+        trial_ends_at: value.trial_ends_at,
+        current_period_end: value.current_period_end,
+    }
+    .insert(db)
+    .await?)
+}
+
+/// Syncs every subscription whose period ended so renewals are picked up.
+pub fn spawn_subscription_sync(pool: SqlitePool, billing: SharedBillingProvider) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = sync_lapsed_subscriptions(&pool, &*billing).await {
+                error!("Subscription Sync Error: {e}");
+            }
+
+            tokio::time::sleep(SYNC_INTERVAL).await;
+        }
+    });
+}
+
+/// Returns how many subscriptions were synced.
+pub async fn sync_lapsed_subscriptions(
+    db: &SqlitePool,
+    billing: &dyn BillingProvider,
+) -> Result<usize> {
+    let mut acq = db.acquire().await?;
+
+    let lapsed = AddonSubscriptionModel::find_lapsed(50, &mut acq).await?;
+    let count = lapsed.len();
+
+    // Released while waiting on the provider.
+    drop(acq);
+
+    for mut sub in lapsed {
+        let Some(provider_id) = sub.provider_subscription_id.as_deref() else {
+            continue;
+        };
+
+        let value = match billing.find_subscription(provider_id).await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Subscription {} failed to sync: {e}", *sub.id);
+                continue;
+            }
+        };
+
+        match value {
+            Some(value) => apply_provider_subscription(&mut sub, value),
+            None => {
+                sub.status = SubscriptionStatus::Cancelled;
+                sub.cancelled_at.get_or_insert_with(OffsetDateTime::now_utc);
+            }
+        }
+
+        sub.update(&mut *db.acquire().await?).await?;
+    }
+
+    Ok(count)
+}
+
+fn apply_provider_subscription(sub: &mut AddonSubscriptionModel, value: ProviderSubscription) {
+    if value.status == SubscriptionStatus::Cancelled && sub.cancelled_at.is_none() {
+        sub.cancelled_at = Some(OffsetDateTime::now_utc());
+    }
+
+    sub.status = value.status;
+    sub.trial_ends_at = value.trial_ends_at;
+    sub.current_period_end = value.current_period_end;
+}
+
+/// Refreshes the status from the billing provider.
+pub async fn sync_subscription(
+    sub: &mut AddonSubscriptionModel,
+    billing: &dyn BillingProvider,
+    db: &mut SqliteConnection,
+) -> Result<()> {
+    let Some(provider_id) = sub.provider_subscription_id.as_deref() else {
+        return Ok(());
+    };
+
+    match billing.find_subscription(provider_id).await? {
+        Some(value) => apply_provider_subscription(sub, value),
+        None => {
+            sub.status = SubscriptionStatus::Cancelled;
+            sub.cancelled_at.get_or_insert_with(OffsetDateTime::now_utc);
+        }
+    }
+
+    sub.update(db).await?;
+
+    Ok(())
+}
+
+pub async fn cancel_subscription(
+    sub: &mut AddonSubscriptionModel,
+    billing: &dyn BillingProvider,
+    db: &mut SqliteConnection,
+) -> Result<()> {
+    if let Some(provider_id) = sub.provider_subscription_id.as_deref() {
+        let value = billing.cancel_subscription(provider_id).await?;
+        apply_provider_subscription(sub, value);
+    }
+
+    sub.status = SubscriptionStatus::Cancelled;
+    sub.cancelled_at.get_or_insert_with(OffsetDateTime::now_utc);
+
+    sub.update(db).await?;
+
+    Ok(())
+}
+
+// Routes
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route("/", get(get_pricing_plans).post(create_pricing_plan))
+        .route("/manage", get(get_all_pricing_plans))
+        .route("/:plan", post(update_pricing_plan))
+}
+
+async fn get_pricing_plans(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+) -> Result<JsonResponse<Vec<AddonPricingPlanModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    Ok(Json(WrappingResponse::okay(
+        AddonPricingPlanModel::find_active_by_addon_id(addon.id, &mut acq).await?,
+    )))
+}
+
+async fn get_all_pricing_plans(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Vec<AddonPricingPlanModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        AddonPricingPlanModel::find_by_addon_id(addon.id, &mut acq).await?,
+    )))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingPlanJson {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_of: PricingType,
+    #[serde(default)]
+    pub price: i64,
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub is_per_seat: bool,
+    #[serde(default)]
+    pub trial_days: i32,
+    pub provider_price_id: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+fn validate_pricing_plan(value: &PricingPlanJson) -> Result<()> {
+    if value.price < 0 || value.trial_days < 0 {
+        return Err(eyre::eyre!("Price and trial days can't be negative"))?;
+    }
+
+    match value.type_of {
+        PricingType::Free if value.price != 0 => Err(eyre::eyre!("Free plans can't have a price"))?,
+        PricingType::Free => Ok(()),
+        _ if value.price == 0 => Err(eyre::eyre!("Paid plans require a price"))?,
+        _ => Ok(()),
+    }
+}
+
+async fn create_pricing_plan(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<PricingPlanJson>,
+) -> Result<JsonResponse<AddonPricingPlanModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    validate_pricing_plan(&value)?;
+
+    let mut plan = NewAddonPricingPlanModel {
+        addon_id: addon.id,
+        name: value.name,
+        type_of: value.type_of,
+        price: value.price,
+        currency: value.currency.unwrap_or_else(|| String::from("usd")),
+        is_per_seat: value.is_per_seat,
+        trial_days: value.trial_days,
+        provider_price_id: value.provider_price_id,
+    }
+    .insert(&mut acq)
+    .await?;
+
+    if value.is_active == Some(false) {
+        plan.is_active = false;
+        plan.update(&mut acq).await?;
+    }
+
+    Ok(Json(WrappingResponse::okay(plan)))
+}
+
+/// The type of plan can't be changed once created since instances are subscribed to it.
+async fn update_pricing_plan(
+    Path((addon_id, plan_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<PricingPlanJson>,
+) -> Result<JsonResponse<AddonPricingPlanModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    let mut plan = AddonPricingPlanModel::find_one_by_public_id(addon.id, plan_id, &mut acq)
+        .await?
+        .context("Pricing Plan not found")?;
+
+    if plan.type_of != value.type_of {
+        return Err(eyre::eyre!("Pricing Plan type can't be changed"))?;
+    }
+
+    validate_pricing_plan(&value)?;
+
+    plan.name = value.name;
+    plan.price = value.price;
+    plan.is_per_seat = value.is_per_seat;
+    plan.trial_days = value.trial_days;
+
+    if let Some(currency) = value.currency {
+        plan.currency = currency;
+    }
+
+    if let Some(provider_price_id) = value.provider_price_id {
+        plan.provider_price_id = Some(provider_price_id);
+    }
+
+    if let Some(is_active) = value.is_active {
+        plan.is_active = is_active;
+    }
+
+    plan.update(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(plan)))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceSubscriptionResponse {
+    pub plan: AddonPricingPlanModel,
+    pub subscription: AddonSubscriptionModel,
+    pub is_usable: bool,
+}
+
+async fn find_instance_subscription(
+    addon_id: Uuid,
+    website_id: Uuid,
+    member: &AuthMember,
+    db: &mut SqliteConnection,
+) -> Result<(AddonPricingPlanModel, AddonSubscriptionModel)> {
+    member.website_access_error(website_id).await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, db)
+        .await?
+        .context("Addon not found")?;
+
+    let instance = AddonInstanceModel::find_by_addon_website_id(addon.id, website_id, db)
+        .await?
+        .context("Addon Instance not found")?;
+
+    let subscription = instance
+        .find_subscription(db)
+        .await?
+        .context("Subscription not found")?;
+
+    let plan = AddonPricingPlanModel::find_one_by_id(subscription.plan_id, db)
+        .await?
+        .context("Pricing Plan not found")?;
+
+    Ok((plan, subscription))
+}
+
+pub async fn get_instance_subscription(
+    Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<InstanceSubscriptionResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let (plan, subscription) =
+        find_instance_subscription(addon_id, website_id, &member, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(InstanceSubscriptionResponse {
+        is_usable: subscription.is_usable(),
+        plan,
+        subscription,
+    })))
+}
+
+pub async fn sync_instance_subscription(
+    Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    Extension(billing): Extension<SharedBillingProvider>,
+    member: AuthMember,
+) -> Result<JsonResponse<InstanceSubscriptionResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let (plan, mut subscription) =
+        find_instance_subscription(addon_id, website_id, &member, &mut acq).await?;
+
+    sync_subscription(&mut subscription, &*billing, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(InstanceSubscriptionResponse {
+        is_usable: subscription.is_usable(),
+        plan,
+        subscription,
+    })))
+}
+
+pub async fn cancel_instance_subscription(
+    Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    Extension(billing): Extension<SharedBillingProvider>,
+    member: AuthMember,
+) -> Result<JsonResponse<InstanceSubscriptionResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let (plan, mut subscription) =
+        find_instance_subscription(addon_id, website_id, &member, &mut acq).await?;
+
+    cancel_subscription(&mut subscription, &*billing, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(InstanceSubscriptionResponse {
+        is_usable: subscription.is_usable(),
+        plan,
+        subscription,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use database::NewAddonInstanceModel;
+    use local_common::WebsiteId;
+    use time::Duration;
+
+    use super::{super::tests::test_addon, *};
+
+    /// Keeps subscriptions in memory. Statuses are changed with [`FakeBillingProvider::set_status`].
+    #[derive(Default)]
+    struct FakeBillingProvider {
+        subscriptions: Mutex<HashMap<String, ProviderSubscription>>,
+    }
+
+    impl FakeBillingProvider {
+        fn set_status(&self, id: &str, status: SubscriptionStatus) {
+            if let Some(sub) = self.subscriptions.lock().unwrap().get_mut(id) {
+                sub.status = status;
+            }
+        }
+    }
+
+    #[async_trait]
+    impl BillingProvider for FakeBillingProvider {
+        async fn create_subscription(
+            &self,
+            value: NewProviderSubscription<'_>,
+        ) -> Result<ProviderSubscription> {
+            let now = OffsetDateTime::now_utc();
+
+            let sub = ProviderSubscription {
+                id: format!("sub_{}", Uuid::new_v4().simple()),
+                status: if value.plan.trial_days > 0 {
+                    SubscriptionStatus::Trialing
+                } else {
+                    SubscriptionStatus::Active
+                },
+                trial_ends_at: (value.plan.trial_days > 0)
+                    .then(|| now + Duration::days(value.plan.trial_days as i64)),
+                current_period_end: match value.plan.type_of {
+                    PricingType::Monthly => Some(now + Duration::days(30)),
+                    PricingType::Yearly => Some(now + Duration::days(365)),
+                    _ => None,
+                },
+            };
+
+            self.subscriptions
+                .lock()
+                .unwrap()
+                .insert(sub.id.clone(), sub.clone());
+
+            Ok(sub)
+        }
+
+        async fn cancel_subscription(&self, id: &str) -> Result<ProviderSubscription> {
+            self.set_status(id, SubscriptionStatus::Cancelled);
+
+            Ok(self
+                .find_subscription(id)
+                .await?
+                .context("Subscription not found")?)
+        }
+
+        async fn find_subscription(&self, id: &str) -> Result<Option<ProviderSubscription>> {
+            Ok(self.subscriptions.lock().unwrap().get(id).cloned())
+        }
+    }
+
+    async fn setup() -> (SqlitePool, AddonId, AddonInstanceModel) {
+        let pool = database::init_memory().await.unwrap();
+        let mut acq = pool.acquire().await.unwrap();
+
+        let addon = test_addon(Uuid::new_v4(), "test")
+            .insert(&mut acq)
+            .await
+            .unwrap();
+
+        let instance = NewAddonInstanceModel {
+            addon_id: addon.id,
+            website_id: WebsiteId::from(1),
+            website_uuid: Uuid::new_v4(),
+            version: String::from("latest"),
+        }
+        .insert(&mut acq)
+        .await
+        .unwrap();
+
+        (pool, addon.id, instance)
+    }
+
+    async fn insert_plan(
+        addon_id: AddonId,
+        type_of: PricingType,
+        price: i64,
+        trial_days: i32,
+        db: &mut SqliteConnection,
+    ) -> AddonPricingPlanModel {
+        NewAddonPricingPlanModel {
+            addon_id,
+            name: String::from("Plan"),
+            type_of,
+            price,
+            currency: String::from("usd"),
+            is_per_seat: false,
+            trial_days,
+            provider_price_id: Some(String::from("price_test")),
+        }
+        .insert(db)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn install_requires_a_plan_once_the_addon_has_plans() {
+        let (pool, addon_id, _) = setup().await;
+        let mut acq = pool.acquire().await.unwrap();
+
+        assert!(find_install_plan(addon_id, None, None, &mut acq)
+            .await
+            .unwrap()
+            .is_none());
+
+        let free = insert_plan(addon_id, PricingType::Free, 0, 0, &mut acq).await;
+
+        // The single free plan is chosen automatically.
+        let plan = find_install_plan(addon_id, None, None, &mut acq)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(plan.public_id, free.public_id);
+
+        let paid = insert_plan(addon_id, PricingType::Monthly, 500, 0, &mut acq).await;
+
+        assert!(find_install_plan(addon_id, None, None, &mut acq)
+            .await
+            .is_err());
+        assert!(
+            find_install_plan(addon_id, Some(paid.public_id), None, &mut acq)
+                .await
+                .is_err()
+        );
+        assert!(
+            find_install_plan(addon_id, Some(paid.public_id), Some("cus_1"), &mut acq)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn subscription_status_gates_the_instance() {
+        let (pool, addon_id, instance) = setup().await;
+        let mut acq = pool.acquire().await.unwrap();
+        let billing = FakeBillingProvider::default();
+
+        let plan = insert_plan(addon_id, PricingType::Monthly, 500, 14, &mut acq).await;
+
+        let mut sub = subscribe_instance(&instance, &plan, Some("cus_1"), 1, &billing, &mut acq)
+            .await
+            .unwrap();
+
+        assert_eq!(sub.status, SubscriptionStatus::Trialing);
+        assert!(instance.is_usable(&mut acq).await.unwrap());
+
+        let provider_id = sub.provider_subscription_id.clone().unwrap();
+
+        billing.set_status(&provider_id, SubscriptionStatus::Active);
+        sync_subscription(&mut sub, &billing, &mut acq)
+            .await
+            .unwrap();
+        assert!(instance.is_usable(&mut acq).await.unwrap());
+
+        billing.set_status(&provider_id, SubscriptionStatus::PastDue);
+        sync_subscription(&mut sub, &billing, &mut acq)
+            .await
+            .unwrap();
+        assert!(!instance.is_usable(&mut acq).await.unwrap());
+
+        billing.set_status(&provider_id, SubscriptionStatus::Active);
+        sync_subscription(&mut sub, &billing, &mut acq)
+            .await
+            .unwrap();
+        cancel_subscription(&mut sub, &billing, &mut acq)
+            .await
+            .unwrap();
+
+        assert_eq!(sub.status, SubscriptionStatus::Cancelled);
+        assert!(sub.cancelled_at.is_some());
+        assert!(!instance.is_usable(&mut acq).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn paid_addons_require_a_current_subscription() {
+        let (pool, addon_id, instance) = setup().await;
+        let mut acq = pool.acquire().await.unwrap();
+        let billing = FakeBillingProvider::default();
+
+        assert!(instance.is_usable(&mut acq).await.unwrap());
+
+        let plan = insert_plan(addon_id, PricingType::Monthly, 500, 0, &mut acq).await;
+
+        assert!(!instance.is_usable(&mut acq).await.unwrap());
+
+        let mut sub = subscribe_instance(&instance, &plan, Some("cus_1"), 1, &billing, &mut acq)
+            .await
+            .unwrap();
+        assert!(instance.is_usable(&mut acq).await.unwrap());
+
+        sub.current_period_end = Some(OffsetDateTime::now_utc() - Duration::days(1));
+        sub.update(&mut acq).await.unwrap();
+        assert!(!instance.is_usable(&mut acq).await.unwrap());
+
+        // The provider still has the renewed period.
+        assert_eq!(sync_lapsed_subscriptions(&pool, &billing).await.unwrap(), 1);
+        assert!(instance.is_usable(&mut acq).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn free_plan_skips_the_billing_provider() {
+        let (pool, addon_id, instance) = setup().await;
+        let mut acq = pool.acquire().await.unwrap();
+        let billing = FakeBillingProvider::default();
+
+        let plan = insert_plan(addon_id, PricingType::Free, 0, 0, &mut acq).await;
+
+        let sub = subscribe_instance(&instance, &plan, None, 1, &billing, &mut acq)
+            .await
+            .unwrap();
+
+        assert_eq!(sub.status, SubscriptionStatus::Active);
+        assert!(sub.provider_subscription_id.is_none());
+        assert!(billing.subscriptions.lock().unwrap().is_empty());
+    }
+}
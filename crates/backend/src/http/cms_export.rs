@@ -0,0 +1,399 @@
+//! Exporting a collection as CSV, a JSON array or NDJSON.
+//!
+//! Rows are read a page at a time while the response is streamed so large collections aren't held
+//! in memory. Tags are written as their names so an export can be imported again through `cms_import`.
+
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use database::{AddonCapability, AddonModel, SchemaDataModel, SchemaDataTagModel, SchemaModel};
+use eyre::ContextCompat;
+use local_common::AddonId;
+use serde_qs::axum::QsQuery;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use webby_global_common::{
+    filter::Filter,
+    request::CmsQuery,
+    schema::{SchematicFieldKey, SchematicFieldType},
+    uuid::CollectionName,
+    value::SimpleValue,
+};
+
+use crate::Result;
+
+use super::{auth::AuthMember, map_to_field_value};
+
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new().route("/:format", get(export_data_rows))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            v => Err(eyre::eyre!("Unknown export format \"{v}\""))?,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json => "application/json",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// Streams the rows of the collection. The filters, sort and columns work the same as querying it.
+/// `offset` and `limit` are optional, every matching row is exported by default.
+async fn export_data_rows(
+    Path((addon_id, coll, format)): Path<(Uuid, CollectionName, String)>,
+    QsQuery(query): QsQuery<CmsQuery>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<Response> {
+    let format = ExportFormat::parse(&format)?;
+
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::View, &mut acq)
+        .await?;
+
+    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
+
+    if schema.store == "addon" {
+        return Err(eyre::eyre!(
+            "Collections stored by the addon can't be exported"
+        ))?;
+    }
+
+    let tag_names = SchemaDataTagModel::get_all(schema.id, &mut acq)
+        .await?
+        .into_iter()
+        .map(|v| (*v.id, v.name))
+        .collect();
+
+    drop(acq);
+
+    let selected = query
+        .columns
+        .map(|columns| HashSet::from_iter(columns.split(',').map(|v| v.to_string())));
+
+    let columns = export_columns(&schema, selected.as_ref());
+
+    let file_name = format!("{}.{}", coll.id, format.extension());
+
+    let export = Export {
+        db,
+        addon_id: addon.id,
+        schema,
+        filters: query.filters,
+        sort: query.sort,
+        selected,
+        columns,
+        tag_names,
+        format,
+        offset: query.offset.unwrap_or(0) as i64,
+        remaining: query.limit.map(|v| v as i64),
+        exported: 0,
+        is_started: false,
+        is_finished: false,
+    };
+
+    let stream = futures::stream::unfold(export, |mut export| async move {
+        match export.next_chunk().await {
+            Ok(Some(chunk)) => Some((Ok(Bytes::from(chunk)), export)),
+            Ok(None) => None,
+            Err(e) => {
+                error!("Export Error: {e}");
+
+                export.is_finished = true;
+
+                Some((Err(std::io::Error::other(e.to_string())), export))
+            }
+        }
+    });
+
+    let mut resp = Body::from_stream(stream).into_response();
+
+    let headers = resp.headers_mut();
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+
+    if let Ok(value) = HeaderValue::from_str(&format!("attachment; filename=\"{file_name}\"")) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+
+    Ok(resp)
+}
+
+/// The row id first, custom fields by name and the timestamps last.
+fn export_columns(
+    schema: &SchemaModel,
+    selected: Option<&HashSet<String>>,
+) -> Vec<(SchematicFieldKey, SchematicFieldType)> {
+    let mut columns = schema
+        .fields
+        .0
+        .iter()
+        .filter(|(key, field)| match key {
+            SchematicFieldKey::Owner => false,
+            SchematicFieldKey::Other(name) => {
+                !field.is_deleted && selected.map_or(true, |v| v.contains(name))
+            }
+            _ => true,
+        })
+        .map(|(key, field)| (key.clone(), field.field_type))
+        .collect::<Vec<_>>();
+
+    columns.sort_by(|(a, _), (b, _)| {
+        fn rank(key: &SchematicFieldKey) -> u8 {
+            match key {
+                SchematicFieldKey::Id => 0,
+                SchematicFieldKey::Other(_) => 1,
+                _ => 2,
+            }
+        }
+
+        rank(a)
+            .cmp(&rank(b))
+            .then_with(|| a.as_str().cmp(b.as_str()))
+    });
+
+    columns
+}
+
+struct Export {
+    db: SqlitePool,
+    addon_id: AddonId,
+    schema: SchemaModel,
+
+    filters: Option<Vec<Filter>>,
+    sort: Option<HashMap<String, String>>,
+    selected: Option<HashSet<String>>,
+    columns: Vec<(SchematicFieldKey, SchematicFieldType)>,
+    tag_names: HashMap<i64, String>,
+
+    format: ExportFormat,
+
+    offset: i64,
+    /// Rows left to export when a limit was set.
+    remaining: Option<i64>,
+    exported: i64,
+    is_started: bool,
+    is_finished: bool,
+}
+
+impl Export {
+    async fn next_chunk(&mut self) -> Result<Option<String>> {
+        if self.is_finished {
+            return Ok(None);
+        }
+
+        let mut chunk = String::new();
+
+        if !self.is_started {
+            self.is_started = true;
+
+            match self.format {
+                ExportFormat::Csv => {
+                    let header = self
+                        .columns
+                        .iter()
+                        .map(|(key, _)| escape_csv(key.as_str()))
+                        .collect::<Vec<_>>();
+
+                    chunk.push_str(&header.join(","));
+                    chunk.push_str("\r\n");
+                }
+                ExportFormat::Json => chunk.push('['),
+                ExportFormat::Ndjson => (),
+            }
+        }
+
+        let limit = self
+            .remaining
+            .map_or(EXPORT_PAGE_SIZE, |v| v.min(EXPORT_PAGE_SIZE));
+
+        let rows = if limit > 0 {
+            SchemaDataModel::find_by(
+                self.addon_id,
+                &self.schema,
+                self.filters.as_deref(),
+                self.sort.clone(),
+                self.offset,
+                limit,
+                &mut *self.db.acquire().await?,
+            )
+            .await?
+        } else {
+            Vec::new()
+        };
+
+        let count = rows.len() as i64;
+
+        for model in rows {
+            let row = self.export_row(model)?;
+
+            match self.format {
+                ExportFormat::Csv => {
+                    let cells = row
+                        .into_iter()
+                        .map(|(_, value)| escape_csv(&csv_cell(value)))
+                        .collect::<Vec<_>>();
+
+                    chunk.push_str(&cells.join(","));
+                    chunk.push_str("\r\n");
+                }
+
+                ExportFormat::Json | ExportFormat::Ndjson => {
+                    let object = row
+                        .into_iter()
+                        .filter_map(|(key, value)| Some((key, serde_json::to_value(value?).ok()?)))
+                        .collect::<serde_json::Map<_, _>>();
+
+                    if self.format == ExportFormat::Json && self.exported != 0 {
+                        chunk.push(',');
+                    }
+
+                    chunk.push_str(&serde_json::to_string(&object)?);
+
+                    if self.format == ExportFormat::Ndjson {
+                        chunk.push('\n');
+                    }
+                }
+            }
+
+            self.exported += 1;
+        }
+
+        self.offset += count;
+
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= count;
+        }
+
+        if count < limit || limit == 0 {
+            self.is_finished = true;
+
+            if self.format == ExportFormat::Json {
+                chunk.push(']');
+            }
+        }
+
+        Ok(Some(chunk))
+    }
+
+    /// The values of the row in column order. Tag ids are replaced with their names.
+    fn export_row(&self, model: SchemaDataModel) -> Result<Vec<(String, Option<SimpleValue>)>> {
+        let mut values = map_to_field_value(&self.schema, model, self.selected.as_ref())?;
+
+        Ok(self
+            .columns
+            .iter()
+            .map(|(key, field_type)| {
+                let value = values.remove(key).map(|value| match (field_type, value) {
+                    (SchematicFieldType::Tags, SimpleValue::ListNumber(ids)) => {
+                        SimpleValue::ListString(
+                            ids.into_iter()
+                                .filter_map(|id| {
+                                    self.tag_names.get(&(id.convert_f64() as i64)).cloned()
+                                })
+                                .collect(),
+                        )
+                    }
+                    (_, value) => value,
+                });
+
+                (key.as_str().to_string(), value)
+            })
+            .collect())
+    }
+}
+
+/// Lists are joined by commas, the same way the importer splits tags. Other values are written as JSON.
+fn csv_cell(value: Option<SimpleValue>) -> String {
+    match value {
+        None => String::new(),
+        Some(SimpleValue::Text(v)) => v,
+        Some(SimpleValue::ListString(v)) => v.join(", "),
+        Some(value) => match serde_json::to_value(value) {
+            Ok(serde_json::Value::Null) | Err(_) => String::new(),
+            Ok(serde_json::Value::String(v)) => v,
+            Ok(v) => v.to_string(),
+        },
+    }
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) || value.starts_with(' ') || value.ends_with(' ') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_escaping() {
+        assert_eq!(escape_csv("plain"), "plain");
+        assert_eq!(escape_csv("a, b"), "\"a, b\"");
+        assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv("two\nlines"), "\"two\nlines\"");
+        assert_eq!(escape_csv(" padded"), "\" padded\"");
+    }
+
+    #[test]
+    fn csv_cells() {
+        assert_eq!(csv_cell(None), "");
+        assert_eq!(csv_cell(Some(SimpleValue::Text(String::from("hi")))), "hi");
+        assert_eq!(
+            csv_cell(Some(SimpleValue::ListString(vec![
+                String::from("red"),
+                String::from("blue")
+            ]))),
+            "red, blue"
+        );
+        assert_eq!(csv_cell(Some(SimpleValue::Boolean(true))), "true");
+    }
+}
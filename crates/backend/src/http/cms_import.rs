@@ -0,0 +1,836 @@
+//! Importing CSV and NDJSON files into a collection.
+//!
+//! The file is read as its' chunks arrive and inserted in batches, each in its' own transaction. Every
+//! row is given an `import_id` hashed from its' mapped values so running the same file again skips
+//! the rows which were already imported. That also makes a partially failed import safe to retry.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    routing::post,
+    Json, Router,
+};
+use database::{
+    AddonCapability, AddonModel, CmsRowEvent, NewSchemaDataModel, SchemaDataModel,
+    SchemaDataTagModel, SchemaModel, WebhookEvent,
+};
+use eyre::{Context, ContextCompat};
+use local_common::{AddonId, SchemaId};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+use webby_api::schema::SchematicField;
+use webby_global_common::{
+    schema::{SchematicFieldKey, SchematicFieldType},
+    uuid::CollectionName,
+    value::SimpleValue,
+};
+
+use crate::Result;
+
+use super::{
+    auth::AuthMember,
+    automation::{spawn_automation_event, AutomationEvent},
+    webhook::queue_cms_row_event,
+};
+
+const MAX_IMPORT_SIZE: usize = 1024 * 1024 * 50;
+const MAX_IMPORT_ROWS: usize = 50_000;
+const IMPORT_BATCH_SIZE: usize = 500;
+const MAX_REPORTED_ERRORS: usize = 100;
+const PREVIEW_ROWS: usize = 10;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new().route(
+        "/",
+        // The file is size checked while streaming, this only leaves room for the other fields.
+        post(import_data_file).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE + 1024 * 1024)),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl ImportFormat {
+    fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            v => Err(eyre::eyre!("Unknown import format \"{v}\""))?,
+        }
+    }
+
+    fn from_file_name(file_name: &str) -> Option<Self> {
+        let (_, ext) = file_name.rsplit_once('.')?;
+
+        Self::parse(ext).ok()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowError {
+    pub line: usize,
+    pub error: String,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// Every column found in the file.
+    pub columns: Vec<String>,
+    /// File column to schema field.
+    pub mapping: HashMap<String, String>,
+
+    pub total: usize,
+    /// Rows inserted. On a dry run, the rows which would be.
+    pub imported: usize,
+    /// Rows which were already imported.
+    pub duplicates: usize,
+    pub failed: usize,
+    pub errors: Vec<ImportRowError>,
+
+    /// The first mapped rows of a dry run.
+    pub preview: Vec<BTreeMap<String, SimpleValue>>,
+}
+
+impl ImportReport {
+    fn push_error(&mut self, line: usize, error: String) {
+        self.failed += 1;
+
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(ImportRowError { line, error });
+        }
+    }
+}
+
+/// Imports an uploaded file into the collection.
+///
+/// Optional fields have to be sent before the `file` field:
+/// - `format`: `csv` or `ndjson`. Otherwise guessed from the file name.
+/// - `mapping`: JSON object of file column to schema field. Otherwise columns named after a field are used.
+/// - `dryRun`: `true` to only validate the file and preview the mapped rows.
+async fn import_data_file(
+    Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    mut multipart: Multipart,
+) -> Result<JsonResponse<ImportReport>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
+    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
+
+    let mut tags = load_tag_cache(schema.id, &mut acq).await?;
+
+    let mut format = None;
+    let mut mapping = None;
+    let mut dry_run = false;
+
+    let mut import = None;
+
+    while let Some(mut field) = multipart.next_field().await? {
+        match field.name() {
+            Some("format") => format = Some(ImportFormat::parse(&field.text().await?)?),
+            Some("mapping") => {
+                mapping = Some(
+                    serde_json::from_str::<HashMap<String, String>>(&field.text().await?)
+                        .wrap_err("Invalid column mapping")?,
+                )
+            }
+            Some("dryRun") => dry_run = field.text().await?.trim() == "true",
+
+            Some("file") => {
+                let format = format
+                    .or_else(|| field.file_name().and_then(ImportFormat::from_file_name))
+                    .context("Unable to determine the import format")?;
+
+                let auto_map = mapping.is_none();
+
+                if let Some(mapping) = mapping.as_ref() {
+                    for field_name in mapping.values() {
+                        if find_import_field(&schema, field_name).is_none() {
+                            return Err(eyre::eyre!("Schema Field not found: {field_name}"))?;
+                        }
+                    }
+                }
+
+                let mut importer = Importer {
+                    addon_id: addon.id,
+                    collection: &coll.id,
+                    schema: &schema,
+                    mapping: mapping.take().unwrap_or_default(),
+                    auto_map,
+                    dry_run,
+                    reader: RecordReader::new(format),
+                    header: None,
+                    columns: BTreeSet::new(),
+                    batch: Vec::new(),
+                    seen: HashSet::new(),
+                    tags: std::mem::take(&mut tags),
+                    report: ImportReport {
+                        dry_run,
+                        ..Default::default()
+                    },
+                    imported_rows: Vec::new(),
+                };
+
+                let mut size = 0;
+
+                while let Some(chunk) = field.chunk().await? {
+                    size += chunk.len();
+
+                    if size > MAX_IMPORT_SIZE {
+                        return Err(eyre::eyre!(
+                            "An import can't be larger than {}MB",
+                            MAX_IMPORT_SIZE / 1024 / 1024
+                        ))?;
+                    }
+
+                    for record in importer.reader.push(&chunk)? {
+                        importer.push_record(record, &mut acq).await?;
+                    }
+                }
+
+                for record in importer.reader.finish()? {
+                    importer.push_record(record, &mut acq).await?;
+                }
+
+                importer.flush(&mut acq).await?;
+
+                import = Some(importer.finish());
+            }
+
+            _ => (),
+        }
+    }
+
+    let (report, imported_rows) = import.context("No file uploaded")?;
+
+    if !imported_rows.is_empty() {
+        spawn_automation_event(
+            db,
+            AutomationEvent::CmsRow {
+                addon_id: addon.id,
+                collection: coll.id,
+                event: CmsRowEvent::Created,
+                row_ids: imported_rows,
+            },
+        );
+    }
+
+    Ok(Json(WrappingResponse::okay(report)))
+}
+
+struct Importer<'a> {
+    addon_id: AddonId,
+    collection: &'a str,
+    schema: &'a SchemaModel,
+
+    /// File column to schema field.
+    mapping: HashMap<String, String>,
+    /// No mapping was sent. Columns named after a schema field are mapped as they're found.
+    auto_map: bool,
+    dry_run: bool,
+
+    reader: RecordReader,
+    /// CSV columns
+    header: Option<Vec<String>>,
+    columns: BTreeSet<String>,
+
+    batch: Vec<(usize, BTreeMap<String, SimpleValue>)>,
+    /// Import ids of this file. Catches the same line appearing twice.
+    seen: HashSet<String>,
+    /// Lowercase tag name to id, per field. Only updated once the batch creating them is committed.
+    tags: HashMap<(String, String), i64>,
+
+    report: ImportReport,
+    imported_rows: Vec<Uuid>,
+}
+
+impl Importer<'_> {
+    async fn push_record(
+        &mut self,
+        (line, record): (usize, String),
+        db: &mut SqliteConnection,
+    ) -> Result<()> {
+        if self.reader.format == ImportFormat::Csv && self.header.is_none() {
+            let header = parse_csv_record(&record)
+                .into_iter()
+                .map(|v| v.trim().to_string())
+                .collect::<Vec<_>>();
+
+            self.map_columns(&header);
+            self.header = Some(header);
+
+            if self.mapping.is_empty() {
+                return Err(eyre::eyre!("No columns are mapped to a field"))?;
+            }
+
+            return Ok(());
+        }
+
+        self.report.total += 1;
+
+        if self.report.total > MAX_IMPORT_ROWS {
+            return Err(eyre::eyre!(
+                "An import can't have more than {MAX_IMPORT_ROWS} rows"
+            ))?;
+        }
+
+        let values = match self.reader.format {
+            ImportFormat::Csv => {
+                let cells = parse_csv_record(&record);
+                let header = self.header.as_deref().unwrap_or_default();
+
+                if cells.len() != header.len() {
+                    self.report.push_error(
+                        line,
+                        format!("Expected {} columns, found {}", header.len(), cells.len()),
+                    );
+
+                    return Ok(());
+                }
+
+                header
+                    .iter()
+                    .cloned()
+                    .zip(cells)
+                    .filter(|(_, cell)| !cell.is_empty())
+                    .map(|(column, cell)| (column, Ok(SimpleValue::Text(cell))))
+                    .collect::<Vec<_>>()
+            }
+
+            ImportFormat::Ndjson => {
+                let object = match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(
+                    &record,
+                ) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        self.report.push_error(line, format!("Invalid JSON: {e}"));
+                        return Ok(());
+                    }
+                };
+
+                self.map_columns(&object.keys().cloned().collect::<Vec<_>>());
+
+                object
+                    .into_iter()
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(column, value)| {
+                        let value = serde_json::from_value::<SimpleValue>(value)
+                            .map_err(|e| format!("Invalid value for \"{column}\": {e}"));
+
+                        (column, value)
+                    })
+                    .collect()
+            }
+        };
+
+        let mut fields = BTreeMap::new();
+
+        for (column, value) in values {
+            let Some(field_name) = self.mapping.get(&column) else {
+                continue;
+            };
+
+            match value {
+                Ok(value) => {
+                    fields.insert(field_name.clone(), value);
+                }
+
+                Err(error) => {
+                    self.report.push_error(line, error);
+                    return Ok(());
+                }
+            }
+        }
+
+        if fields.is_empty() {
+            self.report
+                .push_error(line, String::from("No values for the mapped columns"));
+            return Ok(());
+        }
+
+        if self.dry_run && self.report.preview.len() < PREVIEW_ROWS {
+            self.report.preview.push(fields.clone());
+        }
+
+        self.batch.push((line, fields));
+
+        if self.batch.len() >= IMPORT_BATCH_SIZE {
+            self.flush(db).await?;
+        }
+
+        Ok(())
+    }
+
+    fn map_columns(&mut self, columns: &[String]) {
+        for column in columns {
+            if !self.columns.contains(column) {
+                self.columns.insert(column.clone());
+
+                if self.auto_map && find_import_field(self.schema, column).is_some() {
+                    self.mapping.insert(column.clone(), column.clone());
+                }
+            }
+        }
+    }
+
+    async fn flush(&mut self, db: &mut SqliteConnection) -> Result<()> {
+        let batch = std::mem::take(&mut self.batch);
+
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        if self.dry_run {
+            let mut tags = std::mem::take(&mut self.tags);
+
+            for (line, fields) in batch {
+                if self
+                    .import_row(line, fields, &mut tags, &mut *db)
+                    .await?
+                    .is_some()
+                {
+                    self.report.imported += 1;
+                }
+            }
+
+            self.tags = tags;
+
+            return Ok(());
+        }
+
+        let this = &mut *self;
+        let mut tags = this.tags.clone();
+
+        let (rows, tags) = db
+            .transaction(|trx| {
+                Box::pin(async move {
+                    let mut rows = Vec::new();
+
+                    for (line, fields) in batch {
+                        if let Some(row) = this.import_row(line, fields, &mut tags, trx).await? {
+                            rows.push(row.insert(trx).await?.public_id);
+                        }
+                    }
+
+                    if !rows.is_empty() {
+                        queue_cms_row_event(
+                            this.addon_id,
+                            WebhookEvent::CmsRowCreated,
+                            this.collection,
+                            &rows,
+                            trx,
+                        )
+                        .await?;
+                    }
+
+                    Result::<_, crate::Error>::Ok((rows, tags))
+                })
+            })
+            .await?;
+
+        self.report.imported += rows.len();
+        self.tags = tags;
+        self.imported_rows.extend(rows);
+
+        Ok(())
+    }
+
+    /// Returns the row when it's valid and wasn't imported before. Tags are created on `db` but
+    /// only cached in `tags`, which the caller keeps once they're committed.
+    async fn import_row(
+        &mut self,
+        line: usize,
+        fields: BTreeMap<String, SimpleValue>,
+        tags: &mut HashMap<(String, String), i64>,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<NewSchemaDataModel>> {
+        let import_id = hash_import_row(&fields);
+
+        if self.seen.contains(&import_id)
+            || SchemaDataModel::exists_by_import_id(self.schema.id, &import_id, &mut *db).await?
+        {
+            self.report.duplicates += 1;
+            return Ok(None);
+        }
+
+        let mut row = NewSchemaDataModel::new(self.addon_id, self.schema.id);
+        row.import_id = Some(import_id.clone());
+
+        // Tags are resolved last so an invalid row doesn't create them.
+        let (tag_fields, fields): (Vec<_>, Vec<_>) = fields.into_iter().partition(|(name, _)| {
+            find_import_field(self.schema, name).map(|v| v.field_type)
+                == Some(SchematicFieldType::Tags)
+        });
+
+        for (name, value) in fields {
+            if let Err(e) = insert_import_field(self.schema, &mut row, name, value) {
+                self.report.push_error(line, format!("{e:#}"));
+                return Ok(None);
+            }
+        }
+
+        for (name, value) in tag_fields {
+            let names = match value {
+                SimpleValue::Text(v) => v.split(',').map(|v| v.to_string()).collect(),
+                SimpleValue::ListString(v) => v,
+                v => {
+                    self.report.push_error(
+                        line,
+                        format!("Expected tag names for \"{name}\", found {v:?}"),
+                    );
+                    return Ok(None);
+                }
+            };
+
+            let value = if self.dry_run {
+                SimpleValue::ListNumber(Vec::new())
+            } else {
+                resolve_tag_names(self.schema.id, &name, names, tags, &mut *db).await?
+            };
+
+            if let Err(e) = insert_import_field(self.schema, &mut row, name, value) {
+                self.report.push_error(line, format!("{e:#}"));
+                return Ok(None);
+            }
+        }
+
+        // Only once it's valid, a repeated invalid line is reported as failed again rather than a duplicate.
+        self.seen.insert(import_id);
+
+        Ok(Some(row))
+    }
+
+    fn finish(self) -> (ImportReport, Vec<Uuid>) {
+        let mut report = self.report;
+        report.columns = self.columns.into_iter().collect();
+        report.mapping = self.mapping;
+
+        (report, self.imported_rows)
+    }
+}
+
+fn find_import_field<'a>(schema: &'a SchemaModel, name: &str) -> Option<&'a SchematicField> {
+    schema
+        .fields
+        .get(&SchematicFieldKey::Other(name.to_string()))
+        .filter(|v| !v.is_deleted)
+}
+
+fn insert_import_field(
+    schema: &SchemaModel,
+    row: &mut NewSchemaDataModel,
+    name: String,
+    value: SimpleValue,
+) -> eyre::Result<()> {
+    let field = find_import_field(schema, &name)
+        .with_context(|| format!("Schema Field not found: {name}"))?;
+
+    let value = field
+        .field_type
+        .parse_value(value)
+        .wrap_err_with(|| format!("Parse \"{name}\" into Type: {:?}", field.field_type))?;
+
+    row.insert_field(name, false, field.field_type, value)
+}
+
+/// Every existing tag of the schema, keyed the same as `resolve_tag_names` caches them.
+pub async fn load_tag_cache(
+    schema_id: SchemaId,
+    db: &mut SqliteConnection,
+) -> Result<HashMap<(String, String), i64>> {
+    Ok(SchemaDataTagModel::get_all(schema_id, db)
+        .await?
+        .into_iter()
+        .map(|v| ((v.row_id, v.name.trim().to_lowercase()), *v.id))
+        .collect())
+}
+
+/// Finds or creates the tags of a field, returning their ids.
+pub async fn resolve_tag_names(
+    schema_id: SchemaId,
+    field_name: &str,
+    names: Vec<String>,
+    cache: &mut HashMap<(String, String), i64>,
+    db: &mut SqliteConnection,
+) -> Result<SimpleValue> {
+    let mut items = Vec::new();
+
+    for name in names {
+        let trimmed = name.trim();
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let key = (field_name.to_string(), trimmed.to_lowercase());
+
+        // We don't want to call the DB for no reason
+        if let Some(found) = cache.get(&key).copied() {
+            items.push(found.into());
+        } else {
+            let model = SchemaDataTagModel::insert(
+                schema_id,
+                field_name.to_string(),
+                trimmed.to_string(),
+                String::from("#FAF"),
+                db,
+            )
+            .await?;
+
+            cache.insert(key, *model.id);
+
+            items.push((*model.id).into());
+        }
+    }
+
+    Ok(SimpleValue::ListNumber(items))
+}
+
+/// Hashes the mapped values of a row. The same values always have the same hash.
+fn hash_import_row(fields: &BTreeMap<String, SimpleValue>) -> String {
+    let mut sha = Sha256::new();
+
+    for (name, value) in fields {
+        sha.update(name);
+        sha.update([0]);
+        sha.update(serde_json::to_vec(value).unwrap_or_default());
+        sha.update([0]);
+    }
+
+    format!("{:x}", sha.finalize())
+}
+
+/// Splits the uploaded chunks into records along with the line they start on.
+struct RecordReader {
+    format: ImportFormat,
+
+    buffer: Vec<u8>,
+    /// A CSV record with a quoted value which continues on the next line.
+    pending: Option<(usize, String)>,
+    line: usize,
+}
+
+impl RecordReader {
+    fn new(format: ImportFormat) -> Self {
+        Self {
+            format,
+            buffer: Vec::new(),
+            pending: None,
+            line: 0,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) -> Result<Vec<(usize, String)>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut records = Vec::new();
+
+        while let Some(pos) = self.buffer.iter().position(|v| *v == b'\n') {
+            let line = self.buffer.drain(..=pos).collect::<Vec<_>>();
+
+            self.push_line(&line, &mut records)?;
+        }
+
+        Ok(records)
+    }
+
+    fn finish(&mut self) -> Result<Vec<(usize, String)>> {
+        let mut records = Vec::new();
+
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            self.push_line(&line, &mut records)?;
+        }
+
+        if let Some((line, _)) = self.pending.take() {
+            return Err(eyre::eyre!("Line {line}: Unterminated quoted value"))?;
+        }
+
+        Ok(records)
+    }
+
+    fn push_line(&mut self, line: &[u8], records: &mut Vec<(usize, String)>) -> Result<()> {
+        self.line += 1;
+
+        let text = std::str::from_utf8(line)
+            .with_context(|| format!("Line {}: Invalid UTF-8", self.line))?;
+
+        // Strip the BOM some spreadsheet programs write.
+        let text = if self.line == 1 {
+            text.trim_start_matches('\u{feff}')
+        } else {
+            text
+        };
+
+        let (start, mut record) = match self.pending.take() {
+            Some((start, mut record)) => {
+                record.push('\n');
+                (start, record)
+            }
+            None => (self.line, String::new()),
+        };
+
+        record.push_str(text.trim_end_matches(['\n', '\r']));
+
+        if self.format == ImportFormat::Csv && record.matches('"').count() % 2 == 1 {
+            self.pending = Some((start, record));
+        } else if !record.trim().is_empty() {
+            records.push((start, record));
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a CSV record into its' values. Quoted values may contain commas, newlines and `""` for a quote.
+fn parse_csv_record(record: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut value = String::new();
+    let mut in_quotes = false;
+
+    let mut chars = record.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    value.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if value.is_empty() => in_quotes = true,
+            ',' if !in_quotes => values.push(std::mem::take(&mut value)),
+            c => value.push(c),
+        }
+    }
+
+    values.push(value);
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(format: ImportFormat, chunks: &[&str]) -> Vec<(usize, String)> {
+        let mut reader = RecordReader::new(format);
+        let mut records = Vec::new();
+
+        for chunk in chunks {
+            records.extend(reader.push(chunk.as_bytes()).unwrap());
+        }
+
+        records.extend(reader.finish().unwrap());
+
+        records
+    }
+
+    #[test]
+    fn csv_values() {
+        assert_eq!(parse_csv_record("a,b,,c"), ["a", "b", "", "c"]);
+        assert_eq!(
+            parse_csv_record(r#""a, b","say ""hi""",c"#),
+            ["a, b", r#"say "hi""#, "c"]
+        );
+        assert_eq!(parse_csv_record("\"multi\nline\",x"), ["multi\nline", "x"]);
+    }
+
+    #[test]
+    fn records_across_chunks() {
+        let records = read_all(
+            ImportFormat::Csv,
+            &[
+                "\u{feff}name,bio\r\n",
+                "ann,\"line one\n",
+                "line two\"\r\nbo",
+                "b,x",
+            ],
+        );
+
+        assert_eq!(
+            records,
+            [
+                (1, String::from("name,bio")),
+                (2, String::from("ann,\"line one\nline two\"")),
+                (4, String::from("bob,x")),
+            ]
+        );
+
+        let records = read_all(
+            ImportFormat::Ndjson,
+            &["{\"a\":1}\n\n{\"a\"", ":\"\\\"\"}\n"],
+        );
+
+        assert_eq!(
+            records,
+            [
+                (1, String::from("{\"a\":1}")),
+                (3, String::from("{\"a\":\"\\\"\"}")),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_quote_errors() {
+        let mut reader = RecordReader::new(ImportFormat::Csv);
+
+        assert!(reader.push(b"a,\"b\nc\n").unwrap().is_empty());
+        assert!(reader.finish().is_err());
+    }
+
+    #[test]
+    fn import_hash_is_stable() {
+        let a = BTreeMap::from([
+            (
+                String::from("title"),
+                SimpleValue::Text(String::from("Hello")),
+            ),
+            (
+                String::from("body"),
+                SimpleValue::Text(String::from("World")),
+            ),
+        ]);
+
+        let mut b = BTreeMap::new();
+        b.insert(
+            String::from("body"),
+            SimpleValue::Text(String::from("World")),
+        );
+        b.insert(
+            String::from("title"),
+            SimpleValue::Text(String::from("Hello")),
+        );
+
+        assert_eq!(hash_import_row(&a), hash_import_row(&b));
+
+        b.insert(
+            String::from("title"),
+            SimpleValue::Text(String::from("Hello!")),
+        );
+
+        assert_ne!(hash_import_row(&a), hash_import_row(&b));
+    }
+}
@@ -0,0 +1,183 @@
+//! Members working on an addon besides its' owner.
+//!
+//! Collaborators are invited with a role and only gain access once they accept.
+
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use database::{
+    AddonCapability, AddonCollaboratorModel, AddonModel, AddonRole, NewAddonCollaboratorModel,
+};
+use eyre::ContextCompat;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+
+use crate::Result;
+
+use super::auth::AuthMember;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route("/", get(get_collaborators).post(invite_collaborator))
+        .route("/accept", post(accept_invite))
+        .route("/:member", delete(remove_collaborator))
+}
+
+async fn find_addon(addon_id: Uuid, db: &mut SqliteConnection) -> Result<AddonModel> {
+    Ok(AddonModel::find_one_by_guid(addon_id, db)
+        .await?
+        .context("Addon not found")?)
+}
+
+async fn get_collaborators(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Vec<AddonCollaboratorModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        AddonCollaboratorModel::find_by_addon_id(addon.id, &mut acq).await?,
+    )))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InviteCollaboratorJson {
+    pub member_id: Uuid,
+    pub role: AddonRole,
+}
+
+async fn invite_collaborator(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<InviteCollaboratorJson>,
+) -> Result<JsonResponse<AddonCollaboratorModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageCollaborators, &mut acq)
+        .await?;
+
+    if addon.member_uuid == value.member_id {
+        return Err(eyre::eyre!("Member already owns the Addon"))?;
+    }
+
+    if AddonCollaboratorModel::find_one_by_addon_member(addon.id, value.member_id, &mut acq)
+        .await?
+        .is_some()
+    {
+        return Err(eyre::eyre!("Member is already a Collaborator"))?;
+    }
+
+    let collaborator = NewAddonCollaboratorModel {
+        addon_id: addon.id,
+        member_uuid: value.member_id,
+        role: value.role,
+        invited_by: member.uuid(),
+    }
+    .insert(&mut acq)
+    .await?;
+
+    Ok(Json(WrappingResponse::okay(collaborator)))
+}
+
+async fn accept_invite(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<AddonCollaboratorModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(addon_id, &mut acq).await?;
+
+    let mut collaborator =
+        AddonCollaboratorModel::find_one_by_addon_member(addon.id, member.uuid(), &mut acq)
+            .await?
+            .context("Invite not found")?;
+
+    if !collaborator.is_accepted() {
+        collaborator.accepted_at = Some(OffsetDateTime::now_utc());
+        collaborator.update(&mut acq).await?;
+    }
+
+    Ok(Json(WrappingResponse::okay(collaborator)))
+}
+
+/// Collaborators can always remove themselves, which also declines a pending invite.
+async fn remove_collaborator(
+    Path((addon_id, member_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(addon_id, &mut acq).await?;
+
+    if member.uuid() != member_id {
+        member
+            .addon_access_error(&addon, AddonCapability::ManageCollaborators, &mut acq)
+            .await?;
+    }
+
+    let collaborator =
+        AddonCollaboratorModel::find_one_by_addon_member(addon.id, member_id, &mut acq)
+            .await?
+            .context("Collaborator not found")?;
+
+    collaborator.delete(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberInviteResponse {
+    pub addon_id: Uuid,
+    pub addon_name: String,
+    pub role: AddonRole,
+    pub invited_by: Uuid,
+    pub created_at: OffsetDateTime,
+}
+
+/// Invites the calling member hasn't accepted yet.
+pub async fn get_member_invites(
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Vec<MemberInviteResponse>>> {
+    let mut acq = db.acquire().await?;
+
+    let mut items = Vec::new();
+
+    for invite in
+        AddonCollaboratorModel::find_pending_by_member_uuid(member.uuid(), &mut acq).await?
+    {
+        let Some(addon) = AddonModel::find_one_by_id(invite.addon_id, &mut acq).await? else {
+            continue;
+        };
+
+        if addon.deleted_at.is_some() {
+            continue;
+        }
+
+        items.push(MemberInviteResponse {
+            addon_id: addon.guid,
+            addon_name: addon.name,
+            role: invite.role,
+            invited_by: invite.invited_by,
+            created_at: invite.created_at,
+        });
+    }
+
+    Ok(Json(WrappingResponse::okay(items)))
+}
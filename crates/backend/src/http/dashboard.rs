@@ -0,0 +1,455 @@
+//! The SPA the addon's dashboard pages are rendered with.
+//!
+//! A bundle is uploaded as the built assets, one file per multipart field named by its' path. It's
+//! stored under its' hash so assets requested as `{hash}/{path}` never change and can be cached
+//! forever. Any other path resolves to the entry of the newest bundle.
+//!
+//! Fields are used instead of an archive since we have no archive reader to unpack one with, a
+//! `dist` folder can be sent as is by any multipart client.
+
+use std::path::PathBuf;
+
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use database::{
+    AddonCapability, AddonCompiledModel, AddonDashboardBundleAssetModel, AddonDashboardBundleModel,
+    AddonInstanceModel, AddonModel, NewAddonDashboardBundleAssetModel,
+    NewAddonDashboardBundleModel,
+};
+use eyre::ContextCompat;
+use local_common::upload::StorageService;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+
+use crate::Result;
+
+use super::auth::AuthMember;
+
+const MAX_ASSETS: usize = 200;
+const MAX_BUNDLE_SIZE: usize = 1024 * 1024 * 25;
+
+/// Room for the multipart boundaries and headers on top of the assets.
+const MAX_BUNDLE_BODY_SIZE: usize = MAX_BUNDLE_SIZE + 1024 * 1024;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new().route(
+        "/",
+        get(get_bundle)
+            .post(upload_bundle)
+            .layer(DefaultBodyLimit::max(MAX_BUNDLE_BODY_SIZE)),
+    )
+}
+
+pub fn get_bundle_asset_path(addon_id: Uuid, hash: &str, path: &str) -> PathBuf {
+    let mut full = PathBuf::from("/addon_dashboard_bundle");
+
+    full.push(addon_id.to_string());
+    full.push(hash);
+    full.push(path);
+
+    full
+}
+
+/// Hash of a bundle from the path and hash of each of its' assets.
+pub fn hash_bundle<'a>(assets: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut assets = assets.into_iter().collect::<Vec<_>>();
+    assets.sort_unstable();
+
+    let mut sha = Sha256::new();
+
+    for (path, hash) in assets {
+        sha.update(path);
+        sha.update([0]);
+        sha.update(hash);
+        sha.update([0]);
+    }
+
+    format!("{:x}", sha.finalize())
+}
+
+/// Relative paths only. E.g. `assets/index.js`
+fn validate_asset_path(path: &str) -> Result<()> {
+    let is_valid = !path.is_empty()
+        && path.len() <= 255
+        && path.split('/').all(|v| {
+            !v.is_empty()
+                && v != "."
+                && v != ".."
+                && v.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '@'))
+        });
+
+    if is_valid {
+        Ok(())
+    } else {
+        Err(eyre::eyre!("Invalid asset path \"{path}\""))?
+    }
+}
+
+/// The bundle of the version the instance is on.
+pub async fn find_instance_bundle(
+    instance: &AddonInstanceModel,
+    db: &mut SqliteConnection,
+) -> Result<Option<AddonDashboardBundleModel>> {
+    let Some(compiled) = AddonCompiledModel::find_one_by_addon_uuid_and_version(
+        instance.addon_id,
+        &instance.version,
+        &mut *db,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    Ok(AddonDashboardBundleModel::find_one_by_compiled_id(compiled.pk, db).await?)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleResponse {
+    #[serde(flatten)]
+    pub bundle: AddonDashboardBundleModel,
+    pub assets: Vec<AddonDashboardBundleAssetModel>,
+}
+
+async fn get_bundle(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Option<BundleResponse>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    let Some(bundle) =
+        AddonDashboardBundleModel::find_one_draft_by_addon_id(addon.id, &mut acq).await?
+    else {
+        return Ok(Json(WrappingResponse::okay(None)));
+    };
+
+    let assets = AddonDashboardBundleAssetModel::find_by_bundle_id(bundle.id, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(Some(BundleResponse {
+        bundle,
+        assets,
+    }))))
+}
+
+struct UploadedAsset {
+    path: String,
+    content_type: mime_guess::Mime,
+    hash: String,
+    contents: Vec<u8>,
+}
+
+/// Replaces the bundle being edited.
+///
+/// The entry can be set with an `entry` field. Otherwise it's the first `.js` asset.
+async fn upload_bundle(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    storage: StorageService,
+    mut multipart: Multipart,
+) -> Result<JsonResponse<BundleResponse>> {
+    let addon = member
+        .find_owned_addon(
+            addon_id,
+            AddonCapability::EditCode,
+            &mut *db.acquire().await?,
+        )
+        .await?;
+
+    let mut entry = None;
+    let mut assets = Vec::<UploadedAsset>::new();
+    let mut total_size = 0;
+
+    while let Some(field) = multipart.next_field().await? {
+        let Some(path) = field
+            .file_name()
+            .map(|v| v.trim_start_matches("./").to_string())
+        else {
+            if field.name() == Some("entry") {
+                entry = Some(field.text().await?);
+            }
+
+            continue;
+        };
+
+        validate_asset_path(&path)?;
+
+        if assets.iter().any(|v| v.path == path) {
+            return Err(eyre::eyre!("Duplicate asset \"{path}\""))?;
+        }
+
+        if assets.len() == MAX_ASSETS {
+            return Err(eyre::eyre!(
+                "A bundle can't have more than {MAX_ASSETS} assets"
+            ))?;
+        }
+
+        let contents = field.bytes().await?.to_vec();
+
+        total_size += contents.len();
+
+        if total_size > MAX_BUNDLE_SIZE {
+            return Err(eyre::eyre!(
+                "A bundle can't be larger than {}MB",
+                MAX_BUNDLE_SIZE / 1024 / 1024
+            ))?;
+        }
+
+        assets.push(UploadedAsset {
+            content_type: mime_guess::from_path(&path).first_or_octet_stream(),
+            hash: format!("{:x}", Sha256::digest(&contents)),
+            path,
+            contents,
+        });
+    }
+
+    assets.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+    let entry = match entry {
+        Some(entry) => {
+            if !assets.iter().any(|v| v.path == entry) {
+                return Err(eyre::eyre!("Entry \"{entry}\" isn't in the bundle"))?;
+            }
+
+            entry
+        }
+
+        None => assets
+            .iter()
+            .find(|v| v.path.ends_with(".js"))
+            .map(|v| v.path.clone())
+            .context("Bundle has no .js entry")?,
+    };
+
+    let hash = hash_bundle(assets.iter().map(|v| (v.path.as_str(), v.hash.as_str())));
+
+    let mut uploaded = Vec::new();
+
+    for asset in assets {
+        let store_path = get_bundle_asset_path(addon.guid, &hash, &asset.path);
+
+        let file_size = asset.contents.len() as i64;
+
+        let b2 = storage
+            .upload(
+                store_path.clone(),
+                asset.content_type.clone(),
+                asset.contents,
+            )
+            .await?;
+
+        uploaded.push((
+            asset.path,
+            asset.content_type.to_string(),
+            file_size,
+            asset.hash,
+            store_path.display().to_string(),
+            b2.file_id.to_string(),
+        ));
+    }
+
+    let mut acq = db.acquire().await?;
+
+    let resp = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                if let Some(draft) =
+                    AddonDashboardBundleModel::find_one_draft_by_addon_id(addon.id, &mut *trx)
+                        .await?
+                {
+                    draft.delete(&mut *trx).await?;
+                }
+
+                let bundle = NewAddonDashboardBundleModel {
+                    addon_id: addon.id,
+                    compiled_id: None,
+                    hash,
+                    entry,
+                }
+                .insert(&mut *trx)
+                .await?;
+
+                let mut assets = Vec::new();
+
+                for (path, content_type, file_size, hash, store_path, file_id) in uploaded {
+                    let asset = NewAddonDashboardBundleAssetModel {
+                        bundle_id: bundle.id,
+                        path,
+                        content_type,
+                        file_size,
+                        hash,
+                        store_path,
+                        file_id,
+                    }
+                    .insert(&mut *trx)
+                    .await?;
+
+                    assets.push(asset);
+                }
+
+                Result::<_, crate::Error>::Ok(BundleResponse { bundle, assets })
+            })
+        })
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(resp)))
+}
+
+/// Serves an asset of the bundle.
+///
+/// `{hash}/{path}` is served as immutable. Anything else, e.g. a dashboard page path, is the entry
+/// of the newest published bundle, or the one being edited if nothing was published yet.
+pub async fn get_addon_dashboard_page(
+    Path((guid, path)): Path<(Uuid, String)>,
+    State(db): State<SqlitePool>,
+    storage: StorageService,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let mut acq = db.acquire().await?;
+
+    let Some(addon) = AddonModel::find_one_by_guid(guid, &mut acq).await? else {
+        return Err(eyre::eyre!("Addon not found"))?;
+    };
+
+    let path = path.trim_start_matches('/');
+
+    let hashed = match path.split_once('/') {
+        Some((hash, asset_path)) => {
+            AddonDashboardBundleModel::find_one_by_addon_id_and_hash(addon.id, hash, &mut acq)
+                .await?
+                .map(|bundle| (bundle, asset_path))
+        }
+        None => None,
+    };
+
+    let (asset, is_immutable) = match hashed {
+        Some((bundle, asset_path)) => (
+            AddonDashboardBundleAssetModel::find_one_by_bundle_id_and_path(
+                bundle.id, asset_path, &mut acq,
+            )
+            .await?,
+            true,
+        ),
+
+        None => {
+            let bundle =
+                match AddonDashboardBundleModel::find_one_newest_by_addon_id(addon.id, &mut acq)
+                    .await?
+                {
+                    Some(v) => Some(v),
+                    None => {
+                        AddonDashboardBundleModel::find_one_draft_by_addon_id(addon.id, &mut acq)
+                            .await?
+                    }
+                };
+
+            let asset = match bundle {
+                Some(bundle) => {
+                    AddonDashboardBundleAssetModel::find_one_by_bundle_id_and_path(
+                        bundle.id,
+                        &bundle.entry,
+                        &mut acq,
+                    )
+                    .await?
+                }
+                None => None,
+            };
+
+            (asset, false)
+        }
+    };
+
+    drop(acq);
+
+    let Some(asset) = asset else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let etag = format!("\"{}\"", asset.hash);
+    let cache_control = if is_immutable {
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    };
+
+    let is_unchanged = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|v| v.trim() == etag || v.trim() == "*"));
+
+    let resp = Response::builder()
+        .header(header::ETAG, HeaderValue::from_str(&etag).unwrap())
+        .header(header::CACHE_CONTROL, cache_control);
+
+    if is_unchanged {
+        return Ok(resp
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Default::default())
+            .unwrap());
+    }
+
+    let contents = storage.download(&asset.file_id).await?;
+
+    Ok(resp
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, asset.content_type)
+        .body(contents.into())
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_hash_ignores_order() {
+        let a = hash_bundle([("index.js", "aa"), ("assets/style.css", "bb")]);
+        let b = hash_bundle([("assets/style.css", "bb"), ("index.js", "aa")]);
+
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            hash_bundle([("index.js", "aa"), ("assets/style.css", "cc")])
+        );
+        assert_ne!(
+            a,
+            hash_bundle([("index.js", "aa"), ("assets/main.css", "bb")])
+        );
+    }
+
+    #[test]
+    fn only_relative_asset_paths() {
+        for valid in [
+            "index.js",
+            "assets/index-4f1a.js",
+            "fonts/@inter/regular.woff2",
+        ] {
+            assert!(validate_asset_path(valid).is_ok(), "{valid}");
+        }
+
+        for invalid in [
+            "",
+            "/index.js",
+            "../index.js",
+            "assets//index.js",
+            "assets/./index.js",
+            "a b.js",
+        ] {
+            assert!(validate_asset_path(invalid).is_err(), "{invalid}");
+        }
+    }
+}
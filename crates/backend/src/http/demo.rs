@@ -0,0 +1,412 @@
+//! Previews of an addon for members who haven't installed it yet.
+//!
+//! Developers list demo websites and screenshots linking to them. Members can also spin up a
+//! sandbox, an ephemeral instance of the latest published version on a placeholder website, which
+//! is deleted once it expires.
+
+use std::{collections::HashMap, time::Duration};
+
+use axum::{
+    extract::{self, Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use database::{
+    AddonCapability, AddonCompiledModel, AddonCompiledPage, AddonDemoModel, AddonDemoSandboxModel,
+    AddonInstanceModel, AddonModel, MediaUploadModel, NewAddonDemoModel, NewAddonDemoSandboxModel,
+    NewAddonInstanceModel,
+};
+use eyre::ContextCompat;
+use local_common::upload::{get_public_file_url, StorageService};
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+use webby_global_common::id::{AddonInstanceUuid, AddonUuid};
+
+use crate::Result;
+
+use super::{addon::PublicPage, auth::AuthMember, upload_file};
+
+const MAX_DEMOS: usize = 10;
+const MAX_TITLE_LENGTH: usize = 100;
+/// Sandboxes a member can have at once.
+const MAX_ACTIVE_SANDBOXES: i64 = 3;
+const SANDBOX_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route("/", get(get_demos).post(new_demo))
+        .route("/:demo", post(update_demo).delete(delete_demo))
+        .route("/:demo/screenshot", post(upload_demo_screenshot))
+        .route("/sandbox", post(new_sandbox))
+        .route("/sandbox/:instance", get(get_sandbox))
+}
+
+/// Removes expired sandboxes along with their instances in the background.
+pub fn spawn_sandbox_sweeper(pool: SqlitePool) {
+    tokio::spawn(async move {
+        loop {
+            match sweep_expired_sandboxes(&pool).await {
+                Ok(0) => (),
+                Ok(count) => debug!("Removed {count} expired sandboxes"),
+                Err(e) => error!("Sandbox Sweep Error: {e}"),
+            }
+
+            tokio::time::sleep(SANDBOX_SWEEP_INTERVAL).await;
+        }
+    });
+}
+
+pub async fn sweep_expired_sandboxes(db: &SqlitePool) -> Result<u64> {
+    Ok(AddonDemoSandboxModel::delete_expired(&mut *db.acquire().await?).await?)
+}
+
+async fn find_addon(addon_id: Uuid, db: &mut SqliteConnection) -> Result<AddonModel> {
+    Ok(AddonModel::find_one_by_guid(addon_id, db)
+        .await?
+        .filter(|v| v.deleted_at.is_none())
+        .context("Addon not found")?)
+}
+
+async fn find_demo(
+    addon: &AddonModel,
+    demo_id: Uuid,
+    db: &mut SqliteConnection,
+) -> Result<AddonDemoModel> {
+    Ok(AddonDemoModel::find_one_by_public_id(addon.id, demo_id, db)
+        .await?
+        .context("Demo not found")?)
+}
+
+fn validate(title: &str, url: &str) -> Result<()> {
+    if title.trim().is_empty() {
+        return Err(eyre::eyre!("Title can't be empty"))?;
+    }
+
+    if title.len() > MAX_TITLE_LENGTH {
+        return Err(eyre::eyre!(
+            "Title can't be longer than {MAX_TITLE_LENGTH} characters"
+        ))?;
+    }
+
+    let url = url::Url::parse(url)?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(eyre::eyre!("Demo url must be http or https"))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoResponse {
+    #[serde(flatten)]
+    pub demo: AddonDemoModel,
+    pub screenshot: Option<String>,
+}
+
+/// Resolves the screenshots of every demo at once.
+async fn into_responses(
+    demos: Vec<AddonDemoModel>,
+    db: &mut SqliteConnection,
+) -> Result<Vec<DemoResponse>> {
+    let media_ids = demos.iter().filter_map(|v| v.media_id).collect::<Vec<_>>();
+
+    let screenshots = if media_ids.is_empty() {
+        HashMap::new()
+    } else {
+        MediaUploadModel::find_by_ids(media_ids, db)
+            .await?
+            .into_iter()
+            .filter(|v| v.deleted_at.is_none())
+            .map(|v| (v.id, get_public_file_url(&v.store_path)))
+            .collect::<HashMap<_, _>>()
+    };
+
+    Ok(demos
+        .into_iter()
+        .map(|demo| DemoResponse {
+            screenshot: demo.media_id.and_then(|v| screenshots.get(&v).cloned()),
+            demo,
+        })
+        .collect())
+}
+
+async fn get_demos(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+) -> Result<JsonResponse<Vec<DemoResponse>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(addon_id, &mut acq).await?;
+
+    let demos = AddonDemoModel::find_by_addon_id(addon.id, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(
+        into_responses(demos, &mut acq).await?,
+    )))
+}
+
+#[derive(Deserialize)]
+pub struct NewDemoJson {
+    pub title: String,
+    pub url: String,
+}
+
+async fn new_demo(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<NewDemoJson>,
+) -> Result<JsonResponse<AddonDemoModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditDesign, &mut acq)
+        .await?;
+
+    validate(&value.title, &value.url)?;
+
+    if AddonDemoModel::find_by_addon_id(addon.id, &mut acq)
+        .await?
+        .len()
+        >= MAX_DEMOS
+    {
+        return Err(eyre::eyre!(
+            "An addon can't have more than {MAX_DEMOS} demos"
+        ))?;
+    }
+
+    let demo = NewAddonDemoModel {
+        addon_id: addon.id,
+        title: value.title.trim().to_string(),
+        url: value.url,
+        media_id: None,
+    }
+    .insert(&mut acq)
+    .await?;
+
+    Ok(Json(WrappingResponse::okay(demo)))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateDemoJson {
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub idx: Option<i32>,
+}
+
+async fn update_demo(
+    Path((addon_id, demo_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<UpdateDemoJson>,
+) -> Result<JsonResponse<AddonDemoModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditDesign, &mut acq)
+        .await?;
+
+    let mut demo = find_demo(&addon, demo_id, &mut acq).await?;
+
+    if let Some(title) = value.title {
+        demo.title = title.trim().to_string();
+    }
+
+    if let Some(url) = value.url {
+        demo.url = url;
+    }
+
+    if let Some(idx) = value.idx {
+        demo.idx = idx;
+    }
+
+    validate(&demo.title, &demo.url)?;
+
+    demo.update(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(demo)))
+}
+
+async fn delete_demo(
+    Path((addon_id, demo_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditDesign, &mut acq)
+        .await?;
+
+    find_demo(&addon, demo_id, &mut acq)
+        .await?
+        .delete(&mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+async fn upload_demo_screenshot(
+    Path((addon_id, demo_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    storage: StorageService,
+    mut multipart: extract::Multipart,
+) -> Result<JsonResponse<Option<&'static str>>> {
+    let (addon, mut demo) = {
+        let mut acq = db.acquire().await?;
+
+        let addon = member
+            .find_owned_addon(addon_id, AddonCapability::EditDesign, &mut acq)
+            .await?;
+
+        let demo = find_demo(&addon, demo_id, &mut acq).await?;
+
+        (addon, demo)
+    };
+
+    if let Some(field) = multipart.next_field().await? {
+        if let Some(model) = upload_file(field, addon.member_id, None, &storage, &db).await? {
+            demo.media_id = Some(model.id);
+            demo.update(&mut *db.acquire().await?).await?;
+
+            return Ok(Json(WrappingResponse::okay(Some("ok"))));
+        }
+    }
+
+    Ok(Json(WrappingResponse::okay(None)))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxResponse {
+    pub instance_id: AddonInstanceUuid,
+    /// The placeholder website the instance is installed on.
+    pub website_id: Uuid,
+    pub version: String,
+    pub expires_at: OffsetDateTime,
+    /// The template pages of the version.
+    pub pages: Vec<PublicPage>,
+}
+
+async fn sandbox_response(
+    addon_uuid: AddonUuid,
+    instance: AddonInstanceModel,
+    sandbox: AddonDemoSandboxModel,
+    compiled: &AddonCompiledModel,
+    db: &mut SqliteConnection,
+) -> Result<SandboxResponse> {
+    let pages = AddonCompiledPage::find_by_compiled_id(compiled.pk, db).await?;
+
+    Ok(SandboxResponse {
+        instance_id: instance.public_id,
+        website_id: instance.website_uuid,
+        version: instance.version,
+        expires_at: sandbox.expires_at,
+        pages: pages
+            .into_iter()
+            .map(|p| PublicPage::new(p, addon_uuid))
+            .collect(),
+    })
+}
+
+/// Installs the latest published version on a placeholder website.
+async fn new_sandbox(
+    Path(addon_uuid): Path<AddonUuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<SandboxResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(*addon_uuid, &mut acq).await?;
+
+    // Hidden addons can only be previewed by the people working on them.
+    if !addon.is_visible {
+        member
+            .addon_access_error(&addon, AddonCapability::View, &mut acq)
+            .await?;
+    }
+
+    if AddonDemoSandboxModel::count_active_by_member_uuid(member.uuid(), &mut acq).await?
+        >= MAX_ACTIVE_SANDBOXES
+    {
+        return Err(eyre::eyre!(
+            "You can't have more than {MAX_ACTIVE_SANDBOXES} active sandboxes"
+        ))?;
+    }
+
+    let compiled = AddonCompiledModel::find_latest_published(addon.id, &mut acq)
+        .await?
+        .context("Addon hasn't been published")?;
+
+    let addon_id = addon.id;
+    let member_uuid = member.uuid();
+    let version = compiled.version.clone();
+
+    let (instance, sandbox) = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                let website_id = AddonDemoSandboxModel::next_placeholder_website_id(trx).await?;
+
+                let instance = NewAddonInstanceModel {
+                    addon_id,
+                    website_id,
+                    website_uuid: Uuid::now_v7(),
+                    version,
+                }
+                .insert(trx)
+                .await?;
+
+                let sandbox = NewAddonDemoSandboxModel {
+                    instance_id: instance.id,
+                    member_uuid,
+                }
+                .insert(trx)
+                .await?;
+
+                Result::<_>::Ok((instance, sandbox))
+            })
+        })
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        sandbox_response(addon_uuid, instance, sandbox, &compiled, &mut acq).await?,
+    )))
+}
+
+async fn get_sandbox(
+    Path((addon_uuid, instance_id)): Path<(AddonUuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<SandboxResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(*addon_uuid, &mut acq).await?;
+
+    let instance = AddonInstanceModel::find_by_uuid(instance_id, &mut acq)
+        .await?
+        .filter(|v| v.addon_id == addon.id)
+        .context("Sandbox not found")?;
+
+    let sandbox = AddonDemoSandboxModel::find_one_by_instance_id(instance.id, &mut acq)
+        .await?
+        .filter(|v| v.member_uuid == member.uuid() && !v.is_expired())
+        .context("Sandbox not found")?;
+
+    let compiled = AddonCompiledModel::find_one_by_addon_uuid_and_version(
+        addon.id,
+        &instance.version,
+        &mut acq,
+    )
+    .await?
+    .context("Sandbox version no longer exists")?;
+
+    Ok(Json(WrappingResponse::okay(
+        sandbox_response(addon_uuid, instance, sandbox, &compiled, &mut acq).await?,
+    )))
+}
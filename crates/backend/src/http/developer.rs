@@ -0,0 +1,232 @@
+//! Developer profiles which addons are published under.
+//!
+//! Each member can create a single profile. Creating it links the addons the member already made.
+
+use axum::{
+    extract::{self, Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use database::{AddonModel, DeveloperModel, MediaUploadModel, NewDeveloperModel};
+use eyre::ContextCompat;
+use local_common::{
+    api::{AddonExtendedPublic, DeveloperPublic},
+    upload::{get_public_file_url, StorageService},
+    MediaId,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+
+use crate::Result;
+
+use super::{auth::AuthMember, extend_addons, upload_file};
+
+const MAX_NAME_LENGTH: usize = 64;
+const MAX_DESCRIPTION_LENGTH: usize = 5_000;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route("/", get(get_own_developer).post(new_developer))
+        .route(
+            "/:developer",
+            get(get_developer_page).post(update_developer),
+        )
+        .route("/:developer/icon", post(upload_developer_icon))
+}
+
+fn validate(name: &str, description: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(eyre::eyre!("Name can't be empty"))?;
+    }
+
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(eyre::eyre!(
+            "Name can't be longer than {MAX_NAME_LENGTH} characters"
+        ))?;
+    }
+
+    if description.len() > MAX_DESCRIPTION_LENGTH {
+        return Err(eyre::eyre!(
+            "Description can't be longer than {MAX_DESCRIPTION_LENGTH} characters"
+        ))?;
+    }
+
+    Ok(())
+}
+
+async fn find_icon_url(icon: Option<MediaId>, db: &mut SqliteConnection) -> Result<Option<String>> {
+    let Some(icon) = icon else {
+        return Ok(None);
+    };
+
+    Ok(MediaUploadModel::find_one_by_id(icon, db)
+        .await?
+        .filter(|v| v.deleted_at.is_none())
+        .map(|v| get_public_file_url(&v.store_path)))
+}
+
+async fn into_public(
+    developer: DeveloperModel,
+    db: &mut SqliteConnection,
+) -> Result<DeveloperPublic> {
+    let icon = find_icon_url(developer.icon, db).await?;
+
+    Ok(developer.into_public(icon))
+}
+
+/// The developer owned by the member, if it's not deleted.
+async fn find_owned_developer(
+    developer_id: Uuid,
+    member: &AuthMember,
+    db: &mut SqliteConnection,
+) -> Result<DeveloperModel> {
+    let developer = DeveloperModel::find_one_by_guid(developer_id, db)
+        .await?
+        .filter(|v| v.deleted_at.is_none())
+        .context("Developer not found")?;
+
+    if developer.member_uuid != member.uuid() {
+        return Err(crate::Error::Forbidden);
+    }
+
+    Ok(developer)
+}
+
+async fn get_own_developer(
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Option<DeveloperPublic>>> {
+    let mut acq = db.acquire().await?;
+
+    let developer = match DeveloperModel::find_one_by_member_uuid(member.uuid(), &mut acq).await? {
+        Some(developer) => Some(into_public(developer, &mut acq).await?),
+        None => None,
+    };
+
+    Ok(Json(WrappingResponse::okay(developer)))
+}
+
+#[derive(Deserialize)]
+pub struct NewDeveloperJson {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+async fn new_developer(
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<NewDeveloperJson>,
+) -> Result<JsonResponse<DeveloperPublic>> {
+    let mut acq = db.acquire().await?;
+
+    validate(&value.name, &value.description)?;
+
+    if DeveloperModel::find_one_by_member_uuid(member.uuid(), &mut acq)
+        .await?
+        .is_some()
+    {
+        return Err(eyre::eyre!("You already have a developer profile"))?;
+    }
+
+    let mut developer = NewDeveloperModel {
+        member_id: member.pk(),
+        member_uuid: member.uuid(),
+        name: value.name.trim().to_string(),
+        description: value.description,
+    }
+    .insert(&mut acq)
+    .await?;
+
+    developer.claim_member_addons(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(
+        into_public(developer, &mut acq).await?,
+    )))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeveloperPageResponse {
+    pub developer: DeveloperPublic,
+    pub addons: Vec<AddonExtendedPublic>,
+}
+
+/// The developer with their visible addons.
+async fn get_developer_page(
+    Path(developer_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+) -> Result<JsonResponse<DeveloperPageResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let developer = DeveloperModel::find_one_by_guid(developer_id, &mut acq)
+        .await?
+        .filter(|v| v.deleted_at.is_none())
+        .context("Developer not found")?;
+
+    let addons = AddonModel::find_visible_by_developer_id(developer.id, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(DeveloperPageResponse {
+        addons: extend_addons(addons, &mut acq).await?,
+        developer: into_public(developer, &mut acq).await?,
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateDeveloperJson {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+async fn update_developer(
+    Path(developer_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<UpdateDeveloperJson>,
+) -> Result<JsonResponse<DeveloperPublic>> {
+    let mut acq = db.acquire().await?;
+
+    let mut developer = find_owned_developer(developer_id, &member, &mut acq).await?;
+
+    if let Some(name) = value.name {
+        developer.name = name.trim().to_string();
+    }
+
+    if let Some(description) = value.description {
+        developer.description = description;
+    }
+
+    validate(&developer.name, &developer.description)?;
+
+    developer.update(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(
+        into_public(developer, &mut acq).await?,
+    )))
+}
+
+async fn upload_developer_icon(
+    Path(developer_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    storage: StorageService,
+    mut multipart: extract::Multipart,
+) -> Result<JsonResponse<Option<&'static str>>> {
+    let mut developer =
+        find_owned_developer(developer_id, &member, &mut *db.acquire().await?).await?;
+
+    if let Some(field) = multipart.next_field().await? {
+        if let Some(model) =
+            upload_file(field, member.pk(), Some((200, 200)), &storage, &db).await?
+        {
+            developer.icon = Some(model.id);
+            developer.update(&mut *db.acquire().await?).await?;
+
+            return Ok(Json(WrappingResponse::okay(Some("ok"))));
+        }
+    }
+
+    Ok(Json(WrappingResponse::okay(None)))
+}
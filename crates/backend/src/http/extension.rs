@@ -0,0 +1,181 @@
+//! The extension manifest declares what an addon extends.
+//!
+//! The manifest being edited is copied into the compiled version on publish. Addons can only
+//! create and ship the items they declared.
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use database::{
+    AddonCapability, AddonCompiledPage, AddonCompiledWidget, AddonDashboardPage,
+    AddonExtensionModel, AddonSitePluginModel, AddonTemplatePageModel, AddonWidgetContent,
+    ExtensionKind, ExtensionUsage, NewAddonExtensionModel, SchemaModel,
+};
+use local_common::{AddonCompiledId, AddonId};
+use serde::Deserialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+
+use crate::Result;
+
+use super::auth::AuthMember;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new().route("/", get(get_extension).post(update_extension))
+}
+
+/// Errors if the manifest being edited doesn't declare the kind.
+pub async fn require_extension(
+    addon_id: AddonId,
+    kind: ExtensionKind,
+    db: &mut SqliteConnection,
+) -> Result<()> {
+    let declared = AddonExtensionModel::find_one_draft_by_addon_id(addon_id, db)
+        .await?
+        .is_some_and(|v| v.declares(kind));
+
+    if declared {
+        Ok(())
+    } else {
+        Err(eyre::eyre!(
+            "Addon Extension Manifest doesn't declare \"{}\"",
+            kind.as_str()
+        ))?
+    }
+}
+
+/// Errors with the kinds which are used but not declared.
+pub fn verify_extension_usage(extends: &[ExtensionKind], usage: ExtensionUsage) -> Result<()> {
+    let undeclared = usage.undeclared(extends);
+
+    if undeclared.is_empty() {
+        Ok(())
+    } else {
+        Err(eyre::eyre!(
+            "Addon Extension Manifest doesn't declare: {}",
+            undeclared
+                .into_iter()
+                .map(|v| v.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))?
+    }
+}
+
+/// What the addon currently has, before it's published.
+pub async fn find_draft_usage(
+    addon_id: AddonId,
+    db: &mut SqliteConnection,
+) -> Result<ExtensionUsage> {
+    Ok(ExtensionUsage {
+        widgets: AddonWidgetContent::get_all_no_data(addon_id, db)
+            .await?
+            .len(),
+        site_pages: AddonTemplatePageModel::count_by_addon_id(addon_id, db).await? as usize,
+        dashboard_pages: AddonDashboardPage::find_by_id(addon_id, db).await?.len(),
+        cms_collections: SchemaModel::find_by_addon_id(addon_id, db).await?.len(),
+        site_plugins: AddonSitePluginModel::find_drafts_by_addon_id(addon_id, db)
+            .await?
+            .len(),
+    })
+}
+
+/// What was shipped in the compiled version.
+///
+/// Dashboard pages and CMS collections come from the counts taken when it was published. Versions
+/// published before those were stored fall back to what the addon currently has.
+pub async fn find_compiled_usage(
+    addon_id: AddonId,
+    compiled_id: AddonCompiledId,
+    db: &mut SqliteConnection,
+) -> Result<ExtensionUsage> {
+    let snapshot = AddonExtensionModel::find_one_by_compiled_id(compiled_id, db).await?;
+
+    let dashboard_pages = match snapshot.as_ref().and_then(|v| v.dashboard_page_count) {
+        Some(count) => count as usize,
+        None => AddonDashboardPage::find_by_id(addon_id, db).await?.len(),
+    };
+
+    let cms_collections = match snapshot.as_ref().and_then(|v| v.cms_collection_count) {
+        Some(count) => count as usize,
+        None => SchemaModel::find_by_addon_id(addon_id, db).await?.len(),
+    };
+
+    Ok(ExtensionUsage {
+        widgets: AddonCompiledWidget::find_by_compiled_id(compiled_id, db)
+            .await?
+            .len(),
+        site_pages: AddonCompiledPage::find_by_compiled_id(compiled_id, db)
+            .await?
+            .len(),
+        dashboard_pages,
+        cms_collections,
+        site_plugins: AddonSitePluginModel::find_by_compiled_id(compiled_id, db)
+            .await?
+            .len(),
+    })
+}
+
+async fn get_extension(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Option<AddonExtensionModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        AddonExtensionModel::find_one_draft_by_addon_id(addon.id, &mut acq).await?,
+    )))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateExtensionJson {
+    pub extends: Vec<ExtensionKind>,
+}
+
+async fn update_extension(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(UpdateExtensionJson { extends }): Json<UpdateExtensionJson>,
+) -> Result<JsonResponse<AddonExtensionModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    // Can't remove a kind while the addon still has items of it.
+    verify_extension_usage(&extends, find_draft_usage(addon.id, &mut acq).await?)?;
+
+    let extension =
+        match AddonExtensionModel::find_one_draft_by_addon_id(addon.id, &mut acq).await? {
+            Some(mut extension) => {
+                extension.extends.0 = extends;
+                extension.update(&mut acq).await?;
+
+                extension
+            }
+
+            None => {
+                NewAddonExtensionModel {
+                    addon_id: addon.id,
+                    compiled_id: None,
+                    extends,
+                    dashboard_page_count: None,
+                    cms_collection_count: None,
+                }
+                .insert(&mut acq)
+                .await?
+            }
+        };
+
+    Ok(Json(WrappingResponse::okay(extension)))
+}
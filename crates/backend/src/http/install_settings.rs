@@ -0,0 +1,267 @@
+//! The settings an addon instance is configured with when installed on a website.
+//!
+//! The schema being edited is copied into the compiled version on publish. Instances are validated
+//! against the schema of the version they're on.
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use database::{
+    resolve_install_settings, validate_install_settings, AddonCapability, AddonCompiledModel,
+    AddonInstallSettingsModel, AddonInstanceModel, InstallSettingField,
+    NewAddonInstallSettingsModel,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+
+use crate::Result;
+
+use super::auth::AuthMember;
+
+const MAX_FIELDS: usize = 50;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new().route("/", get(get_install_settings).post(update_install_settings))
+}
+
+/// The instance settings merged over the defaults of its' version.
+///
+/// Versions published before install settings existed accept any settings, as do instances whose
+/// version was never compiled.
+pub async fn resolve_instance_settings(
+    instance: &AddonInstanceModel,
+    overrides: &Map<String, Value>,
+    db: &mut SqliteConnection,
+) -> Result<Map<String, Value>> {
+    let Some(compiled) = AddonCompiledModel::find_one_by_addon_uuid_and_version(
+        instance.addon_id,
+        &instance.version,
+        db,
+    )
+    .await?
+    else {
+        return Ok(overrides.clone());
+    };
+
+    match AddonInstallSettingsModel::find_one_by_compiled_id(compiled.pk, db).await? {
+        Some(schema) => Ok(schema.resolve(overrides)?),
+        None => Ok(overrides.clone()),
+    }
+}
+
+/// Moves the settings of the instance onto the schema of the version it's upgrading to.
+///
+/// Settings the version removed are dropped. Errors if what's left doesn't fit the new schema, e.g. a
+/// new required setting without a default.
+pub async fn migrate_instance_settings(
+    instance: &mut AddonInstanceModel,
+    compiled: &AddonCompiledModel,
+    db: &mut SqliteConnection,
+) -> Result<()> {
+    let Some(schema) = AddonInstallSettingsModel::find_one_by_compiled_id(compiled.pk, db).await?
+    else {
+        return Ok(());
+    };
+
+    let overrides = match instance.settings.as_ref().map(|v| &v.0) {
+        Some(Value::Object(map)) => map.clone(),
+        _ => Map::new(),
+    };
+
+    let migrated = migrate_settings(&schema.fields, overrides)?;
+
+    if instance.settings.is_some() {
+        instance.settings = Some(sqlx::types::Json(Value::Object(migrated)));
+    }
+
+    Ok(())
+}
+
+fn migrate_settings(
+    fields: &[InstallSettingField],
+    mut overrides: Map<String, Value>,
+) -> Result<Map<String, Value>> {
+    overrides.retain(|name, _| fields.iter().any(|v| &v.name == name));
+
+    if let Err(e) = resolve_install_settings(fields, &overrides) {
+        return Err(eyre::eyre!("Settings don't fit the new version. {e}"))?;
+    }
+
+    Ok(overrides)
+}
+
+async fn get_install_settings(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Option<AddonInstallSettingsModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        AddonInstallSettingsModel::find_one_draft_by_addon_id(addon.id, &mut acq).await?,
+    )))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateInstallSettingsJson {
+    pub fields: Vec<InstallSettingField>,
+}
+
+async fn update_install_settings(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(UpdateInstallSettingsJson { fields }): Json<UpdateInstallSettingsJson>,
+) -> Result<JsonResponse<AddonInstallSettingsModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    if fields.len() > MAX_FIELDS {
+        return Err(eyre::eyre!(
+            "An addon can't have more than {MAX_FIELDS} install settings"
+        ))?;
+    }
+
+    validate_install_settings(&fields)?;
+
+    let settings =
+        match AddonInstallSettingsModel::find_one_draft_by_addon_id(addon.id, &mut acq).await? {
+            Some(mut settings) => {
+                settings.fields.0 = fields;
+                settings.update(&mut acq).await?;
+
+                settings
+            }
+
+            None => {
+                NewAddonInstallSettingsModel {
+                    addon_id: addon.id,
+                    compiled_id: None,
+                    fields,
+                }
+                .insert(&mut acq)
+                .await?
+            }
+        };
+
+    Ok(Json(WrappingResponse::okay(settings)))
+}
+
+#[cfg(test)]
+mod tests {
+    use database::InstallSettingType;
+    use serde_json::json;
+
+    use super::*;
+
+    fn fields() -> Vec<InstallSettingField> {
+        serde_json::from_value(json!([
+            { "name": "title", "type": "text", "maxLength": 10, "default": "Hello" },
+            { "name": "count", "type": "number", "min": 1, "max": 5, "required": true },
+            { "name": "layout", "type": "enum", "options": ["grid", "list"], "default": "grid" },
+            { "name": "dark", "type": "boolean" }
+        ]))
+        .unwrap()
+    }
+
+    fn map(value: Value) -> Map<String, Value> {
+        let Value::Object(map) = value else {
+            panic!("Expected Map");
+        };
+
+        map
+    }
+
+    #[test]
+    fn parses_declared_fields() {
+        let fields = fields();
+
+        assert_eq!(
+            fields[0].type_of,
+            InstallSettingType::Text {
+                max_length: Some(10)
+            }
+        );
+        assert!(fields[1].required);
+        assert!(validate_install_settings(&fields).is_ok());
+    }
+
+    #[test]
+    fn merges_overrides_over_defaults() {
+        let resolved =
+            resolve_install_settings(&fields(), &map(json!({ "count": 3, "layout": "list" })))
+                .unwrap();
+
+        assert_eq!(
+            Value::Object(resolved),
+            json!({ "title": "Hello", "count": 3, "layout": "list" })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let fields = fields();
+
+        // Missing required
+        assert!(resolve_install_settings(&fields, &Map::new()).is_err());
+        // Unknown setting
+        assert!(
+            resolve_install_settings(&fields, &map(json!({ "count": 1, "other": 1 }))).is_err()
+        );
+        // Out of range
+        assert!(resolve_install_settings(&fields, &map(json!({ "count": 9 }))).is_err());
+        // Not an option
+        assert!(
+            resolve_install_settings(&fields, &map(json!({ "count": 1, "layout": "row" })))
+                .is_err()
+        );
+        // Wrong type
+        assert!(
+            resolve_install_settings(&fields, &map(json!({ "count": 1, "dark": "yes" }))).is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_schema() {
+        let mut fields = fields();
+
+        fields.push(fields[0].clone());
+        assert!(validate_install_settings(&fields).is_err());
+
+        let fields: Vec<InstallSettingField> = serde_json::from_value(json!([
+            { "name": "layout", "type": "enum", "options": ["grid"], "default": "list" }
+        ]))
+        .unwrap();
+        assert!(validate_install_settings(&fields).is_err());
+    }
+
+    #[test]
+    fn migrates_settings_to_new_schema() {
+        let fields = fields();
+
+        // Removed settings are dropped.
+        assert_eq!(
+            Value::Object(
+                migrate_settings(&fields, map(json!({ "count": 2, "removed": true }))).unwrap()
+            ),
+            json!({ "count": 2 })
+        );
+
+        // A new required setting without a value.
+        assert!(migrate_settings(&fields, map(json!({ "title": "Hi" }))).is_err());
+        // No longer a valid value.
+        assert!(migrate_settings(&fields, map(json!({ "count": 8 }))).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     collections::{HashMap, HashSet},
     net::SocketAddr,
+    sync::Arc,
 };
 
 use axum::{
@@ -12,26 +13,31 @@ use axum::{
     Extension, Router,
 };
 use database::{
-    AddonDashboardPage, AddonInstanceModel, AddonModel, AddonPermissionModel,
-    AddonTemplatePageContentModel, AddonTemplatePageModel, MediaUploadModel, NewAddonMediaModel,
-    NewAddonModel, NewMediaUploadModel, NewSchemaDataModel, NewSchemaModel, SchemaDataFieldUpdate,
-    SchemaDataModel, SchemaDataTagModel, SchemaModel,
+    find_missing_permissions, AddonCapability, AddonCollaboratorModel, AddonCompiledModel,
+    AddonDashboardPage, AddonInstallSessionModel, AddonInstanceGrantModel, AddonInstanceModel,
+    AddonMediaModel, AddonMediaType, AddonModel, AddonOAuthTokenModel, AddonPermissionModel,
+    AddonRole, AddonSearch, AddonSort, AddonTagModel, AddonTemplatePageContentModel,
+    AddonTemplatePageModel, AddonWidgetContent, CmsRowEvent, DeveloperModel, ExtensionKind,
+    MediaUploadModel, NewAddonExtensionModel, NewAddonMediaModel, NewAddonModel,
+    NewMediaUploadModel, NewSchemaDataModel, NewSchemaModel, SchemaDataFieldUpdate,
+    SchemaDataModel, SchemaDataTagModel, SchemaModel, SubscriptionStatus, TagFacet, TagModel,
+    WebhookEvent, WebsiteWidgetSettingsModel,
 };
 use eyre::{Context, ContextCompat};
 use futures::TryStreamExt;
-use hyper::header::CONTENT_TYPE;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
 use lazy_static::lazy_static;
 use local_common::{
-    api::AddonPublic,
+    api::{AddonExtendedPublic, AddonPublic, PermissionPublic, TagPublic},
     generate::generate_file_name,
     upload::{
-        get_full_file_path, get_next_uploading_file_path, get_thumb_file_path,
+        get_full_file_path, get_next_uploading_file_path, get_public_file_url, get_thumb_file_path,
         read_and_upload_data, register_b2, StorageService,
     },
     AddonId, DashboardPageInfo, MemberId,
 };
 use mime_guess::mime::APPLICATION_JSON;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_qs::axum::QsQuery;
 use sha2::{Digest, Sha256};
 use sqlx::{Connection, Pool, Sqlite, SqliteConnection, SqlitePool};
@@ -57,8 +63,32 @@ use webby_storage::DisplayStore;
 
 use crate::Result;
 
+use self::{
+    auth::{AuthMember, SharedIdentityProvider, WebbyIdentityProvider},
+    automation::{spawn_automation_event, AutomationEvent},
+    billing::{SharedBillingProvider, StripeBillingProvider},
+    extension::require_extension,
+    webhook::{queue_cms_row_event, queue_webhook_event},
+};
+
 mod addon;
+mod auth;
+mod automation;
+mod billing;
+mod cms_export;
+mod cms_import;
+mod collaborator;
+mod dashboard;
+mod demo;
+mod developer;
+mod extension;
+mod install_settings;
+mod oauth;
+mod permission;
+mod review;
+mod site_plugin;
 mod vissl;
+mod webhook;
 mod website;
 
 lazy_static! {
@@ -72,70 +102,23 @@ pub async fn serve(pool: Pool<Sqlite>) -> Result<()> {
     debug!("addons listening on {addr}");
 
     let uploader = register_b2().await;
+    let identity: SharedIdentityProvider = Arc::new(WebbyIdentityProvider::from_env());
+    let billing: SharedBillingProvider = Arc::new(StripeBillingProvider::from_env());
+
+    webhook::spawn_delivery_worker(pool.clone());
+    automation::spawn_automation_scheduler(pool.clone());
+    billing::spawn_subscription_sync(pool.clone(), billing.clone());
+    demo::spawn_sandbox_sweeper(pool.clone());
 
     let listener = TcpListener::bind(addr).await.unwrap();
 
     axum::serve(
         listener,
-        Router::new()
-            // API Passthrough
-            .route("/_api/:addon_id/*O", any(handle_api))
-            .route("/list-active/:website", get(get_active_addon_list))
-            .route("/dashboard-pages/:website", get(get_dashboard_pages))
-            .route("/list", get(get_addon_list))
-            // Update Addon Instance
-            .route("/instance/:guid", post(post_addon_instance))
-            // Addon
-            .route("/addon", post(new_addon))
-            .route("/addon/:guid", get(get_addon_public))
-            // Get Website Addon Instance info
-            .route("/addon/:guid/instance/:website", get(get_addon_instance))
-            // Get dashboard page
-            .route("/addon/:guid/dashboard/*O", get(get_addon_dashboard_page))
-            .route("/addon/:guid/icon", post(upload_icon))
-            .route("/addon/:guid/gallery", post(upload_gallery_item))
-            .route("/addon/:guid/template/data", get(get_all_template_data))
-            .route(
-                "/addon/:guid/template/:template",
-                get(get_template_page_data).post(update_template_page_data),
-            )
-            // Private
-            .route("/addon/:guid/access/:user", get(get_addon_member_access))
-            .route("/addon/:guid/schemas", get(get_addon_schemas))
-            .route("/addon/:guid/schema/new", post(new_cms_collection))
-            .route(
-                "/addon/:guid/schema/:name",
-                get(get_cms_info).post(update_cms),
-            )
-            .route("/addon/:guid/schema/:name/query", get(get_cms_query))
-            .route(
-                "/addon/:guid/schema/:name/column",
-                post(create_new_data_column),
-            )
-            .route(
-                "/addon/:guid/schema/:name/column/:col_id",
-                delete(delete_data_column),
-            )
-            .route(
-                "/addon/:guid/schema/:name/column/:col_id/tag",
-                post(add_data_column_tag),
-            )
-            .route("/addon/:guid/schema/:name/row", post(create_new_data_row))
-            .route("/addon/:guid/schema/:name/import", post(import_data_rows))
-            .route(
-                "/addon/:guid/schema/:name/row/:row_id",
-                get(get_cms_row).post(update_cms_row_cell),
-            )
-            .route(
-                "/addon/:guid/schema/:name/row/:row_id/duplicate",
-                post(duplicate_cms_row_cell),
-            )
-            //
-            .nest("/addon/:guid/vissl", vissl::routes())
-            .nest("/website/:website_id", website::routes())
-            .nest("/addon/:addon_id", addon::routes())
+        routes()
             .layer(TraceLayer::new_for_http())
             .layer(Extension(uploader.clone()))
+            .layer(Extension(identity))
+            .layer(Extension(billing))
             .with_state(pool),
     )
     .await?;
@@ -143,6 +126,151 @@ pub async fn serve(pool: Pool<Sqlite>) -> Result<()> {
     Ok(())
 }
 
+fn routes() -> Router<SqlitePool> {
+    Router::new()
+        // API Passthrough
+        .route("/_api/:addon_id/*O", any(handle_api))
+        .route("/list-active/:website", get(get_active_addon_list))
+        .route(
+            "/site-plugins/:website",
+            get(site_plugin::get_website_site_plugins),
+        )
+        .route("/dashboard-pages/:website", get(get_dashboard_pages))
+        .route("/list", get(get_addon_list))
+        .route("/invites", get(collaborator::get_member_invites))
+        // Update Addon Instance
+        .route("/instance/:guid", post(post_addon_instance))
+        // Addon
+        .route("/addon", post(new_addon))
+        .route(
+            "/addon/:guid",
+            get(get_addon_public)
+                .post(update_addon)
+                .delete(delete_addon),
+        )
+        .route("/tags/categories", get(get_tag_categories))
+        .route("/permissions", get(permission::get_known_permissions))
+        // Get Website Addon Instance info
+        .route(
+            "/addon/:guid/instance/:website",
+            get(get_addon_instance).delete(uninstall_addon_instance),
+        )
+        .route(
+            "/addon/:guid/instance/:website/consent",
+            get(get_addon_install_consent),
+        )
+        .route(
+            "/addon/:guid/instance/:website/resume",
+            post(resume_addon_install),
+        )
+        .route(
+            "/addon/:guid/instance/:website/redirect",
+            get(get_addon_install_redirect),
+        )
+        .route(
+            "/addon/:guid/instance/:website/complete",
+            post(complete_addon_install),
+        )
+        .route(
+            "/addon/:guid/instance/:website/subscription",
+            get(billing::get_instance_subscription),
+        )
+        .route(
+            "/addon/:guid/instance/:website/subscription/sync",
+            post(billing::sync_instance_subscription),
+        )
+        .route(
+            "/addon/:guid/instance/:website/subscription/cancel",
+            post(billing::cancel_instance_subscription),
+        )
+        // Get dashboard page
+        .route(
+            "/addon/:guid/dashboard/*O",
+            get(dashboard::get_addon_dashboard_page),
+        )
+        .route("/addon/:guid/icon", post(upload_icon))
+        .route("/addon/:guid/gallery", post(upload_gallery_item))
+        .route("/addon/:guid/template/data", get(get_all_template_data))
+        .route(
+            "/addon/:guid/template/:template",
+            get(get_template_page_data).post(update_template_page_data),
+        )
+        // Private
+        .route("/addon/:guid/access/:user", get(get_addon_member_access))
+        .route("/addon/:guid/schemas", get(get_addon_schemas))
+        .route("/addon/:guid/schema/new", post(new_cms_collection))
+        .route(
+            "/addon/:guid/schema/:name",
+            get(get_cms_info).post(update_cms),
+        )
+        .route("/addon/:guid/schema/:name/query", get(get_cms_query))
+        .route(
+            "/addon/:guid/schema/:name/column",
+            post(create_new_data_column),
+        )
+        .route(
+            "/addon/:guid/schema/:name/column/:col_id",
+            delete(delete_data_column),
+        )
+        .route(
+            "/addon/:guid/schema/:name/column/:col_id/tag",
+            post(add_data_column_tag),
+        )
+        .route(
+            "/addon/:guid/schema/:name/row",
+            post(create_new_data_row).delete(delete_cms_rows),
+        )
+        .route("/addon/:guid/schema/:name/import", post(import_data_rows))
+        .route("/addon/:guid/schema/:name/batch", post(batch_cms_rows))
+        .nest(
+            "/addon/:guid/schema/:name/import/file",
+            cms_import::routes(),
+        )
+        .nest("/addon/:guid/schema/:name/export", cms_export::routes())
+        .route(
+            "/addon/:guid/schema/:name/row/:row_id",
+            get(get_cms_row)
+                .post(update_cms_row_cell)
+                .delete(delete_cms_row),
+        )
+        .route(
+            "/addon/:guid/schema/:name/row/:row_id/duplicate",
+            post(duplicate_cms_row_cell),
+        )
+        .route("/addon/:guid/schema/:name/trash", get(get_cms_trash))
+        .route(
+            "/addon/:guid/schema/:name/trash/restore",
+            post(restore_cms_rows),
+        )
+        .route(
+            "/addon/:guid/schema/:name/trash/purge",
+            post(purge_cms_rows),
+        )
+        //
+        .nest("/addon/:guid/vissl", vissl::routes())
+        .nest("/website/:website_id", website::routes())
+        .nest("/addon/:addon_id/webhook", webhook::routes())
+        .nest("/addon/:addon_id/automation", automation::routes())
+        .nest("/addon/:addon_id/extension", extension::routes())
+        .nest(
+            "/addon/:addon_id/install-settings",
+            install_settings::routes(),
+        )
+        .nest("/addon/:addon_id/pricing", billing::routes())
+        .nest("/addon/:addon_id/collaborator", collaborator::routes())
+        .nest("/addon/:addon_id/review", review::routes())
+        .nest("/addon/:addon_id/demo", demo::routes())
+        .nest("/addon/:addon_id/oauth", oauth::client_routes())
+        .nest("/addon/:addon_id/permissions", permission::routes())
+        .nest("/addon/:addon_id/dashboard-bundle", dashboard::routes())
+        .nest("/addon/:addon_id/site-plugin", site_plugin::routes())
+        .nest("/oauth", oauth::routes())
+        .nest("/developer", developer::routes())
+        .nest("/addon/:addon_id", addon::routes())
+}
+
+/// Called by installed websites and their visitors so it stays public, the addon is responsible
+/// for authorizing its' own API.
 async fn handle_api(
     Path((addon_id, rest)): Path<(Uuid, String)>,
     State(db): State<SqlitePool>,
@@ -160,7 +288,10 @@ async fn handle_api(
 
     let uri = req.uri().clone();
     let method = req.method().clone();
-    let headers = req.headers().clone();
+    let mut headers = req.headers().clone();
+
+    // A member's token is only meant for us.
+    headers.remove(AUTHORIZATION);
 
     let mut buf = Vec::new();
 
@@ -215,7 +346,10 @@ async fn handle_api(
 async fn get_dashboard_pages(
     Path(website): Path<Uuid>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonListResponse<serde_json::Value>> {
+    member.website_access_error(website).await?;
+
     let active =
         AddonInstanceModel::find_by_website_uuid(website, &mut *db.acquire().await?).await?;
 
@@ -226,19 +360,24 @@ async fn get_dashboard_pages(
             .await?
             .unwrap();
 
-        if addon.deleted_at.is_some() {
+        if addon.deleted_at.is_some() || !instance.is_usable(&mut *db.acquire().await?).await? {
             continue;
         }
 
         let pages = AddonDashboardPage::find_by_id(addon.id, &mut *db.acquire().await?).await?;
 
-        // TODO: Return if its' an SPA incl. a hash for the page incase we're using multiple SPA's so we know if we have to re-fetch the data.
+        // The hash changes with each bundle so we know if we have to re-fetch the SPA.
+        let spa = dashboard::find_instance_bundle(&instance, &mut *db.acquire().await?).await?;
 
         items.push(serde_json::json!({
             "name": addon.name,
             "icon": addon.icon,
             "guid": addon.guid,
             "rootPage": addon.root_dashboard_page,
+            "spa": spa.map(|v| serde_json::json!({
+                "hash": v.hash,
+                "entry": format!("{}/{}", v.hash, v.entry),
+            })),
             "pages": pages.into_iter().filter_map(|p| {
                 if p.is_sidebar_visible {
                     Some(p.into())
@@ -258,15 +397,22 @@ struct ActiveAddonsResponse {
     instance_guid: AddonInstanceUuid,
     instance_version: String,
     addon: AddonPublic,
+    /// False while the subscription is past due or cancelled.
+    #[serde(skip)]
+    is_usable: bool,
 }
 
 async fn get_active_addon_list(
     Path(website): Path<WebsiteUuid>,
     State(db): State<SqlitePool>,
 ) -> Result<JsonListResponse<ActiveAddonsResponse>> {
-    Ok(Json(WrappingResponse::okay(ListResponse::all(
-        query_active_addon_list(website, &mut *db.acquire().await?).await?,
-    ))))
+    let items = query_active_addon_list(website, &mut *db.acquire().await?)
+        .await?
+        .into_iter()
+        .filter(|v| v.is_usable)
+        .collect();
+
+    Ok(Json(WrappingResponse::okay(ListResponse::all(items))))
 }
 
 pub async fn query_active_addon_list(
@@ -282,7 +428,10 @@ pub async fn query_active_addon_list(
             .await?
             .context("Addon not found")?;
 
+        let is_usable = instance.is_usable(db).await?;
+
         items.push(ActiveAddonsResponse {
+            is_usable,
             instance_guid: instance.public_id,
             instance_version: instance.version,
             addon: addon.into_public(None, None, Vec::new()),
@@ -292,51 +441,90 @@ pub async fn query_active_addon_list(
     Ok(items)
 }
 
-// TODO: Route: (User) Uninstall
-// TODO: Route: (User) Resume Install
-// TODO: Route: (Addon) Instance Install Complete
-
 #[derive(Deserialize)]
 struct Query {
     pub view: Option<String>,
     pub member: Option<Uuid>,
+    /// Full-text search
+    pub q: Option<String>,
+    /// Comma separated tag slugs
+    pub tags: Option<String>,
+    #[serde(default)]
+    pub sort: AddonSort,
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+struct AddonListResponse<T> {
+    #[serde(flatten)]
+    list: ListResponse<T>,
+    facets: Vec<TagFacet>,
 }
 
 async fn get_addon_list(
     State(db): State<SqlitePool>,
-    extract::Query(Query { view, member }): extract::Query<Query>,
+    extract::Query(Query {
+        view,
+        member,
+        q,
+        tags,
+        sort,
+        offset,
+        limit,
+    }): extract::Query<Query>,
 ) -> Result<Response> {
+    let search = AddonSearch {
+        query: q,
+        tags: tags
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect(),
+        member,
+        sort,
+    };
+
+    let offset = offset.unwrap_or(0).max(0);
+    let limit = limit.unwrap_or(50).clamp(1, 100);
+
     match view.as_deref() {
         None | Some("simple") => {
-            let addons = if let Some(member) = member {
-                AddonModel::find_all_by_member(member, &mut *db.acquire().await?).await?
-            } else {
-                AddonModel::find_all(&mut *db.acquire().await?).await?
-            };
-
-            Ok(Json(WrappingResponse::okay(ListResponse::all(
-                addons
-                    .into_iter()
-                    .map(|a| a.into_public(None, None, Vec::new()))
-                    .collect(),
-            )))
+            let mut acq = db.acquire().await?;
+
+            let addons = AddonModel::search(&search, offset, limit, &mut acq).await?;
+
+            Ok(Json(WrappingResponse::okay(AddonListResponse {
+                list: ListResponse {
+                    offset,
+                    limit,
+                    total: AddonModel::count_search(&search, &mut acq).await?,
+                    items: addons
+                        .into_iter()
+                        .map(|a| a.into_public(None, None, Vec::new()))
+                        .collect::<Vec<_>>(),
+                },
+                facets: AddonTagModel::count_facets(&search, &mut acq).await?,
+            }))
             .into_response())
         }
 
         Some("extended") => {
-            // TODO: Extended Variant
-            let addons = if let Some(member) = member {
-                AddonModel::find_all_by_member(member, &mut *db.acquire().await?).await?
-            } else {
-                AddonModel::find_all(&mut *db.acquire().await?).await?
-            };
-
-            Ok(Json(WrappingResponse::okay(ListResponse::all(
-                addons
-                    .into_iter()
-                    .map(|a| a.into_public(None, None, Vec::new()))
-                    .collect(),
-            )))
+            let mut acq = db.acquire().await?;
+
+            let addons = AddonModel::search(&search, offset, limit, &mut acq).await?;
+
+            Ok(Json(WrappingResponse::okay(AddonListResponse {
+                list: ListResponse {
+                    offset,
+                    limit,
+                    total: AddonModel::count_search(&search, &mut acq).await?,
+                    items: extend_addons(addons, &mut acq).await?,
+                },
+                facets: AddonTagModel::count_facets(&search, &mut acq).await?,
+            }))
             .into_response())
         }
 
@@ -344,10 +532,134 @@ async fn get_addon_list(
     }
 }
 
+/// Loads the listing details of every addon at once instead of per addon.
+async fn extend_addons(
+    addons: Vec<AddonModel>,
+    db: &mut SqliteConnection,
+) -> Result<Vec<AddonExtendedPublic>> {
+    if addons.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids = addons.iter().map(|v| v.id).collect::<Vec<_>>();
+
+    let mut permissions = HashMap::<AddonId, Vec<String>>::new();
+
+    for perm in AddonPermissionModel::find_by_addon_ids(&ids, &mut *db).await? {
+        permissions
+            .entry(perm.addon_id)
+            .or_default()
+            .push(perm.perm.to_string());
+    }
+
+    let media = AddonMediaModel::find_by_addon_ids(&ids, &mut *db).await?;
+
+    let developer_ids = addons
+        .iter()
+        .filter_map(|v| v.developer_id)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let developers = if developer_ids.is_empty() {
+        Vec::new()
+    } else {
+        DeveloperModel::find_by_ids(&developer_ids, &mut *db).await?
+    };
+
+    let upload_ids = addons
+        .iter()
+        .filter_map(|v| v.icon)
+        .chain(media.iter().filter_map(|v| v.uploader_id))
+        .chain(developers.iter().filter_map(|v| v.icon))
+        .collect::<HashSet<_>>();
+
+    let upload_paths = if upload_ids.is_empty() {
+        HashMap::new()
+    } else {
+        MediaUploadModel::find_by_ids(upload_ids.into_iter().collect(), &mut *db)
+            .await?
+            .into_iter()
+            .filter(|v| v.deleted_at.is_none())
+            .map(|v| (v.id, get_public_file_url(&v.store_path)))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let mut gallery = HashMap::<AddonId, Vec<String>>::new();
+
+    for item in media {
+        let url = match item.type_of {
+            AddonMediaType::Upload => item
+                .uploader_id
+                .and_then(|id| upload_paths.get(&id).cloned()),
+            AddonMediaType::Embed => item.embed_url,
+        };
+
+        if let Some(url) = url {
+            gallery.entry(item.addon_id).or_default().push(url);
+        }
+    }
+
+    let mut versions = AddonCompiledModel::find_latest_published_versions(&ids, &mut *db)
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let widget_counts = AddonWidgetContent::count_by_addon_ids(&ids, &mut *db)
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let page_counts = AddonTemplatePageModel::count_by_addon_ids(&ids, &mut *db)
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let schema_counts = SchemaModel::count_by_addon_ids(&ids, &mut *db)
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let developers = developers
+        .into_iter()
+        .map(|v| (v.id, v))
+        .collect::<HashMap<_, _>>();
+
+    Ok(addons
+        .into_iter()
+        .map(|addon| {
+            let id = addon.id;
+            let icon = addon.icon.and_then(|v| upload_paths.get(&v).cloned());
+
+            AddonExtendedPublic {
+                latest_version: versions.remove(&id),
+                widget_count: widget_counts.get(&id).copied().unwrap_or_default(),
+                page_count: page_counts.get(&id).copied().unwrap_or_default(),
+                schema_count: schema_counts.get(&id).copied().unwrap_or_default(),
+                developer: addon
+                    .developer_id
+                    .and_then(|v| developers.get(&v))
+                    .map(|v| {
+                        v.clone()
+                            .into_public(v.icon.and_then(|v| upload_paths.get(&v).cloned()))
+                    }),
+                addon: addon.into_public(
+                    icon,
+                    gallery.remove(&id),
+                    permissions.remove(&id).unwrap_or_default(),
+                ),
+            }
+        })
+        .collect())
+}
+
 async fn get_addon_instance(
     Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonResponse<serde_json::Value>> {
+    member.website_access_error(website_id).await?;
+
     let mut acq = db.acquire().await?;
 
     let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
@@ -366,172 +678,707 @@ async fn get_addon_instance(
     }))))
 }
 
-#[derive(Deserialize)]
-pub struct UpdateAddonInstance {
-    pub version: Option<String>,
-    pub settings: Option<serde_json::Value>,
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallConsentResponse {
+    pub version: String,
+    /// Permissions the version requests
+    pub requested: Vec<PermissionPublic>,
+    /// Permissions the instance was already granted
+    pub granted: Vec<PermissionPublic>,
+    /// Requested permissions which need consent before installing or upgrading
+    pub added: Vec<PermissionPublic>,
+    /// Granted permissions which the version no longer requests
+    pub removed: Vec<PermissionPublic>,
 }
 
-async fn post_addon_instance(
-    Path(instance_id): Path<Uuid>,
+/// (User) Install Consent
+///
+/// The permissions which have to be consented to when installing or upgrading to the newest version.
+async fn get_addon_install_consent(
+    Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
     State(db): State<SqlitePool>,
-    Json(json): Json<UpdateAddonInstance>,
-) -> Result<JsonResponse<&'static str>> {
+    member: AuthMember,
+) -> Result<JsonResponse<InstallConsentResponse>> {
+    member.website_access_error(website_id).await?;
+
     let mut acq = db.acquire().await?;
 
-    let mut inst = AddonInstanceModel::find_by_uuid(instance_id, &mut acq)
+    let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
+        return Err(eyre::eyre!("Addon not found"))?;
+    };
+
+    let compiled = AddonCompiledModel::find_latest_published(addon.id, &mut acq)
         .await?
-        .context("Addon Instance not found")?;
+        .context("Addon doesn't exist")?;
 
-    if let Some(version) = json.version {
-        inst.version = version;
-    }
+    let granted =
+        match AddonInstanceModel::find_by_addon_website_id(addon.id, website_id, &mut acq).await? {
+            Some(inst) => {
+                AddonInstanceGrantModel::find_permissions_by_instance_id(inst.id, &mut acq).await?
+            }
+            None => Vec::new(),
+        };
 
-    if let Some(settings) = json.settings {
-        inst.settings = Some(sqlx::types::Json(settings));
-    }
+    let requested = compiled.permissions.0;
 
-    inst.update(&mut acq).await?;
+    let describe = |perms: Vec<String>| {
+        perms
+            .into_iter()
+            .map(PermissionPublic::describe)
+            .collect::<Vec<_>>()
+    };
 
-    Ok(Json(WrappingResponse::okay("ok")))
+    Ok(Json(WrappingResponse::okay(InstallConsentResponse {
+        version: compiled.version,
+        added: describe(find_missing_permissions(&granted, &requested)),
+        removed: describe(find_missing_permissions(&requested, &granted)),
+        requested: describe(requested),
+        granted: describe(granted),
+    })))
 }
 
-async fn get_addon_public(
-    Path(guid): Path<Uuid>,
+#[derive(Deserialize)]
+pub struct UninstallAddonJson {
+    pub reason: Option<String>,
+}
+
+/// (User) Uninstall
+async fn uninstall_addon_instance(
+    Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
     State(db): State<SqlitePool>,
-) -> Result<JsonResponse<AddonPublic>> {
+    Extension(billing): Extension<SharedBillingProvider>,
+    member: AuthMember,
+    Json(UninstallAddonJson { reason }): Json<UninstallAddonJson>,
+) -> Result<JsonResponse<&'static str>> {
+    member.website_access_error(website_id).await?;
+
     let mut acq = db.acquire().await?;
 
-    let Some(addon) = AddonModel::find_one_by_guid(guid, &mut acq).await? else {
+    let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
         return Err(eyre::eyre!("Addon not found"))?;
     };
 
-    let perms = AddonPermissionModel::find_by_addon_id(addon.id, &mut acq).await?;
+    let Some(mut inst) =
+        AddonInstanceModel::find_by_addon_website_id(addon.id, website_id, &mut acq).await?
+    else {
+        return Err(eyre::eyre!("Addon Instance not found"))?;
+    };
 
-    Ok(Json(WrappingResponse::okay(addon.into_public(
-        None,
-        None,
-        perms.into_iter().map(|p| p.perm.to_string()).collect(),
-    ))))
+    // Billing stops before the instance is removed so a failure can be retried.
+    if let Some(mut sub) = inst.find_subscription(&mut acq).await? {
+        if sub.status != SubscriptionStatus::Cancelled {
+            billing::cancel_subscription(&mut sub, &*billing, &mut acq).await?;
+        }
+    }
+
+    let addon_id = addon.id;
+
+    let inst = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                WebsiteWidgetSettingsModel::delete_by_website_id_and_addon_id(
+                    inst.website_id,
+                    inst.addon_id,
+                    trx,
+                )
+                .await?;
+
+                inst.soft_delete(reason, trx).await?;
+
+                AddonOAuthTokenModel::revoke_by_instance_id(inst.id, trx).await?;
+
+                queue_webhook_event(
+                    addon_id,
+                    WebhookEvent::InstanceUninstalled,
+                    serde_json::json!({
+                        "instanceId": inst.public_id,
+                        "websiteId": inst.website_uuid,
+                        "reason": inst.delete_reason,
+                    }),
+                    trx,
+                )
+                .await?;
+
+                Result::<_, crate::Error>::Ok(inst)
+            })
+        })
+        .await?;
+
+    if let Some(url) = addon.action_url {
+        // The instance is already removed on our end. We don't want an unreachable addon to prevent uninstalling.
+        let resp = CLIENT
+            .post(format!("{url}/uninstall"))
+            .json(&serde_json::json!({
+                "instanceId": inst.public_id,
+                "websiteId": inst.website_uuid,
+                "reason": inst.delete_reason,
+            }))
+            .send()
+            .await;
+
+        match resp {
+            Ok(resp) if !resp.status().is_success() => warn!(
+                "Addon {} uninstall hook failed with status {}",
+                addon.guid,
+                resp.status()
+            ),
+            Err(e) => warn!("Addon {} uninstall hook failed: {e}", addon.guid),
+            _ => (),
+        }
+    }
+
+    Ok(Json(WrappingResponse::okay("ok")))
 }
 
-async fn get_addon_member_access(
-    Path((addon, member)): Path<(Uuid, Uuid)>,
+/// (User) Resume Install
+///
+/// Re-sends the registration request for an instance which hasn't finished its' setup.
+async fn resume_addon_install(
+    Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
     State(db): State<SqlitePool>,
-) -> Result<JsonResponse<bool>> {
+    member: AuthMember,
+) -> Result<JsonResponse<serde_json::Value>> {
+    let website = member.find_owned_website(website_id).await?;
+
     let mut acq = db.acquire().await?;
 
-    let Some(addon) = AddonModel::find_one_by_guid(addon, &mut acq).await? else {
+    let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
         return Err(eyre::eyre!("Addon not found"))?;
     };
 
-    Ok(Json(WrappingResponse::okay(addon.member_uuid == member)))
+    let Some(mut inst) =
+        AddonInstanceModel::find_by_addon_website_id(addon.id, website_id, &mut acq).await?
+    else {
+        return Err(eyre::eyre!("Addon Instance not found"))?;
+    };
+
+    if !inst.is_setup {
+        if let Some(url) = addon.action_url {
+            let owner = member.find_member().await?;
+
+            addon::register_addon_instance(&url, &mut inst, &owner, &website, &mut acq).await?;
+        } else {
+            inst.is_setup = true;
+            inst.update(&mut acq).await?;
+        }
+    }
+
+    Ok(Json(WrappingResponse::okay(serde_json::json!({
+        "uuid": inst.public_id,
+        "isSetup": inst.is_setup,
+    }))))
 }
 
-async fn get_addon_dashboard_page(
-    Path((guid, _path)): Path<(Uuid, String)>,
+/// (User) Pending Install Redirect
+///
+/// Returns where the editor should send the user to continue a multi-step install.
+async fn get_addon_install_redirect(
+    Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
     State(db): State<SqlitePool>,
-) -> Result<impl IntoResponse> {
-    let Some(_addon) = AddonModel::find_one_by_guid(guid, &mut *db.acquire().await?).await? else {
+    member: AuthMember,
+) -> Result<JsonResponse<Option<serde_json::Value>>> {
+    member.website_access_error(website_id).await?;
+
+    let mut acq = db.acquire().await?;
+
+    let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
         return Err(eyre::eyre!("Addon not found"))?;
     };
 
-    // TODO: Upload SPA file instead
-    let mut files = tokio::fs::read_dir("../addon-blog/dashboard/dist/assets").await?;
-
-    let resp_builder = axum::response::Response::builder()
-        .status(hyper::StatusCode::OK)
-        .header(
-            hyper::header::CONTENT_TYPE,
-            mime_guess::mime::TEXT_PLAIN_UTF_8.as_ref(),
-        );
+    let Some(inst) =
+        AddonInstanceModel::find_by_addon_website_id(addon.id, website_id, &mut acq).await?
+    else {
+        return Err(eyre::eyre!("Addon Instance not found"))?;
+    };
 
-    while let Some(entry) = files.next_entry().await? {
-        let meta = entry.metadata().await?;
+    if inst.is_setup {
+        return Ok(Json(WrappingResponse::okay(None)));
+    }
 
-        if meta.is_file() && entry.file_name().to_string_lossy().ends_with(".js") {
-            let contents = tokio::fs::read_to_string(entry.path()).await?;
+    let Some(session) =
+        AddonInstallSessionModel::find_one_pending_by_instance_id(inst.id, &mut acq).await?
+    else {
+        return Ok(Json(WrappingResponse::okay(None)));
+    };
 
-            return Ok(resp_builder
-                .header(
-                    hyper::header::CONTENT_TYPE,
-                    mime_guess::mime::TEXT_JAVASCRIPT.as_ref(),
-                )
-                .body(contents)
-                .unwrap()
-                .into_response());
-        }
+    if session.is_expired() {
+        return Err(eyre::eyre!(
+            "Install session expired. Resume the install to restart it."
+        ))?;
     }
 
-    Ok(resp_builder.body(String::new()).unwrap().into_response())
+    let mut redirect_url =
+        url::Url::parse(&session.redirect_url).context("Invalid addon redirect url")?;
+    redirect_url
+        .query_pairs_mut()
+        .append_pair("state", &session.state);
+
+    Ok(Json(WrappingResponse::okay(Some(serde_json::json!({
+        "url": redirect_url,
+        "step": session.step,
+        "expiresAt": session.expires_at,
+    })))))
 }
 
 #[derive(Deserialize)]
-pub struct NewAddonJson {
-    title: String,
-    description: String,
-    tagline: String,
-    // tags: Vec<String>,
+pub struct CompleteAddonInstallJson {
+    /// State token of the install session.
+    pub state: Option<String>,
+    /// Redirect for the next install step. Setup is finished if not set.
+    pub next: Option<String>,
 }
 
-async fn new_addon(
+/// (Addon) Instance Install Complete
+///
+/// Called by the addon once a multi-step install has finished, or to continue onto the next step.
+///
+/// Without an install session the addon has to use an access token of the instance, otherwise the
+/// website owner has to be the one calling.
+async fn complete_addon_install(
+    Path((addon_id, website_id)): Path<(Uuid, Uuid)>,
     State(db): State<SqlitePool>,
-    Json(NewAddonJson {
-        title,
-        description,
-        tagline,
-        // tags,
-    }): Json<NewAddonJson>,
-) -> Result<JsonResponse<AddonPublic>> {
-    let addon = NewAddonModel {
-        // TODO: Remove
-        member_id: MemberId::from(1),
-        member_uuid: Uuid::from_bytes([
-            0x2c, 0x5e, 0xa4, 0xc0, 0x40, 0x67, 0x11, 0xe9, 0x8b, 0x2d, 0x1b, 0x9d, 0x6b, 0xcd,
-            0xbb, 0xfd,
-        ]),
-        // TODO: Only keep A-Z 0-9 _
-        name_id: title.to_lowercase(),
-        name: title,
-        tag_line: tagline,
-        description,
-        icon: None,
-        version: String::new(),
-        action_url: None,
-        root_dashboard_page: None,
-    }
-    .insert(&mut *db.acquire().await?)
-    .await?;
-
-    //
+    token: Option<oauth::AddonToken>,
+    member: Option<AuthMember>,
+    Json(CompleteAddonInstallJson { state, next }): Json<CompleteAddonInstallJson>,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
 
-    Ok(Json(WrappingResponse::okay(addon.into_public(
-        None,
-        None,
-        Vec::new(),
-    ))))
-}
+    let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
+        return Err(eyre::eyre!("Addon not found"))?;
+    };
 
-async fn upload_icon(
+    let Some(mut inst) =
+        AddonInstanceModel::find_by_addon_website_id(addon.id, website_id, &mut acq).await?
+    else {
+        return Err(eyre::eyre!("Addon Instance not found"))?;
+    };
+
+    if inst.is_setup {
+        return Ok(Json(WrappingResponse::okay("ok")));
+    }
+
+    // Instances which were redirected have to present the state token of their session.
+    if let Some(mut session) =
+        AddonInstallSessionModel::find_one_pending_by_instance_id(inst.id, &mut acq).await?
+    {
+        if state.as_deref() != Some(session.state.as_str()) {
+            return Err(eyre::eyre!("Invalid install state"))?;
+        }
+
+        if session.is_expired() {
+            return Err(eyre::eyre!("Install session expired"))?;
+        }
+
+        if let Some(next) = next {
+            url::Url::parse(&next).context("Invalid addon redirect url")?;
+
+            session.next_step(next, &mut acq).await?;
+
+            return Ok(Json(WrappingResponse::okay("ok")));
+        }
+
+        acq.transaction(|trx| {
+            Box::pin(async move {
+                session.complete(trx).await?;
+
+                inst.is_setup = true;
+                inst.update(trx).await?;
+
+                Result::<_, crate::Error>::Ok(())
+            })
+        })
+        .await?;
+    } else {
+        if !token.is_some_and(|v| v.instance.id == inst.id) {
+            member
+                .ok_or(crate::Error::Unauthorized)?
+                .website_access_error(website_id)
+                .await?;
+        }
+
+        inst.is_setup = true;
+        inst.update(&mut acq).await?;
+    }
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+/// The version is only changed by upgrading so new permissions are consented to.
+#[derive(Deserialize)]
+pub struct UpdateAddonInstance {
+    pub settings: Option<serde_json::Value>,
+}
+
+/// Responds with the settings merged over the defaults of the instance's version.
+async fn post_addon_instance(
+    Path(instance_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(json): Json<UpdateAddonInstance>,
+) -> Result<JsonResponse<serde_json::Value>> {
+    let mut acq = db.acquire().await?;
+
+    let mut inst = AddonInstanceModel::find_by_uuid(instance_id, &mut acq)
+        .await?
+        .context("Addon Instance not found")?;
+
+    member.website_access_error(inst.website_uuid).await?;
+
+    if let Some(settings) = json.settings {
+        inst.settings = Some(sqlx::types::Json(settings));
+    }
+
+    let overrides = match inst.settings.as_ref().map(|v| &v.0) {
+        Some(serde_json::Value::Object(map)) => map.clone(),
+        Some(serde_json::Value::Null) | None => serde_json::Map::new(),
+        Some(_) => return Err(eyre::eyre!("Settings must be an object"))?,
+    };
+
+    let resolved = install_settings::resolve_instance_settings(&inst, &overrides, &mut acq).await?;
+
+    inst.update(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(serde_json::Value::Object(
+        resolved,
+    ))))
+}
+
+async fn get_addon_public(
+    Path(guid): Path<Uuid>,
+    State(db): State<SqlitePool>,
+) -> Result<JsonResponse<AddonPublic>> {
+    let mut acq = db.acquire().await?;
+
+    let Some(addon) = AddonModel::find_one_by_guid(guid, &mut acq).await? else {
+        return Err(eyre::eyre!("Addon not found"))?;
+    };
+
+    let perms = AddonPermissionModel::find_by_addon_id(addon.id, &mut acq).await?;
+    let tags = AddonTagModel::find_tags_by_addon_id(addon.id, &mut acq).await?;
+
+    let mut public = addon.into_public(
+        None,
+        None,
+        perms.into_iter().map(|p| p.perm.to_string()).collect(),
+    );
+
+    public.tags = Some(tags.into_iter().map(|v| v.into_public()).collect());
+
+    Ok(Json(WrappingResponse::okay(public)))
+}
+
+async fn get_tag_categories(State(db): State<SqlitePool>) -> Result<JsonResponse<Vec<TagPublic>>> {
+    Ok(Json(WrappingResponse::okay(
+        TagModel::find_categories(&mut *db.acquire().await?)
+            .await?
+            .into_iter()
+            .map(|v| v.into_public())
+            .collect(),
+    )))
+}
+
+const MAX_ADDON_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 32;
+
+/// Replaces the tags of the addon. Unknown tags are created, categories are matched by their slug.
+async fn set_addon_tags(
+    addon_id: AddonId,
+    tags: &[String],
+    db: &mut SqliteConnection,
+) -> Result<Vec<TagModel>> {
+    if tags.len() > MAX_ADDON_TAGS {
+        return Err(eyre::eyre!("Addons can have up to {MAX_ADDON_TAGS} tags"))?;
+    }
+
+    let mut models = Vec::new();
+
+    for tag in tags {
+        if tag.trim().len() > MAX_TAG_LENGTH {
+            return Err(eyre::eyre!(
+                "Tags can't be longer than {MAX_TAG_LENGTH} characters"
+            ))?;
+        }
+
+        if let Some(model) = TagModel::find_or_create(tag, db).await? {
+            if !models.iter().any(|v: &TagModel| v.id == model.id) {
+                models.push(model);
+            }
+        }
+    }
+
+    AddonTagModel::set_for_addon(
+        addon_id,
+        &models.iter().map(|v| v.id).collect::<Vec<_>>(),
+        db,
+    )
+    .await?;
+
+    Ok(models)
+}
+
+#[derive(Deserialize)]
+pub struct UpdateAddonJson {
+    title: Option<String>,
+    description: Option<String>,
+    tagline: Option<String>,
+    tags: Option<Vec<String>>,
+}
+
+async fn update_addon(
+    Path(guid): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<UpdateAddonJson>,
+) -> Result<JsonResponse<AddonPublic>> {
+    let mut acq = db.acquire().await?;
+
+    let Some(mut addon) = AddonModel::find_one_by_guid(guid, &mut acq).await? else {
+        return Err(eyre::eyre!("Addon not found"))?;
+    };
+
+    member
+        .addon_access_error(&addon, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    if let Some(title) = value.title {
+        addon.name = title;
+    }
+
+    if let Some(description) = value.description {
+        addon.description = description;
+    }
+
+    if let Some(tagline) = value.tagline {
+        addon.tag_line = tagline;
+    }
+
+    addon.update(&mut acq).await?;
+
+    let tags = match value.tags {
+        Some(tags) => set_addon_tags(addon.id, &tags, &mut acq).await?,
+        None => AddonTagModel::find_tags_by_addon_id(addon.id, &mut acq).await?,
+    };
+
+    let mut public = addon.into_public(None, None, Vec::new());
+    public.tags = Some(tags.into_iter().map(|v| v.into_public()).collect());
+
+    Ok(Json(WrappingResponse::okay(public)))
+}
+
+#[derive(Deserialize)]
+pub struct DeleteAddonJson {
+    pub reason: Option<String>,
+}
+
+async fn delete_addon(
+    Path(guid): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(DeleteAddonJson { reason }): Json<DeleteAddonJson>,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(guid, &mut acq)
+        .await?
+        .filter(|v| v.deleted_at.is_none())
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    // Websites would be left with an addon they're still paying for but can't reach anymore.
+    let installed = AddonInstanceModel::count_active_by_addon_id(addon.id, &mut acq).await?;
+
+    if installed != 0 {
+        return Err(crate::Error::Conflict(format!(
+            "Addon is still installed on {installed} website(s)"
+        )));
+    }
+
+    AddonModel::delete(addon.id, reason.unwrap_or_default(), &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddonMemberAccess {
+    role: Option<AddonRole>,
+    capabilities: &'static [AddonCapability],
+}
+
+/// Members can always look up their own access.
+async fn get_addon_member_access(
+    Path((addon_id, member_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<AddonMemberAccess>> {
+    let mut acq = db.acquire().await?;
+
+    let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
+        return Err(eyre::eyre!("Addon not found"))?;
+    };
+
+    if member.uuid() != member_id {
+        member
+            .addon_access_error(&addon, AddonCapability::View, &mut acq)
+            .await?;
+    }
+
+    let role = AddonCollaboratorModel::find_role(&addon, member_id, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(AddonMemberAccess {
+        role,
+        capabilities: role.map(|v| v.capabilities()).unwrap_or_default(),
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct NewAddonJson {
+    title: String,
+    description: String,
+    tagline: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    extends: Vec<ExtensionKind>,
+}
+
+async fn new_addon(
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(NewAddonJson {
+        title,
+        description,
+        tagline,
+        tags,
+        extends,
+    }): Json<NewAddonJson>,
+) -> Result<JsonResponse<AddonPublic>> {
+    let mut acq = db.acquire().await?;
+
+    let name_id = find_available_name_id(&title, &mut acq).await?;
+
+    let developer = DeveloperModel::find_one_by_member_uuid(member.uuid(), &mut acq).await?;
+
+    let addon = NewAddonModel {
+        member_id: member.pk(),
+        member_uuid: member.uuid(),
+        developer_id: developer.as_ref().map(|v| v.id),
+        name_id: name_id.clone(),
+        name: title,
+        tag_line: tagline,
+        description,
+        icon: None,
+        version: String::new(),
+        action_url: None,
+        root_dashboard_page: None,
+    }
+    .insert(&mut acq)
+    .await
+    .map_err(|e| {
+        // Another addon took the name id since it was checked.
+        if is_unique_violation(&e) {
+            crate::Error::Conflict(format!("Name id \"{name_id}\" is already in use"))
+        } else {
+            e.into()
+        }
+    })?;
+
+    if let Some(developer) = developer {
+        DeveloperModel::refresh_addon_count(developer.id, &mut acq).await?;
+    }
+
+    NewAddonExtensionModel {
+        addon_id: addon.id,
+        compiled_id: None,
+        extends,
+        dashboard_page_count: None,
+        cms_collection_count: None,
+    }
+    .insert(&mut acq)
+    .await?;
+
+    let tags = set_addon_tags(addon.id, &tags, &mut acq).await?;
+
+    let mut public = addon.into_public(None, None, Vec::new());
+    public.tags = Some(tags.into_iter().map(|v| v.into_public()).collect());
+
+    Ok(Json(WrappingResponse::okay(public)))
+}
+
+/// Converts the title into a name id only containing a-z 0-9 _
+fn slugify_name_id(title: &str) -> String {
+    let mut name_id = String::with_capacity(title.len());
+
+    for c in title.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            name_id.push(c.to_ascii_lowercase());
+        } else if !name_id.is_empty() && !name_id.ends_with('_') {
+            name_id.push('_');
+        }
+    }
+
+    let name_id = name_id.trim_end_matches('_');
+
+    if name_id.is_empty() {
+        String::from("addon")
+    } else {
+        name_id.to_string()
+    }
+}
+
+fn is_unique_violation(e: &eyre::Report) -> bool {
+    e.downcast_ref::<sqlx::Error>()
+        .and_then(|v| v.as_database_error())
+        .is_some_and(|v| v.is_unique_violation())
+}
+
+/// Suffixes the slugified title with a number until it's not used by another addon.
+async fn find_available_name_id(title: &str, db: &mut SqliteConnection) -> Result<String> {
+    let base = slugify_name_id(title);
+    let mut name_id = base.clone();
+    let mut suffix = 1;
+
+    while AddonModel::find_one_by_name_id(&name_id, db)
+        .await?
+        .is_some()
+    {
+        suffix += 1;
+        name_id = format!("{base}_{suffix}");
+    }
+
+    Ok(name_id)
+}
+
+async fn upload_icon(
     Path(guid): Path<Uuid>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
     storage: StorageService,
     mut multipart: extract::Multipart,
 ) -> Result<JsonResponse<Option<&'static str>>> {
-    let Some(addon) = AddonModel::find_one_by_guid(guid, &mut *db.acquire().await?).await? else {
+    let Some(mut addon) = AddonModel::find_one_by_guid(guid, &mut *db.acquire().await?).await?
+    else {
         return Err(eyre::eyre!("Addon not found"))?;
     };
 
+    member
+        .addon_access_error(
+            &addon,
+            AddonCapability::EditDesign,
+            &mut *db.acquire().await?,
+        )
+        .await?;
+
     if let Some(field) = multipart.next_field().await? {
         if let Some(model) =
             upload_file(field, addon.member_id, Some((200, 200)), &storage, &db).await?
         {
-            NewAddonMediaModel::Upload {
-                addon_id: addon.id,
-                upload_id: model.id,
-            }
-            .insert(&mut *db.acquire().await?)
-            .await?;
+            addon.icon = Some(model.id);
+            addon.update(&mut *db.acquire().await?).await?;
 
             return Ok(Json(WrappingResponse::okay(Some("ok"))));
         }
@@ -543,6 +1390,7 @@ async fn upload_icon(
 async fn upload_gallery_item(
     Path(guid): Path<Uuid>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
     storage: StorageService,
     mut multipart: extract::Multipart,
 ) -> Result<JsonResponse<&'static str>> {
@@ -550,6 +1398,14 @@ async fn upload_gallery_item(
         return Err(eyre::eyre!("Addon not found"))?;
     };
 
+    member
+        .addon_access_error(
+            &addon,
+            AddonCapability::EditDesign,
+            &mut *db.acquire().await?,
+        )
+        .await?;
+
     let mut models = Vec::new();
 
     while let Some(field) = multipart.next_field().await? {
@@ -672,12 +1528,13 @@ async fn upload_file(
 async fn get_template_page_data(
     Path((addon_id, template_id)): Path<(Uuid, Uuid)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonResponse<serde_json::Value>> {
     let mut acq = db.acquire().await?;
 
-    let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
-        return Err(eyre::eyre!("Addon not found"))?;
-    };
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
 
     let Some(addon_page) = AddonTemplatePageModel::find_by_public_id(template_id, &mut acq).await?
     else {
@@ -704,6 +1561,7 @@ async fn get_template_page_data(
 async fn update_template_page_data(
     Path((addon_id, template_id)): Path<(Uuid, Uuid)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Json(mut page): Json<DisplayStore>,
 ) -> Result<JsonResponse<&'static str>> {
     let mut acq = db.acquire().await?;
@@ -712,6 +1570,10 @@ async fn update_template_page_data(
         return Err(eyre::eyre!("Addon not found"))?;
     };
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditDesign, &mut acq)
+        .await?;
+
     let Some(mut addon_page) =
         AddonTemplatePageModel::find_by_public_id(template_id, &mut acq).await?
     else {
@@ -775,12 +1637,13 @@ pub struct AddonPageWithDataItem {
 async fn get_all_template_data(
     Path(addon_id): Path<Uuid>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonListResponse<AddonPageWithDataItem>> {
     let mut acq = db.acquire().await?;
 
-    let Some(addon) = AddonModel::find_one_by_guid(addon_id, &mut acq).await? else {
-        return Err(eyre::eyre!("Addon not found"))?;
-    };
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
 
     let list = AddonTemplatePageModel::find_by_addon_id(addon.id, &mut acq).await?;
 
@@ -809,14 +1672,15 @@ async fn get_all_template_data(
 // TODO: From Main Program request addon schemas - remember if the schema is already in main program db then use main one.
 
 async fn get_addon_schemas(
-    Path(addon): Path<Uuid>,
+    Path(addon_id): Path<Uuid>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonListResponse<BasicCmsInfo>> {
     let mut acq = db.acquire().await?;
 
-    let Some(addon) = AddonModel::find_one_by_guid(addon, &mut acq).await? else {
-        return Err(eyre::eyre!("Addon not found"))?;
-    };
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
 
     let schemas = SchemaModel::find_by_addon_id(addon.id, &mut acq).await?;
 
@@ -836,6 +1700,7 @@ async fn get_addon_schemas(
 pub async fn new_cms_collection(
     Path(addon_id): Path<Uuid>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 
     Json(CmsCreate {
         id: coll,
@@ -853,6 +1718,12 @@ pub async fn new_cms_collection(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    require_extension(addon.id, ExtensionKind::CmsCollection, &mut acq).await?;
+
     // TODO: Id replace invalids
     // .replace(/[^a-zA-Z0-9_\s]/g, "")
     // .replace(/(?:^\w|[A-Z]|\b\w)/g, function (word, index) {
@@ -980,12 +1851,13 @@ pub async fn new_cms_collection(
 pub async fn get_cms_info(
     Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonResponse<CmsResponse>> {
     let mut acq = db.acquire().await?;
 
-    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
-        .await?
-        .context("Addon not found")?;
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
 
     let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
@@ -1028,6 +1900,7 @@ pub async fn get_cms_info(
 pub async fn update_cms(
     Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 
     Json(CmsUpdate { views }): Json<CmsUpdate>,
 ) -> Result<JsonResponse<&'static str>> {
@@ -1037,6 +1910,10 @@ pub async fn update_cms(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
     let mut schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
         .context("Schema not found")?;
@@ -1052,16 +1929,10 @@ pub async fn update_cms(
 
 // TODO: Instead of addon id use instance id ??
 // We need to not only return an instances' cms but also default values
+/// Read by installed websites when rendering so it's public like the rest of the site data.
 pub async fn get_cms_query(
     Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
-    QsQuery(CmsQuery {
-        filters,
-        sort,
-        columns,
-        limit,
-        offset,
-        include_files,
-    }): QsQuery<CmsQuery>,
+    QsQuery(query): QsQuery<CmsQuery>,
     State(db): State<SqlitePool>,
 ) -> Result<JsonListResponse<CmsRowResponse>> {
     let mut acq = db.acquire().await?;
@@ -1082,8 +1953,6 @@ pub async fn get_cms_query(
         }
     };
 
-    // addon.no_access_error(member.id(), &mut *acq).await?;
-
     let schema = match SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq).await? {
         Some(v) => v,
         None => {
@@ -1092,9 +1961,6 @@ pub async fn get_cms_query(
         }
     };
 
-    let offset = offset.unwrap_or(0) as i64;
-    let limit = limit.unwrap_or(50).max(20) as i64;
-
     if schema.store == "addon" {
         let Some(url) = addon.action_url else {
             return Err(eyre::eyre!("Addon Action URL not found"))?;
@@ -1138,105 +2004,126 @@ pub async fn get_cms_query(
             Ok(Json(resp.json().await?))
         }
     } else {
-        let total =
-            SchemaDataModel::count_by(addon.id, &schema, filters.as_deref(), &mut acq).await?;
-
-        let data = SchemaDataModel::find_by(
-            addon.id,
-            &schema,
-            filters.as_deref(),
-            sort,
-            offset,
-            limit,
-            &mut acq,
-        )
-        .await?;
+        Ok(Json(WrappingResponse::okay(
+            query_local_cms_rows(addon.id, &schema, query, &mut acq).await?,
+        )))
+    }
+}
 
-        let columns =
-            columns.map(|columns| HashSet::from_iter(columns.split(',').map(|v| v.to_string())));
+/// Queries the rows of a schema stored by us.
+async fn query_local_cms_rows(
+    addon_id: AddonId,
+    schema: &SchemaModel,
+    CmsQuery {
+        filters,
+        sort,
+        columns,
+        limit,
+        offset,
+        include_files: _,
+    }: CmsQuery,
+    db: &mut SqliteConnection,
+) -> Result<ListResponse<CmsRowResponse>> {
+    let offset = offset.unwrap_or(0) as i64;
+    let limit = limit.unwrap_or(50).max(20) as i64;
 
-        let mut items = Vec::new();
+    let total = SchemaDataModel::count_by(addon_id, schema, filters.as_deref(), db).await?;
 
-        {
-            for model in data {
-                let mut uuids = Vec::new();
+    let data = SchemaDataModel::find_by(
+        addon_id,
+        schema,
+        filters.as_deref(),
+        sort,
+        offset,
+        limit,
+        db,
+    )
+    .await?;
 
-                if let Some(value) = model.field_audio.as_ref() {
-                    uuids.append(&mut value.values().copied().collect());
-                }
+    let columns =
+        columns.map(|columns| HashSet::from_iter(columns.split(',').map(|v| v.to_string())));
 
-                if let Some(value) = model.field_document.as_ref() {
-                    uuids.append(&mut value.values().copied().collect());
-                }
+    let mut items = Vec::new();
 
-                if let Some(value) = model.field_image.as_ref() {
-                    uuids.append(&mut value.values().copied().collect());
-                }
+    {
+        for model in data {
+            let mut uuids = Vec::new();
 
-                if let Some(value) = model.field_video.as_ref() {
-                    uuids.append(&mut value.values().copied().collect());
-                }
+            if let Some(value) = model.field_audio.as_ref() {
+                uuids.append(&mut value.values().copied().collect());
+            }
 
-                if let Some(value) = model.field_multi_document.as_ref() {
-                    uuids.append(&mut value.values().flatten().copied().collect());
-                }
+            if let Some(value) = model.field_document.as_ref() {
+                uuids.append(&mut value.values().copied().collect());
+            }
 
-                uuids.sort_unstable();
-                uuids.dedup();
-
-                let fields = map_to_field_value(&schema, model, columns.as_ref())?;
-
-                // let mut files = Vec::new();
-                //
-                // if include_files {
-                //     for uuid in uuids {
-                //         if let Some(upload_id) =
-                //             WebsiteUploadLink::find_one_by_public_id(&uuid.to_string(), &mut *acq)
-                //                 .await?
-                //                 .and_then(|v| v.upload_id)
-                //         {
-                //             if let Some(item) =
-                //                 MemberUploadModel::find_one_by_id(upload_id, &mut *acq).await?
-                //             {
-                //                 // Replace public id w/ Field ID as to not expose things.
-                //                 files.push(WebsiteUpload {
-                //                     id: Some(item.id),
-                //                     public_id: uuid.to_string(),
-                //                     upload_type: String::from("media"),
-                //                     display_name: item.file_name,
-                //                     created_at: item.created_at,
-                //                     deleted_at: None,
-                //                     media: Some(WebsiteUploadFile {
-                //                         file_size: item.file_size,
-                //                         file_type: item.file_type,
-                //                         media_width: item.media_width,
-                //                         media_height: item.media_height,
-                //                         media_duration: item.media_duration,
-                //                         is_editable: item.is_editable,
-                //                         has_thumbnail: item.has_thumbnail,
-                //                         is_global: item.is_global,
-                //                     }),
-                //                     using_variant: None,
-                //                 });
-                //             }
-                //         }
-                //     }
-                // }
-
-                items.push(CmsRowResponse {
-                    files: Vec::new(),
-                    fields,
-                });
+            if let Some(value) = model.field_image.as_ref() {
+                uuids.append(&mut value.values().copied().collect());
+            }
+
+            if let Some(value) = model.field_video.as_ref() {
+                uuids.append(&mut value.values().copied().collect());
+            }
+
+            if let Some(value) = model.field_multi_document.as_ref() {
+                uuids.append(&mut value.values().flatten().copied().collect());
             }
-        }
 
-        Ok(Json(WrappingResponse::okay(ListResponse {
-            offset,
-            limit,
-            total,
-            items,
-        })))
+            uuids.sort_unstable();
+            uuids.dedup();
+
+            let fields = map_to_field_value(schema, model, columns.as_ref())?;
+
+            // let mut files = Vec::new();
+            //
+            // if include_files {
+            //     for uuid in uuids {
+            //         if let Some(upload_id) =
+            //             WebsiteUploadLink::find_one_by_public_id(&uuid.to_string(), &mut *db)
+            //                 .await?
+            //                 .and_then(|v| v.upload_id)
+            //         {
+            //             if let Some(item) =
+            //                 MemberUploadModel::find_one_by_id(upload_id, &mut *db).await?
+            //             {
+            //                 // Replace public id w/ Field ID as to not expose things.
+            //                 files.push(WebsiteUpload {
+            //                     id: Some(item.id),
+            //                     public_id: uuid.to_string(),
+            //                     upload_type: String::from("media"),
+            //                     display_name: item.file_name,
+            //                     created_at: item.created_at,
+            //                     deleted_at: None,
+            //                     media: Some(WebsiteUploadFile {
+            //                         file_size: item.file_size,
+            //                         file_type: item.file_type,
+            //                         media_width: item.media_width,
+            //                         media_height: item.media_height,
+            //                         media_duration: item.media_duration,
+            //                         is_editable: item.is_editable,
+            //                         has_thumbnail: item.has_thumbnail,
+            //                         is_global: item.is_global,
+            //                     }),
+            //                     using_variant: None,
+            //                 });
+            //             }
+            //         }
+            //     }
+            // }
+
+            items.push(CmsRowResponse {
+                files: Vec::new(),
+                fields,
+            });
+        }
     }
+
+    Ok(ListResponse {
+        offset,
+        limit,
+        total,
+        items,
+    })
 }
 
 // Column
@@ -1244,6 +2131,7 @@ pub async fn get_cms_query(
 pub async fn create_new_data_column(
     Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 
     Json(create_data): Json<CmsCreateDataColumn>,
 ) -> Result<JsonResponse<SchematicField>> {
@@ -1253,6 +2141,10 @@ pub async fn create_new_data_column(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
     let mut schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
         .context("Schema not found")?;
@@ -1329,6 +2221,7 @@ fn insert_columns(
 pub async fn add_data_column_tag(
     Path((addon_id, coll, column_id)): Path<(Uuid, CollectionName, String)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 
     Json(CmsCreateDataColumnTag { tag }): Json<CmsCreateDataColumnTag>,
 ) -> Result<JsonResponse<webby_api::SchemaTag>> {
@@ -1338,6 +2231,10 @@ pub async fn add_data_column_tag(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
     let mut schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
         .context("Schema not found")?;
@@ -1374,6 +2271,7 @@ pub async fn add_data_column_tag(
 pub async fn delete_data_column(
     Path((addon_id, coll, column_id)): Path<(Uuid, CollectionName, String)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonResponse<&'static str>> {
     let mut acq = db.acquire().await?;
 
@@ -1381,6 +2279,10 @@ pub async fn delete_data_column(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
     let mut schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
         .context("Schema not found")?;
@@ -1403,19 +2305,23 @@ pub async fn delete_data_column(
 pub async fn get_cms_row(
     Path((addon_id, coll, row_id)): Path<(Uuid, CollectionName, Uuid)>,
     State(db): State<SqlitePool>,
+    member: AuthMember,
 ) -> Result<JsonResponse<webby_api::CmsRowResponse>> {
     let mut acq = db.acquire().await?;
 
-    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
-        .await?
-        .context("Addon not found")?;
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
 
     let schema: SchemaModel = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
         .context("Schema not found")?;
 
     // TODO: add schema.id to find
-    let Some(schema_data) = SchemaDataModel::find_by_public_id(row_id, &mut acq).await? else {
+    let Some(schema_data) = SchemaDataModel::find_by_public_id(row_id, &mut acq)
+        .await?
+        .filter(|v| v.schema_id == schema.id && v.deleted_at.is_none())
+    else {
         return Err(eyre::eyre!("Schema Data not found"))?;
     };
 
@@ -1479,86 +2385,551 @@ pub async fn get_cms_row(
     //     }
     // }
 
-    Ok(Json(WrappingResponse::okay(webby_api::CmsRowResponse {
-        files,
-        fields,
-    })))
+    Ok(Json(WrappingResponse::okay(webby_api::CmsRowResponse {
+        files,
+        fields,
+    })))
+}
+
+pub async fn update_cms_row_cell(
+    Path((addon_id, coll, row_id)): Path<(Uuid, CollectionName, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+
+    Json(CmsUpdateDataCell { field_name, value }): Json<CmsUpdateDataCell>,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
+    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
+
+    let Some(schema_field) = schema
+        .fields
+        .get(&SchematicFieldKey::Other(field_name.clone()))
+    else {
+        return Err(eyre::eyre!("Schema Field not found"))?;
+    };
+
+    let Some(schema_data) =
+        SchemaDataFieldUpdate::find_data_field_by_uuid(row_id, schema_field.field_type, &mut acq)
+            .await?
+            .filter(|v| v.schema_id == schema.id && v.deleted_at.is_none())
+    else {
+        return Err(eyre::eyre!("Schema Data not found"))?;
+    };
+
+    schema_data
+        .update(
+            field_name,
+            value
+                .map(|v| schema_field.field_type.parse_value(v))
+                .transpose()?,
+            &mut acq,
+        )
+        .await?;
+
+    queue_cms_row_event(
+        addon.id,
+        WebhookEvent::CmsRowUpdated,
+        &coll.id,
+        &[row_id],
+        &mut acq,
+    )
+    .await?;
+
+    spawn_automation_event(
+        db,
+        AutomationEvent::CmsRow {
+            addon_id: addon.id,
+            collection: coll.id,
+            event: CmsRowEvent::Updated,
+            row_ids: vec![row_id],
+        },
+    );
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+pub async fn create_new_data_row(
+    Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<webby_api::CmsRowResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
+    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
+
+    let data_row = NewSchemaDataModel::new(addon.id, schema.id)
+        .insert(&mut acq)
+        .await?;
+
+    queue_cms_row_event(
+        addon.id,
+        WebhookEvent::CmsRowCreated,
+        &coll.id,
+        &[data_row.public_id],
+        &mut acq,
+    )
+    .await?;
+
+    spawn_automation_event(
+        db.clone(),
+        AutomationEvent::CmsRow {
+            addon_id: addon.id,
+            collection: coll.id.clone(),
+            event: CmsRowEvent::Created,
+            row_ids: vec![data_row.public_id],
+        },
+    );
+
+    Ok(Json(WrappingResponse::okay(webby_api::CmsRowResponse {
+        files: Vec::new(),
+        fields: map_to_field_value(&schema, data_row, None)?,
+    })))
+}
+
+pub async fn import_data_rows(
+    Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+
+    Json(map): Json<HashMap<String, Vec<SimpleValue>>>,
+) -> Result<JsonResponse<&'static str>> {
+    // Uploaded files go through `cms_import` instead, which hashes each row to prevent duplication.
+
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
+    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
+
+    let addon_id = addon.id;
+    let collection = coll.id.clone();
+
+    let row_ids = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                let row_ids = insert_rows(map, addon.id, &schema, trx).await?;
+
+                queue_cms_row_event(
+                    addon.id,
+                    WebhookEvent::CmsRowCreated,
+                    &coll.id,
+                    &row_ids,
+                    trx,
+                )
+                .await?;
+
+                Result::<_, crate::Error>::Ok(row_ids)
+            })
+        })
+        .await?;
+
+    spawn_automation_event(
+        db,
+        AutomationEvent::CmsRow {
+            addon_id,
+            collection,
+            event: CmsRowEvent::Created,
+            row_ids: row_ids.into_iter().map(|v| *v).collect(),
+        },
+    );
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+async fn insert_rows(
+    data: HashMap<String, Vec<SimpleValue>>,
+    addon_id: AddonId,
+    schema: &SchemaModel,
+    db: &mut SqliteConnection,
+) -> Result<Vec<SchemaDataPublicId>> {
+    let mut inserting_rows = data
+        .values()
+        .next()
+        .unwrap()
+        .iter()
+        .map(|_| NewSchemaDataModel::new(addon_id, schema.id))
+        .collect::<Vec<_>>();
+
+    let mut adding_tags = cms_import::load_tag_cache(schema.id, db).await?;
+
+    for (key, mut value) in data {
+        let key = SchematicFieldKey::Other(key);
+
+        if let Some(field) = schema.fields.get(&key) {
+            // TODO: Incorporate into insert_field/parse_value
+            if field.field_type == SchematicFieldType::Tags {
+                for val in value.iter_mut() {
+                    if let SimpleValue::Text(text_list) = val.clone() {
+                        *val = cms_import::resolve_tag_names(
+                            schema.id,
+                            &key.to_string(),
+                            text_list.split(',').map(|v| v.to_string()).collect(),
+                            &mut adding_tags,
+                            db,
+                        )
+                        .await?;
+                    } else {
+                        warn!("Import: Expected Text, Found {val:?}");
+                    }
+                }
+            }
+
+            for (i, value) in value.into_iter().enumerate() {
+                inserting_rows[i].insert_field(
+                    key.to_string(),
+                    false,
+                    field.field_type,
+                    field.field_type.parse_value(value).wrap_err_with(|| {
+                        format!("Parse Value into Type: {:?}", field.field_type)
+                    })?,
+                )?;
+            }
+        }
+    }
+
+    let mut inserted = Vec::new();
+
+    for row in inserting_rows {
+        let model = row.insert(db).await?;
+        inserted.push(SchemaDataPublicId::from(model.public_id));
+    }
+
+    Ok(inserted)
+}
+
+pub async fn duplicate_cms_row_cell(
+    Path((addon_id, coll, row_id)): Path<(Uuid, CollectionName, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<webby_api::CmsRowResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
+    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
+
+    let schema_data = SchemaDataModel::find_by_public_id(row_id, &mut acq)
+        .await?
+        .filter(|v| v.schema_id == schema.id && v.deleted_at.is_none())
+        .context("Schema Data not found")?
+        .into_new()
+        .insert(&mut acq)
+        .await?;
+
+    queue_cms_row_event(
+        addon.id,
+        WebhookEvent::CmsRowCreated,
+        &coll.id,
+        &[schema_data.public_id],
+        &mut acq,
+    )
+    .await?;
+
+    spawn_automation_event(
+        db.clone(),
+        AutomationEvent::CmsRow {
+            addon_id: addon.id,
+            collection: coll.id.clone(),
+            event: CmsRowEvent::Created,
+            row_ids: vec![schema_data.public_id],
+        },
+    );
+
+    Ok(Json(WrappingResponse::okay(webby_api::CmsRowResponse {
+        files: Vec::new(),
+        fields: map_to_field_value(&schema, schema_data, None)?,
+    })))
+}
+
+/// The most rows a single delete, restore or purge request may touch.
+const MAX_BULK_ROWS: usize = 100;
+
+#[derive(Deserialize)]
+pub struct CmsRowsJson {
+    pub rows: Vec<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct CmsPurgeJson {
+    /// Empties the whole trash if not set.
+    #[serde(default)]
+    pub rows: Option<Vec<Uuid>>,
+}
+
+#[derive(Deserialize)]
+pub struct CmsTrashQuery {
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CmsTrashRowResponse {
+    #[serde(flatten)]
+    pub row: CmsRowResponse,
+    pub deleted_at: Option<time::OffsetDateTime>,
+}
+
+fn validate_bulk_rows(rows: &mut Vec<Uuid>) -> Result<()> {
+    rows.sort_unstable();
+    rows.dedup();
+
+    if rows.is_empty() {
+        return Err(eyre::eyre!("No rows provided"))?;
+    }
+
+    if rows.len() > MAX_BULK_ROWS {
+        return Err(eyre::eyre!("Too many rows. Max {MAX_BULK_ROWS}"))?;
+    }
+
+    Ok(())
+}
+
+pub async fn delete_cms_row(
+    Path((addon_id, coll, row_id)): Path<(Uuid, CollectionName, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Vec<Uuid>>> {
+    soft_delete_cms_rows(addon_id, coll, vec![row_id], db, member).await
+}
+
+pub async fn delete_cms_rows(
+    Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+
+    Json(CmsRowsJson { rows }): Json<CmsRowsJson>,
+) -> Result<JsonResponse<Vec<Uuid>>> {
+    soft_delete_cms_rows(addon_id, coll, rows, db, member).await
+}
+
+/// Moves the rows into the trash, returning the ones which were deleted.
+async fn soft_delete_cms_rows(
+    addon_id: Uuid,
+    coll: CollectionName,
+    mut rows: Vec<Uuid>,
+    db: SqlitePool,
+    member: AuthMember,
+) -> Result<JsonResponse<Vec<Uuid>>> {
+    validate_bulk_rows(&mut rows)?;
+
+    let mut acq = db.acquire().await?;
+
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
+
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
+    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
+
+    let addon_id = addon.id;
+    let collection = coll.id.clone();
+
+    let deleted = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                let mut deleted = Vec::new();
+
+                for row_id in rows {
+                    if SchemaDataModel::soft_delete_by_public_id(schema.id, row_id, trx).await? {
+                        deleted.push(row_id);
+                    }
+                }
+
+                if !deleted.is_empty() {
+                    queue_cms_row_event(
+                        addon.id,
+                        WebhookEvent::CmsRowDeleted,
+                        &coll.id,
+                        &deleted,
+                        trx,
+                    )
+                    .await?;
+                }
+
+                Result::<_, crate::Error>::Ok(deleted)
+            })
+        })
+        .await?;
+
+    if deleted.is_empty() {
+        return Err(eyre::eyre!("Schema Data not found"))?;
+    }
+
+    spawn_automation_event(
+        db,
+        AutomationEvent::CmsRow {
+            addon_id,
+            collection,
+            event: CmsRowEvent::Deleted,
+            row_ids: deleted.clone(),
+        },
+    );
+
+    Ok(Json(WrappingResponse::okay(deleted)))
 }
 
-pub async fn update_cms_row_cell(
-    Path((addon_id, coll, row_id)): Path<(Uuid, CollectionName, Uuid)>,
+pub async fn get_cms_trash(
+    Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
     State(db): State<SqlitePool>,
-
-    Json(CmsUpdateDataCell { field_name, value }): Json<CmsUpdateDataCell>,
-) -> Result<JsonResponse<&'static str>> {
+    member: AuthMember,
+    extract::Query(CmsTrashQuery { offset, limit }): extract::Query<CmsTrashQuery>,
+) -> Result<JsonListResponse<CmsTrashRowResponse>> {
     let mut acq = db.acquire().await?;
 
     let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
     let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
         .context("Schema not found")?;
 
-    let Some(schema_field) = schema
-        .fields
-        .get(&SchematicFieldKey::Other(field_name.clone()))
-    else {
-        return Err(eyre::eyre!("Schema Field not found"))?;
-    };
+    let offset = offset.unwrap_or(0).max(0);
+    let limit = limit.unwrap_or(50).clamp(1, 100);
 
-    let Some(schema_data) =
-        SchemaDataFieldUpdate::find_data_field_by_uuid(row_id, schema_field.field_type, &mut acq)
-            .await?
-    else {
-        return Err(eyre::eyre!("Schema Data not found"))?;
-    };
+    let total = SchemaDataModel::count_deleted_by_schema_id(schema.id, &mut acq).await?;
+    let rows =
+        SchemaDataModel::find_deleted_by_schema_id(schema.id, offset, limit, &mut acq).await?;
 
-    schema_data
-        .update(
-            field_name,
-            value
-                .map(|v| schema_field.field_type.parse_value(v))
-                .transpose()?,
-            &mut acq,
-        )
-        .await?;
+    let mut items = Vec::new();
 
-    Ok(Json(WrappingResponse::okay("ok")))
+    for row in rows {
+        let deleted_at = row.deleted_at;
+
+        items.push(CmsTrashRowResponse {
+            row: CmsRowResponse {
+                files: Vec::new(),
+                fields: map_to_field_value(&schema, row, None)?,
+            },
+            deleted_at,
+        });
+    }
+
+    Ok(Json(WrappingResponse::okay(ListResponse {
+        items,
+        offset,
+        limit,
+        total,
+    })))
 }
 
-pub async fn create_new_data_row(
+/// Takes the rows out of the trash, returning the ones which were restored.
+pub async fn restore_cms_rows(
     Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
     State(db): State<SqlitePool>,
-) -> Result<JsonResponse<webby_api::CmsRowResponse>> {
+    member: AuthMember,
+
+    Json(CmsRowsJson { mut rows }): Json<CmsRowsJson>,
+) -> Result<JsonResponse<Vec<Uuid>>> {
+    validate_bulk_rows(&mut rows)?;
+
     let mut acq = db.acquire().await?;
 
     let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
     let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
         .context("Schema not found")?;
 
-    let data_row = NewSchemaDataModel::new(addon.id, schema.id)
-        .insert(&mut acq)
+    let restored = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                let mut restored = Vec::new();
+
+                for row_id in rows {
+                    if SchemaDataModel::restore_by_public_id(schema.id, row_id, trx).await? {
+                        restored.push(row_id);
+                    }
+                }
+
+                // Webhook consumers mirroring the collection saw the row removed.
+                if !restored.is_empty() {
+                    queue_cms_row_event(
+                        addon.id,
+                        WebhookEvent::CmsRowCreated,
+                        &coll.id,
+                        &restored,
+                        trx,
+                    )
+                    .await?;
+                }
+
+                Result::<_, crate::Error>::Ok(restored)
+            })
+        })
         .await?;
 
-    Ok(Json(WrappingResponse::okay(webby_api::CmsRowResponse {
-        files: Vec::new(),
-        fields: map_to_field_value(&schema, data_row, None)?,
-    })))
+    Ok(Json(WrappingResponse::okay(restored)))
 }
 
-pub async fn import_data_rows(
+/// Permanently removes rows in the trash along with the tags only they used.
+pub async fn purge_cms_rows(
     Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
     State(db): State<SqlitePool>,
-
-    Json(map): Json<HashMap<String, Vec<SimpleValue>>>,
-) -> Result<JsonResponse<&'static str>> {
-    // TODO: Receive full file to somehow hash each line to have a unique import_id to prevent duplication?
+    member: AuthMember,
+
+    Json(CmsPurgeJson { rows }): Json<CmsPurgeJson>,
+) -> Result<JsonResponse<i64>> {
+    let rows = match rows {
+        Some(mut rows) => {
+            validate_bulk_rows(&mut rows)?;
+            Some(rows)
+        }
+        None => None,
+    };
 
     let mut acq = db.acquire().await?;
 
@@ -1566,131 +2937,293 @@ pub async fn import_data_rows(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
+
     let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
         .await?
         .context("Schema not found")?;
 
-    acq.transaction(|trx| {
-        Box::pin(async move {
-            insert_rows(map, addon.id, &schema, trx).await?;
+    let purged = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                let models = match rows {
+                    Some(rows) => {
+                        let mut models = Vec::new();
+
+                        for row_id in rows {
+                            if let Some(model) = SchemaDataModel::find_one_deleted_by_public_id(
+                                schema.id, row_id, trx,
+                            )
+                            .await?
+                            {
+                                models.push(model);
+                            }
+                        }
+
+                        models
+                    }
+
+                    None => SchemaDataModel::find_all_deleted_by_schema_id(schema.id, trx).await?,
+                };
+
+                let mut tag_ids = Vec::new();
+                let mut purged = 0;
+
+                for model in models {
+                    if let Some(tags) = model.field_tags.as_ref() {
+                        tag_ids.extend(tags.values().flatten().copied());
+                    }
+
+                    purged += model.delete(trx).await? as i64;
+                }
+
+                tag_ids.sort_unstable();
+                tag_ids.dedup();
 
-            Result::<_, crate::Error>::Ok(())
+                SchemaDataTagModel::delete_unused(schema.id, &tag_ids, trx).await?;
+
+                Result::<_, crate::Error>::Ok(purged)
+            })
         })
-    })
-    .await?;
+        .await?;
 
-    Ok(Json(WrappingResponse::okay("ok")))
+    Ok(Json(WrappingResponse::okay(purged)))
 }
 
-async fn insert_rows(
-    data: HashMap<String, Vec<SimpleValue>>,
-    addon_id: AddonId,
-    schema: &SchemaModel,
-    db: &mut SqliteConnection,
-) -> Result<Vec<SchemaDataPublicId>> {
-    let mut inserting_rows = data
-        .values()
-        .next()
-        .unwrap()
-        .iter()
-        .map(|_| NewSchemaDataModel::new(addon_id, schema.id))
-        .collect::<Vec<_>>();
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum CmsRowOperation {
+    Create {
+        #[serde(default)]
+        fields: HashMap<String, SimpleValue>,
+    },
+    /// Fields set to null are cleared.
+    Update {
+        row: Uuid,
+        fields: HashMap<String, Option<SimpleValue>>,
+    },
+    Delete {
+        row: Uuid,
+    },
+    Duplicate {
+        row: Uuid,
+    },
+}
 
-    for (key, mut value) in data {
-        let key = SchematicFieldKey::Other(key);
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum CmsRowOperationResult {
+    Ok { row: Uuid },
+    Error { error: String },
+}
 
-        if let Some(field) = schema.fields.get(&key) {
-            // TODO: Incorporate into insert_field/parse_value
-            if field.field_type == SchematicFieldType::Tags {
-                let mut adding_tags: HashMap<String, i64> = HashMap::new();
+#[derive(Deserialize)]
+pub struct CmsRowBatchJson {
+    pub operations: Vec<CmsRowOperation>,
+}
 
-                for val in value.iter_mut() {
-                    if let SimpleValue::Text(text_list) = val.clone() {
-                        // Nothing in Text?
-                        if text_list.trim().is_empty() {
-                            *val = SimpleValue::ListNumber(Vec::new());
-                            continue;
-                        }
+/// Applies every operation in a single transaction. An operation which fails validation is skipped and
+/// reported in its result, the others are still applied.
+pub async fn batch_cms_rows(
+    Path((addon_id, coll)): Path<(Uuid, CollectionName)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+
+    Json(CmsRowBatchJson { operations }): Json<CmsRowBatchJson>,
+) -> Result<JsonResponse<Vec<CmsRowOperationResult>>> {
+    if operations.is_empty() {
+        return Err(eyre::eyre!("No operations provided"))?;
+    }
+
+    if operations.len() > MAX_BULK_ROWS {
+        return Err(eyre::eyre!("Too many operations. Max {MAX_BULK_ROWS}"))?;
+    }
+
+    let mut acq = db.acquire().await?;
 
-                        let mut items = Vec::new();
+    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+        .await?
+        .context("Addon not found")?;
 
-                        for text in text_list.split(",") {
-                            let trimmed = text.trim();
+    member
+        .addon_access_error(&addon, AddonCapability::EditData, &mut acq)
+        .await?;
 
-                            // We don't want to call the DB for no reason
-                            if let Some(found) = adding_tags.get(&trimmed.to_lowercase()).copied() {
-                                items.push(found.into());
-                            } else {
-                                let model = SchemaDataTagModel::insert(
-                                    schema.id,
-                                    key.to_string(),
-                                    trimmed.to_string(),
-                                    String::from("#FAF"),
-                                    db,
-                                )
-                                .await?;
+    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
 
-                                adding_tags.insert(model.name.to_lowercase(), *model.id);
+    let addon_id = addon.id;
+    let collection = coll.id.clone();
 
-                                items.push((*model.id as i32).into());
+    let (results, events) = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                let mut results = Vec::new();
+                let mut events: Vec<(CmsRowEvent, Vec<Uuid>)> = vec![
+                    (CmsRowEvent::Created, Vec::new()),
+                    (CmsRowEvent::Updated, Vec::new()),
+                    (CmsRowEvent::Deleted, Vec::new()),
+                ];
+
+                for operation in operations {
+                    match apply_row_operation(addon.id, &schema, operation, trx).await? {
+                        Ok((event, row)) => {
+                            if let Some((_, rows)) = events.iter_mut().find(|(v, _)| *v == event) {
+                                rows.push(row);
                             }
+
+                            results.push(CmsRowOperationResult::Ok { row });
                         }
 
-                        *val = SimpleValue::ListNumber(items);
-                    } else {
-                        warn!("Import: Expected Text, Found {val:?}");
+                        Err(error) => results.push(CmsRowOperationResult::Error { error }),
+                    }
+                }
+
+                for (event, rows) in &events {
+                    if rows.is_empty() {
+                        continue;
                     }
+
+                    let webhook_event = match event {
+                        CmsRowEvent::Created => WebhookEvent::CmsRowCreated,
+                        CmsRowEvent::Updated => WebhookEvent::CmsRowUpdated,
+                        CmsRowEvent::Deleted => WebhookEvent::CmsRowDeleted,
+                    };
+
+                    queue_cms_row_event(addon.id, webhook_event, &coll.id, rows, trx).await?;
+                }
+
+                Result::<_, crate::Error>::Ok((results, events))
+            })
+        })
+        .await?;
+
+    for (event, row_ids) in events {
+        if row_ids.is_empty() {
+            continue;
+        }
+
+        spawn_automation_event(
+            db.clone(),
+            AutomationEvent::CmsRow {
+                addon_id,
+                collection: collection.clone(),
+                event,
+                row_ids,
+            },
+        );
+    }
+
+    Ok(Json(WrappingResponse::okay(results)))
+}
+
+/// The outer error aborts the whole batch, the inner one only fails the operation.
+async fn apply_row_operation(
+    addon_id: AddonId,
+    schema: &SchemaModel,
+    operation: CmsRowOperation,
+    db: &mut SqliteConnection,
+) -> Result<std::result::Result<(CmsRowEvent, Uuid), String>> {
+    match operation {
+        CmsRowOperation::Create { fields } => {
+            let mut row = NewSchemaDataModel::new(addon_id, schema.id);
+
+            for (name, value) in fields {
+                if let Err(e) = set_row_field(schema, &mut row, name, Some(value)) {
+                    return Ok(Err(e.to_string()));
                 }
             }
 
-            for (i, value) in value.into_iter().enumerate() {
-                inserting_rows[i].insert_field(
-                    key.to_string(),
-                    false,
-                    field.field_type,
-                    field.field_type.parse_value(value).wrap_err_with(|| {
-                        format!("Parse Value into Type: {:?}", field.field_type)
-                    })?,
-                )?;
+            let row = row.insert(db).await?;
+
+            Ok(Ok((CmsRowEvent::Created, row.public_id)))
+        }
+
+        CmsRowOperation::Update {
+            row: row_id,
+            fields,
+        } => {
+            let Some(model) = find_active_row(schema, row_id, &mut *db).await? else {
+                return Ok(Err(String::from("Schema Data not found")));
+            };
+
+            let id = model.id;
+            let mut row = model.into_editing();
+
+            for (name, value) in fields {
+                if let Err(e) = set_row_field(schema, &mut row, name, value) {
+                    return Ok(Err(e.to_string()));
+                }
             }
+
+            row.update(id, db).await?;
+
+            Ok(Ok((CmsRowEvent::Updated, row_id)))
         }
-    }
 
-    let mut inserted = Vec::new();
+        CmsRowOperation::Delete { row: row_id } => {
+            if SchemaDataModel::soft_delete_by_public_id(schema.id, row_id, db).await? {
+                Ok(Ok((CmsRowEvent::Deleted, row_id)))
+            } else {
+                Ok(Err(String::from("Schema Data not found")))
+            }
+        }
 
-    for row in inserting_rows {
-        let model = row.insert(db).await?;
-        inserted.push(SchemaDataPublicId::from(model.public_id));
-    }
+        CmsRowOperation::Duplicate { row: row_id } => {
+            let Some(model) = find_active_row(schema, row_id, &mut *db).await? else {
+                return Ok(Err(String::from("Schema Data not found")));
+            };
 
-    Ok(inserted)
-}
+            let row = model.into_new().insert(db).await?;
 
-pub async fn duplicate_cms_row_cell(
-    Path((addon_id, coll, row_id)): Path<(Uuid, CollectionName, Uuid)>,
-    State(db): State<SqlitePool>,
-) -> Result<JsonResponse<webby_api::CmsRowResponse>> {
-    let mut acq = db.acquire().await?;
+            Ok(Ok((CmsRowEvent::Created, row.public_id)))
+        }
+    }
+}
 
-    let addon = AddonModel::find_one_by_guid(addon_id, &mut acq)
+async fn find_active_row(
+    schema: &SchemaModel,
+    row_id: Uuid,
+    db: &mut SqliteConnection,
+) -> Result<Option<SchemaDataModel>> {
+    Ok(SchemaDataModel::find_by_public_id(row_id, db)
         .await?
-        .context("Addon not found")?;
+        .filter(|v| v.schema_id == schema.id && v.deleted_at.is_none()))
+}
 
-    let schema = SchemaModel::find_one_by_public_id(addon.id, &coll.id, &mut acq)
-        .await?
-        .context("Schema not found")?;
+/// Validates the value against the schema field before placing it into the row. `None` clears the field.
+fn set_row_field(
+    schema: &SchemaModel,
+    row: &mut NewSchemaDataModel,
+    name: String,
+    value: Option<SimpleValue>,
+) -> eyre::Result<()> {
+    let Some(field) = schema
+        .fields
+        .get(&SchematicFieldKey::Other(name.clone()))
+        .filter(|v| !v.is_deleted)
+    else {
+        eyre::bail!("Schema Field not found: {name}");
+    };
 
-    let schema_data = SchemaDataModel::find_by_public_id(row_id, &mut acq)
-        .await?
-        .context("Schema Data not found")?
-        .into_new()
-        .insert(&mut acq)
-        .await?;
+    match value {
+        Some(value) => {
+            let value = field
+                .field_type
+                .parse_value(value)
+                .wrap_err_with(|| format!("Parse Value into Type: {:?}", field.field_type))?;
 
-    Ok(Json(WrappingResponse::okay(webby_api::CmsRowResponse {
-        files: Vec::new(),
-        fields: map_to_field_value(&schema, schema_data, None)?,
-    })))
+            row.insert_field(name, false, field.field_type, value)
+        }
+
+        None => {
+            row.remove_field(&name, false, field.field_type);
+            Ok(())
+        }
+    }
 }
 
 fn map_to_field_value(
@@ -2017,3 +3550,74 @@ fn map_to_field_value(
 
     Ok(map)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Addon owned by `member_uuid` for the route tests, which only care about who owns it.
+    pub fn test_addon(member_uuid: Uuid, name_id: &str) -> NewAddonModel {
+        NewAddonModel {
+            member_id: MemberId::from(1),
+            member_uuid,
+            developer_id: None,
+            name: String::from("Test"),
+            name_id: name_id.to_string(),
+            tag_line: String::new(),
+            description: String::new(),
+            icon: None,
+            version: String::new(),
+            action_url: None,
+            root_dashboard_page: None,
+        }
+    }
+
+    #[test]
+    fn slugifies_name_ids() {
+        assert_eq!(slugify_name_id("My Addon"), "my_addon");
+        assert_eq!(slugify_name_id("  Hello,  World!  "), "hello_world");
+        assert_eq!(slugify_name_id("v2.0 -- Beta"), "v2_0_beta");
+        assert_eq!(slugify_name_id("__already_slug__"), "already_slug");
+        assert_eq!(slugify_name_id("Café Menü"), "caf_men");
+    }
+
+    #[test]
+    fn slugifies_empty_titles() {
+        assert_eq!(slugify_name_id(""), "addon");
+        assert_eq!(slugify_name_id("!!!"), "addon");
+        assert_eq!(slugify_name_id("日本語"), "addon");
+    }
+
+    #[tokio::test]
+    async fn suffixes_taken_name_ids() {
+        let pool = database::init_memory().await.unwrap();
+        let mut acq = pool.acquire().await.unwrap();
+
+        for expected in ["my_addon", "my_addon_2", "my_addon_3"] {
+            let name_id = find_available_name_id("My Addon", &mut acq).await.unwrap();
+            assert_eq!(name_id, expected);
+
+            test_addon(Uuid::new_v4(), &name_id)
+                .insert(&mut acq)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn duplicate_name_id_is_a_unique_violation() {
+        let pool = database::init_memory().await.unwrap();
+        let mut acq = pool.acquire().await.unwrap();
+
+        test_addon(Uuid::new_v4(), "test")
+            .insert(&mut acq)
+            .await
+            .unwrap();
+
+        let err = test_addon(Uuid::new_v4(), "test")
+            .insert(&mut acq)
+            .await
+            .unwrap_err();
+        assert!(is_unique_violation(&err));
+    }
+}
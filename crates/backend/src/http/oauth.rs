@@ -0,0 +1,668 @@
+//! Tokens the addon's server calls back into the API with.
+//!
+//! The server exchanges its' client secret for an access token scoped to one of its' instances.
+//! Tokens can only hold the permissions granted to the instance and are revoked once the instance
+//! is uninstalled.
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, Path, State},
+    http::request::Parts,
+    routing::{get, post},
+    Json, Router,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use database::{
+    is_permission_granted, AddonCapability, AddonInstanceGrantModel, AddonInstanceModel,
+    AddonModel, AddonOAuthClientModel, AddonOAuthTokenModel, IssuedOAuthToken,
+    NewAddonOAuthClientModel, NewAddonOAuthTokenModel, SchemaModel, ACCESS_TOKEN_LIFETIME,
+};
+use eyre::ContextCompat;
+use serde::{Deserialize, Serialize};
+use serde_qs::axum::QsQuery;
+use sqlx::{Connection, SqlitePool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use webby_addon_common::{JsonListResponse, JsonResponse, WrappingResponse};
+use webby_global_common::{
+    id::AddonInstanceUuid, request::CmsQuery, response::CmsRowResponse, uuid::CollectionName,
+};
+
+use crate::{Error, Result};
+
+use super::{auth::AuthMember, query_local_cms_rows};
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route("/token", post(issue_token))
+        .route("/revoke", post(revoke_token))
+        .route("/api/instance", get(get_token_instance))
+        .route("/api/schema/:name/query", get(get_token_cms_query))
+}
+
+/// Managing the client secret of an addon.
+pub fn client_routes() -> Router<SqlitePool> {
+    Router::new().route(
+        "/",
+        get(get_client)
+            .post(regenerate_client_secret)
+            .delete(delete_client),
+    )
+}
+
+/// The instance an access token belongs to. Rejects the request if the token isn't valid.
+pub struct AddonToken {
+    pub token: AddonOAuthTokenModel,
+    pub instance: AddonInstanceModel,
+}
+
+impl AddonToken {
+    /// Errors unless the token was granted the permission.
+    pub fn scope_error(&self, scope: &str) -> Result<()> {
+        if self.token.has_scope(scope) {
+            Ok(())
+        } else {
+            Err(Error::Forbidden)
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AddonToken
+where
+    SqlitePool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Ok(TypedHeader(Authorization(bearer))) =
+            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await
+        else {
+            return Err(Error::Unauthorized);
+        };
+
+        let mut acq = SqlitePool::from_ref(state).acquire().await?;
+
+        let Some(token) = AddonOAuthTokenModel::find_one_by_access_token(bearer.token(), &mut acq)
+            .await?
+            .filter(|v| v.is_access_valid())
+        else {
+            return Err(Error::Unauthorized);
+        };
+
+        let Some(instance) = AddonInstanceModel::find_by_id(token.instance_id, &mut acq)
+            .await?
+            .filter(|v| v.deleted_at.is_none())
+        else {
+            return Err(Error::Unauthorized);
+        };
+
+        Ok(Self { token, instance })
+    }
+}
+
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub scope: String,
+}
+
+impl TokenResponse {
+    fn new(issued: IssuedOAuthToken, scopes: &[String]) -> Self {
+        Self {
+            access_token: issued.access_token,
+            token_type: "Bearer",
+            expires_in: ACCESS_TOKEN_LIFETIME.whole_seconds(),
+            refresh_token: issued.refresh_token,
+            scope: scopes.join(" "),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "grant_type", rename_all = "snake_case")]
+pub enum TokenGrantJson {
+    ClientCredentials {
+        /// The addon guid
+        client_id: Uuid,
+        client_secret: String,
+        instance_id: Uuid,
+        /// Space separated permissions. Defaults to every permission granted to the instance.
+        scope: Option<String>,
+    },
+
+    RefreshToken {
+        refresh_token: String,
+        /// Space separated permissions. Can only narrow the refreshed token.
+        scope: Option<String>,
+    },
+}
+
+/// The requested scopes. Errors if one of them can't be granted.
+fn narrow_scopes(granted: Vec<String>, requested: Option<&str>) -> Result<Vec<String>> {
+    let Some(requested) = requested else {
+        return Ok(granted);
+    };
+
+    let mut scopes = Vec::new();
+
+    for scope in requested.split_whitespace() {
+        if !is_permission_granted(&granted, scope) {
+            return Err(eyre::eyre!("Scope \"{scope}\" can't be granted"))?;
+        }
+
+        if !scopes.iter().any(|v| v == scope) {
+            scopes.push(scope.to_string());
+        }
+    }
+
+    Ok(scopes)
+}
+
+async fn issue_token(
+    State(db): State<SqlitePool>,
+    Json(grant): Json<TokenGrantJson>,
+) -> Result<JsonResponse<TokenResponse>> {
+    let mut acq = db.acquire().await?;
+
+    match grant {
+        TokenGrantJson::ClientCredentials {
+            client_id,
+            client_secret,
+            instance_id,
+            scope,
+        } => {
+            let Some(addon) = AddonModel::find_one_by_guid(client_id, &mut acq).await? else {
+                return Err(Error::Unauthorized);
+            };
+
+            if !AddonOAuthClientModel::find_one_by_addon_id(addon.id, &mut acq)
+                .await?
+                .is_some_and(|v| v.is_secret(&client_secret))
+            {
+                return Err(Error::Unauthorized);
+            }
+
+            let instance = AddonInstanceModel::find_by_uuid(instance_id, &mut acq)
+                .await?
+                .filter(|v| v.addon_id == addon.id && v.deleted_at.is_none())
+                .context("Addon Instance not found")?;
+
+            let scopes = narrow_scopes(
+                AddonInstanceGrantModel::find_permissions_by_instance_id(instance.id, &mut acq)
+                    .await?,
+                scope.as_deref(),
+            )?;
+
+            let (_, issued) = NewAddonOAuthTokenModel {
+                addon_id: addon.id,
+                instance_id: instance.id,
+                scopes: scopes.clone(),
+            }
+            .insert(&mut acq)
+            .await?;
+
+            Ok(Json(WrappingResponse::okay(TokenResponse::new(
+                issued, &scopes,
+            ))))
+        }
+
+        TokenGrantJson::RefreshToken {
+            refresh_token,
+            scope,
+        } => {
+            let Some(mut token) =
+                AddonOAuthTokenModel::find_one_by_refresh_token(&refresh_token, &mut acq).await?
+            else {
+                return Err(Error::Unauthorized);
+            };
+
+            if token.revoked_at.is_some() {
+                // Refresh tokens are single use. Reusing one means it leaked.
+                AddonOAuthTokenModel::revoke_by_instance_id(token.instance_id, &mut acq).await?;
+
+                return Err(Error::Unauthorized);
+            }
+
+            if !token.is_refresh_valid() {
+                return Err(Error::Unauthorized);
+            }
+
+            let instance = AddonInstanceModel::find_by_id(token.instance_id, &mut acq)
+                .await?
+                .filter(|v| v.deleted_at.is_none())
+                .ok_or(Error::Unauthorized)?;
+
+            // Permissions the instance is no longer granted are dropped.
+            let instance_granted =
+                AddonInstanceGrantModel::find_permissions_by_instance_id(instance.id, &mut acq)
+                    .await?;
+
+            let granted = token
+                .scopes
+                .iter()
+                .filter(|v| is_permission_granted(&instance_granted, v))
+                .cloned()
+                .collect();
+
+            let scopes = narrow_scopes(granted, scope.as_deref())?;
+
+            let issued = acq
+                .transaction(|trx| {
+                    Box::pin(async move {
+                        token.revoke(trx).await?;
+
+                        let (_, issued) = NewAddonOAuthTokenModel {
+                            addon_id: token.addon_id,
+                            instance_id: token.instance_id,
+                            scopes: scopes.clone(),
+                        }
+                        .insert(trx)
+                        .await?;
+
+                        Result::<_, crate::Error>::Ok(TokenResponse::new(issued, &scopes))
+                    })
+                })
+                .await?;
+
+            Ok(Json(WrappingResponse::okay(issued)))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RevokeTokenJson {
+    /// Either the access or refresh token.
+    pub token: String,
+}
+
+/// Revokes the token. Unknown tokens are ignored.
+async fn revoke_token(
+    State(db): State<SqlitePool>,
+    Json(RevokeTokenJson { token }): Json<RevokeTokenJson>,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let found = match AddonOAuthTokenModel::find_one_by_access_token(&token, &mut acq).await? {
+        Some(v) => Some(v),
+        None => AddonOAuthTokenModel::find_one_by_refresh_token(&token, &mut acq).await?,
+    };
+
+    if let Some(mut found) = found {
+        found.revoke(&mut acq).await?;
+    }
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInstanceResponse {
+    pub instance_id: AddonInstanceUuid,
+    pub website_id: Uuid,
+    pub version: String,
+    pub is_setup: bool,
+    pub settings: Option<serde_json::Value>,
+    pub scopes: Vec<String>,
+    pub expires_at: OffsetDateTime,
+}
+
+/// Describes the token itself so it's available to every valid token.
+async fn get_token_instance(token: AddonToken) -> Result<JsonResponse<TokenInstanceResponse>> {
+    let AddonToken { token, instance } = token;
+
+    Ok(Json(WrappingResponse::okay(TokenInstanceResponse {
+        instance_id: instance.public_id,
+        website_id: instance.website_uuid,
+        version: instance.version,
+        is_setup: instance.is_setup,
+        settings: instance.settings.map(|v| v.0),
+        scopes: token.scopes.0,
+        expires_at: token.access_expires_at,
+    })))
+}
+
+async fn get_token_cms_query(
+    Path(coll): Path<CollectionName>,
+    QsQuery(query): QsQuery<CmsQuery>,
+    State(db): State<SqlitePool>,
+    token: AddonToken,
+) -> Result<JsonListResponse<CmsRowResponse>> {
+    token.scope_error(&format!("cms.data.read.{}", coll.id))?;
+
+    let mut acq = db.acquire().await?;
+
+    let schema = SchemaModel::find_one_by_public_id(token.token.addon_id, &coll.id, &mut acq)
+        .await?
+        .context("Schema not found")?;
+
+    if schema.store == "addon" {
+        return Err(eyre::eyre!("Schema is stored by the addon"))?;
+    }
+
+    Ok(Json(WrappingResponse::okay(
+        query_local_cms_rows(token.token.addon_id, &schema, query, &mut acq).await?,
+    )))
+}
+
+async fn get_client(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Option<AddonOAuthClientModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        AddonOAuthClientModel::find_one_by_addon_id(addon.id, &mut acq).await?,
+    )))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientSecretResponse {
+    pub client_id: Uuid,
+    /// Only shown once.
+    pub client_secret: String,
+}
+
+/// Creates the client or replaces its' secret. Tokens which were already issued stay valid.
+async fn regenerate_client_secret(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<ClientSecretResponse>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    let client_secret =
+        match AddonOAuthClientModel::find_one_by_addon_id(addon.id, &mut acq).await? {
+            Some(mut client) => {
+                let secret = client.regenerate_secret();
+                client.update(&mut acq).await?;
+                secret
+            }
+
+            None => {
+                NewAddonOAuthClientModel { addon_id: addon.id }
+                    .insert(&mut acq)
+                    .await?
+                    .1
+            }
+        };
+
+    Ok(Json(WrappingResponse::okay(ClientSecretResponse {
+        client_id: addon.guid,
+        client_secret,
+    })))
+}
+
+/// Removes the client along with every token issued to it.
+async fn delete_client(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    let client = AddonOAuthClientModel::find_one_by_addon_id(addon.id, &mut acq)
+        .await?
+        .context("OAuth Client not found")?;
+
+    acq.transaction(|trx| {
+        Box::pin(async move {
+            AddonOAuthTokenModel::revoke_by_addon_id(client.addon_id, trx).await?;
+            client.delete(trx).await?;
+
+            Result::<_, crate::Error>::Ok(())
+        })
+    })
+    .await?;
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use database::{NewAddonInstanceGrantModel, NewAddonInstanceModel};
+    use local_common::WebsiteId;
+    use tower::ServiceExt;
+
+    use super::{super::tests::test_addon, *};
+
+    struct Harness {
+        app: Router,
+        pool: SqlitePool,
+        addon: Uuid,
+        instance: Uuid,
+        secret: String,
+    }
+
+    async fn setup() -> Harness {
+        let pool = database::init_memory().await.unwrap();
+        let mut acq = pool.acquire().await.unwrap();
+
+        let addon = test_addon(Uuid::new_v4(), "test")
+            .insert(&mut acq)
+            .await
+            .unwrap();
+
+        let instance = NewAddonInstanceModel {
+            addon_id: addon.id,
+            website_id: WebsiteId::from(1),
+            website_uuid: Uuid::new_v4(),
+            version: String::from("latest"),
+        }
+        .insert(&mut acq)
+        .await
+        .unwrap();
+
+        NewAddonInstanceGrantModel {
+            instance_id: instance.id,
+            permissions: vec![
+                String::from("member.info.read.email"),
+                String::from("member.info.read.name"),
+            ],
+        }
+        .insert(&mut acq)
+        .await
+        .unwrap();
+
+        let (_, secret) = NewAddonOAuthClientModel { addon_id: addon.id }
+            .insert(&mut acq)
+            .await
+            .unwrap();
+
+        Harness {
+            app: super::super::routes().with_state(pool.clone()),
+            pool,
+            addon: addon.guid,
+            instance: *instance.public_id,
+            secret,
+        }
+    }
+
+    async fn grant(pool: &SqlitePool, grant: TokenGrantJson) -> Result<TokenResponse> {
+        match issue_token(State(pool.clone()), Json(grant)).await?.0 {
+            WrappingResponse::Resp(resp) => Ok(resp),
+            WrappingResponse::Error(e) => panic!("{e}"),
+        }
+    }
+
+    async fn client_credentials(h: &Harness, scope: Option<&str>) -> Result<TokenResponse> {
+        grant(
+            &h.pool,
+            TokenGrantJson::ClientCredentials {
+                client_id: h.addon,
+                client_secret: h.secret.clone(),
+                instance_id: h.instance,
+                scope: scope.map(|v| v.to_string()),
+            },
+        )
+        .await
+    }
+
+    async fn refresh(pool: &SqlitePool, refresh_token: &str) -> Result<TokenResponse> {
+        grant(
+            pool,
+            TokenGrantJson::RefreshToken {
+                refresh_token: refresh_token.to_string(),
+                scope: None,
+            },
+        )
+        .await
+    }
+
+    async fn call_api(app: &Router, token: Option<&str>) -> StatusCode {
+        let mut req = Request::builder()
+            .method(Method::GET)
+            .uri("/oauth/api/instance");
+
+        if let Some(token) = token {
+            req = req.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+
+        app.clone()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn issues_tokens_limited_to_addon_permissions() {
+        let h = setup().await;
+
+        let wrong_secret = grant(
+            &h.pool,
+            TokenGrantJson::ClientCredentials {
+                client_id: h.addon,
+                client_secret: String::from("wcs_wrong"),
+                instance_id: h.instance,
+                scope: None,
+            },
+        )
+        .await;
+        assert!(matches!(wrong_secret, Err(Error::Unauthorized)));
+
+        let all = client_credentials(&h, None).await.unwrap();
+        assert_eq!(all.scope, "member.info.read.email member.info.read.name");
+
+        let narrowed = client_credentials(&h, Some("member.info.read.name"))
+            .await
+            .unwrap();
+        assert_eq!(narrowed.scope, "member.info.read.name");
+
+        assert!(client_credentials(&h, Some("website.pages.write"))
+            .await
+            .is_err());
+
+        assert_eq!(call_api(&h.app, None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            call_api(&h.app, Some("wat_unknown")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            call_api(&h.app, Some(&all.access_token)).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn data_requires_granted_scope() {
+        let h = setup().await;
+
+        let token = client_credentials(&h, None).await.unwrap();
+
+        let status = h
+            .app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/oauth/api/schema/posts/query")
+                    .header(
+                        header::AUTHORIZATION,
+                        format!("Bearer {}", token.access_token),
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status();
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn refresh_rotates_and_detects_reuse() {
+        let h = setup().await;
+
+        let first = client_credentials(&h, None).await.unwrap();
+        let second = refresh(&h.pool, &first.refresh_token).await.unwrap();
+
+        assert_eq!(second.scope, first.scope);
+        assert_eq!(
+            call_api(&h.app, Some(&first.access_token)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            call_api(&h.app, Some(&second.access_token)).await,
+            StatusCode::OK
+        );
+
+        // Reusing the old refresh token revokes everything issued for the instance.
+        assert!(matches!(
+            refresh(&h.pool, &first.refresh_token).await,
+            Err(Error::Unauthorized)
+        ));
+        assert_eq!(
+            call_api(&h.app, Some(&second.access_token)).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn revoked_tokens_are_rejected() {
+        let h = setup().await;
+
+        let token = client_credentials(&h, None).await.unwrap();
+
+        revoke_token(
+            State(h.pool.clone()),
+            Json(RevokeTokenJson {
+                token: token.refresh_token.clone(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            call_api(&h.app, Some(&token.access_token)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert!(refresh(&h.pool, &token.refresh_token).await.is_err());
+    }
+}
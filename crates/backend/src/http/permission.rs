@@ -0,0 +1,183 @@
+//! The permissions an addon requests from the installer.
+//!
+//! Permissions are written as `scope.category[.operation[.info]]` and have to refer to a known
+//! permission. Requested permissions are copied into the compiled version on publish.
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use database::{AddonCapability, AddonPermissionModel};
+use local_common::{api::PermissionPublic, KNOWN_PERMISSIONS};
+use serde::Deserialize;
+use sqlx::{Connection, SqlitePool};
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+
+use crate::Result;
+
+use super::auth::AuthMember;
+
+const MAX_PERMISSIONS: usize = 50;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new().route("/", get(get_permissions).post(update_permissions))
+}
+
+/// Every permission an addon can request.
+pub async fn get_known_permissions() -> JsonResponse<Vec<PermissionPublic>> {
+    Json(WrappingResponse::okay(
+        KNOWN_PERMISSIONS
+            .iter()
+            .map(|v| PermissionPublic {
+                permission: v.permission().to_string(),
+                description: v.description.to_string(),
+            })
+            .collect(),
+    ))
+}
+
+async fn get_permissions(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Vec<PermissionPublic>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        AddonPermissionModel::find_by_addon_id(addon.id, &mut acq)
+            .await?
+            .into_iter()
+            .map(|v| PermissionPublic::describe(v.perm.to_string()))
+            .collect(),
+    )))
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePermissionsJson {
+    pub permissions: Vec<String>,
+}
+
+/// Replaces the requested permissions. Installers consent to them once the next version is published.
+async fn update_permissions(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(UpdatePermissionsJson { permissions }): Json<UpdatePermissionsJson>,
+) -> Result<JsonResponse<Vec<PermissionPublic>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    if permissions.len() > MAX_PERMISSIONS {
+        return Err(eyre::eyre!(
+            "An addon can't request more than {MAX_PERMISSIONS} permissions"
+        ))?;
+    }
+
+    let mut perms = Vec::<AddonPermissionModel>::new();
+
+    for permission in &permissions {
+        let perm = AddonPermissionModel::try_from(addon.id, permission.trim())?;
+
+        if !perms.iter().any(|v| v.perm == perm.perm) {
+            perms.push(perm);
+        }
+    }
+
+    let perms = acq
+        .transaction(|trx| {
+            Box::pin(async move {
+                AddonPermissionModel::set_for_addon(addon.id, &perms, trx).await?;
+
+                Result::<_, crate::Error>::Ok(perms)
+            })
+        })
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        perms
+            .into_iter()
+            .map(|v| PermissionPublic::describe(v.perm.to_string()))
+            .collect(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use local_common::AddonPermission;
+
+    fn perm(value: &str) -> AddonPermission {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_displays() {
+        let value = perm("member.info.read.email");
+
+        assert_eq!(value.scope, "member");
+        assert_eq!(value.info.as_deref(), Some("email"));
+        assert_eq!(value.to_string(), "member.info.read.email");
+
+        assert_eq!(perm("cms.*").category, "*");
+
+        for invalid in [
+            "member",
+            "member.info.read.email.extra",
+            "*.info",
+            "member.*.read",
+            "member..read",
+            "Member.info",
+        ] {
+            assert!(invalid.parse::<AddonPermission>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn grants_children_and_wildcards() {
+        assert!(perm("member.info.read").grants(&perm("member.info.read.email")));
+        assert!(perm("member.info.*").grants(&perm("member.info.read.name")));
+        assert!(perm("cms.*").grants(&perm("cms.data.write")));
+        assert!(perm("cms.data.read").grants(&perm("cms.data.read")));
+
+        assert!(!perm("member.info.read.email").grants(&perm("member.info.read")));
+        assert!(!perm("member.info.read.*").grants(&perm("member.info.read")));
+        assert!(!perm("cms.data.read").grants(&perm("cms.data.write")));
+        assert!(!perm("member.info.*").grants(&perm("member.billing.read")));
+    }
+
+    #[test]
+    fn validates_against_known() {
+        assert!(perm("member.info.read.email").validate().is_ok());
+        assert!(perm("cms.*").validate().is_ok());
+        // Narrowed to a single collection
+        assert!(perm("cms.data.read.posts").validate().is_ok());
+
+        assert!(perm("member.billing.read").validate().is_err());
+        assert!(perm("shop.*").validate().is_err());
+    }
+
+    #[test]
+    fn describes() {
+        assert_eq!(
+            perm("member.info.read.email").describe(),
+            "Read your email address"
+        );
+        assert_eq!(
+            perm("cms.data.read.posts").describe(),
+            "Read the items of the website's collections (cms.data.read.posts)"
+        );
+        assert_eq!(
+            perm("member.info.read.*").describe(),
+            "Read your email address, Read your name and tag, Read your avatar"
+        );
+        assert_eq!(perm("member.*").describe(), "Read your profile");
+    }
+}
@@ -0,0 +1,314 @@
+//! Marketplace reviews of an addon.
+//!
+//! Each member can leave one review. Reviews are marked as verified when the member has the addon
+//! installed on one of their websites. Developers reply under the reviews and moderate them.
+
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use database::{
+    AddonCapability, AddonCollaboratorModel, AddonCommentModel, AddonInstanceModel, AddonModel,
+    NewAddonCommentModel, REPORT_HIDE_THRESHOLD,
+};
+use eyre::ContextCompat;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use webby_addon_common::{JsonResponse, WrappingResponse};
+
+use crate::Result;
+
+use super::auth::AuthMember;
+
+const MAX_BODY_LENGTH: usize = 5_000;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route(
+            "/",
+            get(get_reviews).post(post_review).delete(delete_review),
+        )
+        .route("/manage", get(get_all_reviews))
+        .route("/:comment/reply", post(reply_to_review))
+        .route("/:comment/hide", post(hide_comment))
+        .route("/:comment/report", post(report_comment))
+}
+
+async fn find_addon(addon_id: Uuid, db: &mut SqliteConnection) -> Result<AddonModel> {
+    Ok(AddonModel::find_one_by_guid(addon_id, db)
+        .await?
+        .context("Addon not found")?)
+}
+
+fn validate_body(body: &str) -> Result<()> {
+    if body.trim().is_empty() {
+        return Err(eyre::eyre!("Review can't be empty"))?;
+    }
+
+    if body.len() > MAX_BODY_LENGTH {
+        return Err(eyre::eyre!(
+            "Review can't be longer than {MAX_BODY_LENGTH} characters"
+        ))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewResponse {
+    #[serde(flatten)]
+    pub review: AddonCommentModel,
+    pub replies: Vec<AddonCommentModel>,
+}
+
+/// Threads the replies under their reviews.
+fn thread_comments(comments: Vec<AddonCommentModel>) -> Vec<ReviewResponse> {
+    let (reviews, mut replies): (Vec<_>, Vec<_>) =
+        comments.into_iter().partition(|v| v.is_review());
+
+    // Oldest reply first.
+    replies.reverse();
+
+    reviews
+        .into_iter()
+        .map(|review| {
+            let (own, rest) = std::mem::take(&mut replies)
+                .into_iter()
+                .partition(|v| v.parent_id == Some(review.id));
+
+            replies = rest;
+
+            ReviewResponse {
+                review,
+                replies: own,
+            }
+        })
+        .collect()
+}
+
+async fn get_reviews(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+) -> Result<JsonResponse<Vec<ReviewResponse>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(addon_id, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(thread_comments(
+        AddonCommentModel::find_by_addon_id(addon.id, false, &mut acq).await?,
+    ))))
+}
+
+/// Includes the hidden reviews and replies.
+async fn get_all_reviews(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Vec<ReviewResponse>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageReviews, &mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(thread_comments(
+        AddonCommentModel::find_by_addon_id(addon.id, true, &mut acq).await?,
+    ))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostReviewJson {
+    pub rating: i32,
+    pub body: String,
+    /// The website the member has the addon installed on.
+    pub website_id: Option<Uuid>,
+}
+
+/// Creates or updates the review of the calling member.
+async fn post_review(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<PostReviewJson>,
+) -> Result<JsonResponse<AddonCommentModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(addon_id, &mut acq).await?;
+
+    if AddonCollaboratorModel::find_role(&addon, member.uuid(), &mut acq)
+        .await?
+        .is_some()
+    {
+        return Err(eyre::eyre!("Members of the addon can't review it"))?;
+    }
+
+    if !(1..=5).contains(&value.rating) {
+        return Err(eyre::eyre!("Rating must be between 1 and 5"))?;
+    }
+
+    validate_body(&value.body)?;
+
+    let is_verified = match value.website_id {
+        Some(website_id) => {
+            member.website_access_error(website_id).await?;
+
+            AddonInstanceModel::find_by_addon_website_id(addon.id, website_id, &mut acq)
+                .await?
+                .is_some()
+        }
+
+        None => false,
+    };
+
+    let review =
+        match AddonCommentModel::find_one_review_by_member(addon.id, member.uuid(), &mut acq)
+            .await?
+        {
+            Some(mut review) => {
+                review.rating = Some(value.rating);
+                review.body = value.body;
+                review.is_verified |= is_verified;
+                review.update(&mut acq).await?;
+
+                review
+            }
+
+            None => {
+                NewAddonCommentModel {
+                    addon_id: addon.id,
+                    parent_id: None,
+                    member_uuid: member.uuid(),
+                    rating: Some(value.rating),
+                    body: value.body,
+                    is_verified,
+                }
+                .insert(&mut acq)
+                .await?
+            }
+        };
+
+    AddonCommentModel::refresh_addon_rating(addon.id, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(review)))
+}
+
+async fn delete_review(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(addon_id, &mut acq).await?;
+
+    let review = AddonCommentModel::find_one_review_by_member(addon.id, member.uuid(), &mut acq)
+        .await?
+        .context("Review not found")?;
+
+    review.delete(&mut acq).await?;
+
+    AddonCommentModel::refresh_addon_rating(addon.id, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+#[derive(Deserialize)]
+pub struct ReplyJson {
+    pub body: String,
+}
+
+async fn reply_to_review(
+    Path((addon_id, comment_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<ReplyJson>,
+) -> Result<JsonResponse<AddonCommentModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageReviews, &mut acq)
+        .await?;
+
+    validate_body(&value.body)?;
+
+    let review = AddonCommentModel::find_one_by_public_id(addon.id, comment_id, &mut acq)
+        .await?
+        .filter(|v| v.is_review())
+        .context("Review not found")?;
+
+    let reply = NewAddonCommentModel {
+        addon_id: addon.id,
+        parent_id: Some(review.id),
+        member_uuid: member.uuid(),
+        rating: None,
+        body: value.body,
+        is_verified: false,
+    }
+    .insert(&mut acq)
+    .await?;
+
+    Ok(Json(WrappingResponse::okay(reply)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HideJson {
+    pub is_hidden: bool,
+}
+
+async fn hide_comment(
+    Path((addon_id, comment_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<HideJson>,
+) -> Result<JsonResponse<AddonCommentModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageReviews, &mut acq)
+        .await?;
+
+    let mut comment = AddonCommentModel::find_one_by_public_id(addon.id, comment_id, &mut acq)
+        .await?
+        .context("Comment not found")?;
+
+    // Doesn't change the rating. Only reports remove a review from it.
+    comment.is_hidden = value.is_hidden;
+    comment.update(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(comment)))
+}
+
+#[derive(Deserialize)]
+pub struct ReportJson {
+    pub reason: Option<String>,
+}
+
+async fn report_comment(
+    Path((addon_id, comment_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(value): Json<ReportJson>,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = find_addon(addon_id, &mut acq).await?;
+
+    let mut comment = AddonCommentModel::find_one_by_public_id(addon.id, comment_id, &mut acq)
+        .await?
+        .context("Comment not found")?;
+
+    let is_reported = comment
+        .report(member.uuid(), value.reason, &mut acq)
+        .await?;
+
+    if is_reported && comment.report_count == REPORT_HIDE_THRESHOLD && comment.is_review() {
+        AddonCommentModel::refresh_addon_rating(addon.id, &mut acq).await?;
+    }
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
@@ -0,0 +1,524 @@
+//! Scripts and styles an addon injects into every page of the published website.
+//!
+//! Plugins are versioned with the addon. A website gets the plugins of the version each of its'
+//! instances is on, with `{{settings.name}}` placeholders replaced by the instance settings.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use database::{
+    AddonCapability, AddonCompiledModel, AddonInstanceModel, AddonModel, AddonSitePluginModel,
+    ExtensionKind, NewAddonSitePluginModel, SitePluginContent, SitePluginPlacement,
+};
+use eyre::ContextCompat;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use webby_addon_common::{JsonListResponse, JsonResponse, ListResponse, WrappingResponse};
+use webby_global_common::id::AddonInstanceUuid;
+
+use crate::Result;
+
+use super::{auth::AuthMember, extension::require_extension, install_settings};
+
+const MAX_SITE_PLUGINS: usize = 20;
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_INLINE_LENGTH: usize = 64 * 1024;
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route("/", get(get_site_plugin_list).post(create_site_plugin))
+        .route(
+            "/:plugin",
+            get(get_site_plugin)
+                .post(update_site_plugin)
+                .delete(delete_site_plugin),
+        )
+}
+
+/// Replaces every `{{settings.name}}` with the escaped setting. Unknown settings are removed.
+fn substitute_settings(
+    template: &str,
+    settings: &Map<String, Value>,
+    escape: fn(&str) -> String,
+) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+
+        output.push_str(&rest[..start]);
+
+        let key = rest[start + 2..start + end].trim();
+
+        match key.strip_prefix("settings.") {
+            Some(name) => {
+                let value = match settings.get(name) {
+                    Some(Value::String(v)) => v.clone(),
+                    Some(Value::Null) | None => String::new(),
+                    Some(v) => v.to_string(),
+                };
+
+                output.push_str(&escape(&value));
+            }
+
+            // Not a placeholder
+            None => output.push_str(&rest[start..start + end + 2]),
+        }
+
+        rest = &rest[start + end + 2..];
+    }
+
+    output.push_str(rest);
+
+    output
+}
+
+/// Safe inside of a JS string literal in a script tag.
+fn escape_script(value: &str) -> String {
+    let value = serde_json::to_string(value).unwrap_or_default();
+
+    value[1..value.len() - 1].replace('<', "\\u003c")
+}
+
+/// Values can't break out of the declaration they're in.
+fn escape_style(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '<' | '>' | '{' | '}' | ';' | '"' | '\'' | '\\'))
+        .collect()
+}
+
+fn escape_url(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+fn resolve_content(
+    content: &SitePluginContent,
+    settings: &Map<String, Value>,
+) -> SitePluginContent {
+    let url = |v: &Option<String>| {
+        v.as_deref()
+            .map(|v| substitute_settings(v, settings, escape_url))
+    };
+
+    match content {
+        SitePluginContent::Script {
+            src,
+            inline,
+            strategy,
+        } => SitePluginContent::Script {
+            src: url(src),
+            inline: inline
+                .as_deref()
+                .map(|v| substitute_settings(v, settings, escape_script)),
+            strategy: *strategy,
+        },
+
+        SitePluginContent::Style { href, inline } => SitePluginContent::Style {
+            href: url(href),
+            inline: inline
+                .as_deref()
+                .map(|v| substitute_settings(v, settings, escape_style)),
+        },
+    }
+}
+
+fn validate_site_plugin(name: &str, content: &SitePluginContent) -> Result<()> {
+    if name.trim().is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(eyre::eyre!(
+            "Name must be between 1 and {MAX_NAME_LENGTH} characters"
+        ))?;
+    }
+
+    let (url, inline, closing_tag) = match content {
+        SitePluginContent::Script { src, inline, .. } => (src, inline, "</script"),
+        SitePluginContent::Style { href, inline } => (href, inline, "</style"),
+    };
+
+    match (url, inline) {
+        (Some(url), None) => {
+            if !url.starts_with("https://") {
+                return Err(eyre::eyre!("Plugin URL must start with https://"))?;
+            }
+        }
+
+        (None, Some(inline)) => {
+            if inline.len() > MAX_INLINE_LENGTH {
+                return Err(eyre::eyre!(
+                    "Inline plugins can't be larger than {}KB",
+                    MAX_INLINE_LENGTH / 1024
+                ))?;
+            }
+
+            if inline.to_ascii_lowercase().contains(closing_tag) {
+                return Err(eyre::eyre!("Inline plugin can't contain \"{closing_tag}\""))?;
+            }
+        }
+
+        _ => {
+            return Err(eyre::eyre!(
+                "Plugin must have either a URL or inline content"
+            ))?
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedSitePlugin {
+    pub addon_id: Uuid,
+    pub instance_id: AddonInstanceUuid,
+    pub name: String,
+    pub placement: SitePluginPlacement,
+    #[serde(flatten)]
+    pub content: SitePluginContent,
+}
+
+/// The plugins to inject into the website, in order.
+pub async fn get_website_site_plugins(
+    Path(website): Path<Uuid>,
+    State(db): State<SqlitePool>,
+) -> Result<JsonListResponse<ResolvedSitePlugin>> {
+    let mut acq = db.acquire().await?;
+
+    let mut items = Vec::new();
+
+    for instance in AddonInstanceModel::find_by_website_uuid(website, &mut acq).await? {
+        let Some(addon) = AddonModel::find_one_by_id(instance.addon_id, &mut acq).await? else {
+            continue;
+        };
+
+        if addon.deleted_at.is_some() || !instance.is_usable(&mut acq).await? {
+            continue;
+        }
+
+        let Some(compiled) = AddonCompiledModel::find_one_by_addon_uuid_and_version(
+            addon.id,
+            &instance.version,
+            &mut acq,
+        )
+        .await?
+        else {
+            continue;
+        };
+
+        let plugins = AddonSitePluginModel::find_by_compiled_id(compiled.pk, &mut acq)
+            .await?
+            .into_iter()
+            .filter(|v| v.is_enabled)
+            .collect::<Vec<_>>();
+
+        if plugins.is_empty() {
+            continue;
+        }
+
+        let overrides = match instance.settings.as_ref().map(|v| &v.0) {
+            Some(Value::Object(map)) => map.clone(),
+            _ => Map::new(),
+        };
+
+        // One misconfigured instance shouldn't stop the website from rendering.
+        let settings = match install_settings::resolve_instance_settings(
+            &instance, &overrides, &mut acq,
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Site Plugin Settings for {} on {website}: {e}", addon.guid);
+                overrides
+            }
+        };
+
+        for plugin in plugins {
+            items.push((
+                plugin.sort_order,
+                ResolvedSitePlugin {
+                    addon_id: addon.guid,
+                    instance_id: instance.public_id,
+                    name: plugin.name,
+                    placement: plugin.placement.0,
+                    content: resolve_content(&plugin.content, &settings),
+                },
+            ));
+        }
+    }
+
+    // Stable so an addons' plugins stay in the order they were created in.
+    items.sort_by_key(|(sort_order, v)| (v.placement, *sort_order));
+
+    Ok(Json(WrappingResponse::okay(ListResponse::all(
+        items.into_iter().map(|(_, v)| v).collect(),
+    ))))
+}
+
+#[derive(Deserialize)]
+pub struct SitePluginListQuery {
+    /// Returns the plugins of the compiled version instead of the drafts.
+    pub version: Option<String>,
+}
+
+async fn get_site_plugin_list(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Query(SitePluginListQuery { version }): Query<SitePluginListQuery>,
+) -> Result<JsonResponse<Vec<AddonSitePluginModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    let items = if let Some(version) = version {
+        let compiled =
+            AddonCompiledModel::find_one_by_addon_uuid_and_version(addon.id, &version, &mut acq)
+                .await?
+                .context("Version not found")?;
+
+        AddonSitePluginModel::find_by_compiled_id(compiled.pk, &mut acq).await?
+    } else {
+        AddonSitePluginModel::find_drafts_by_addon_id(addon.id, &mut acq).await?
+    };
+
+    Ok(Json(WrappingResponse::okay(items)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SitePluginJson {
+    pub name: String,
+    pub placement: SitePluginPlacement,
+    pub content: SitePluginContent,
+    #[serde(default)]
+    pub sort_order: i32,
+    pub is_enabled: Option<bool>,
+}
+
+async fn create_site_plugin(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(SitePluginJson {
+        name,
+        placement,
+        content,
+        sort_order,
+        is_enabled,
+    }): Json<SitePluginJson>,
+) -> Result<JsonResponse<AddonSitePluginModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    require_extension(addon.id, ExtensionKind::SitePlugin, &mut acq).await?;
+
+    validate_site_plugin(&name, &content)?;
+
+    if AddonSitePluginModel::find_drafts_by_addon_id(addon.id, &mut acq)
+        .await?
+        .len()
+        >= MAX_SITE_PLUGINS
+    {
+        return Err(eyre::eyre!(
+            "An addon can't have more than {MAX_SITE_PLUGINS} site plugins"
+        ))?;
+    }
+
+    let plugin = NewAddonSitePluginModel {
+        addon_id: addon.id,
+        compiled_id: None,
+        name,
+        placement,
+        content,
+        sort_order,
+        is_enabled: is_enabled.unwrap_or(true),
+    }
+    .insert(&mut acq)
+    .await?;
+
+    Ok(Json(WrappingResponse::okay(plugin)))
+}
+
+async fn get_site_plugin(
+    Path((addon_id, plugin_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<AddonSitePluginModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::View, &mut acq)
+        .await?;
+
+    let plugin = AddonSitePluginModel::find_one_draft_by_public_id(addon.id, plugin_id, &mut acq)
+        .await?
+        .context("Site Plugin not found")?;
+
+    Ok(Json(WrappingResponse::okay(plugin)))
+}
+
+async fn update_site_plugin(
+    Path((addon_id, plugin_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(SitePluginJson {
+        name,
+        placement,
+        content,
+        sort_order,
+        is_enabled,
+    }): Json<SitePluginJson>,
+) -> Result<JsonResponse<AddonSitePluginModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    let mut plugin =
+        AddonSitePluginModel::find_one_draft_by_public_id(addon.id, plugin_id, &mut acq)
+            .await?
+            .context("Site Plugin not found")?;
+
+    validate_site_plugin(&name, &content)?;
+
+    plugin.name = name;
+    plugin.placement.0 = placement;
+    plugin.content.0 = content;
+    plugin.sort_order = sort_order;
+
+    if let Some(is_enabled) = is_enabled {
+        plugin.is_enabled = is_enabled;
+    }
+
+    plugin.update(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(plugin)))
+}
+
+async fn delete_site_plugin(
+    Path((addon_id, plugin_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<&'static str>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::EditCode, &mut acq)
+        .await?;
+
+    let plugin = AddonSitePluginModel::find_one_draft_by_public_id(addon.id, plugin_id, &mut acq)
+        .await?
+        .context("Site Plugin not found")?;
+
+    plugin.delete(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay("ok")))
+}
+
+#[cfg(test)]
+mod tests {
+    use database::ScriptStrategy;
+    use serde_json::json;
+
+    use super::*;
+
+    fn settings() -> Map<String, Value> {
+        json!({
+            "siteId": "abc\"</script>",
+            "count": 3,
+            "color": "red;} body { display: none",
+            "query": "a b&c",
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    #[test]
+    fn substitutes_and_escapes_settings() {
+        let resolved = resolve_content(
+            &SitePluginContent::Script {
+                src: None,
+                inline: Some(
+                    "init(\"{{ settings.siteId }}\", {{settings.count}}, \"{{settings.missing}}\", {{other}})"
+                        .into(),
+                ),
+                strategy: Some(ScriptStrategy::Defer),
+            },
+            &settings(),
+        );
+
+        assert_eq!(
+            resolved,
+            SitePluginContent::Script {
+                src: None,
+                inline: Some("init(\"abc\\\"\\u003c/script>\", 3, \"\", {{other}})".into()),
+                strategy: Some(ScriptStrategy::Defer),
+            }
+        );
+
+        let resolved = resolve_content(
+            &SitePluginContent::Style {
+                href: Some("https://fonts.example.com/css?q={{settings.query}}".into()),
+                inline: None,
+            },
+            &settings(),
+        );
+
+        assert_eq!(
+            resolved,
+            SitePluginContent::Style {
+                href: Some("https://fonts.example.com/css?q=a+b%26c".into()),
+                inline: None,
+            }
+        );
+
+        assert_eq!(
+            substitute_settings(
+                "a { color: {{settings.color}}; }",
+                &settings(),
+                escape_style
+            ),
+            "a { color: red body  display: none; }"
+        );
+    }
+
+    #[test]
+    fn validates_site_plugins() {
+        let script = |src: Option<&str>, inline: Option<&str>| SitePluginContent::Script {
+            src: src.map(|v| v.into()),
+            inline: inline.map(|v| v.into()),
+            strategy: None,
+        };
+
+        assert!(
+            validate_site_plugin("Analytics", &script(Some("https://a.com/a.js"), None)).is_ok()
+        );
+        assert!(validate_site_plugin("Analytics", &script(None, Some("init()"))).is_ok());
+
+        assert!(validate_site_plugin("", &script(None, Some("init()"))).is_err());
+        assert!(
+            validate_site_plugin("Analytics", &script(Some("http://a.com/a.js"), None)).is_err()
+        );
+        assert!(validate_site_plugin("Analytics", &script(None, None)).is_err());
+        assert!(validate_site_plugin(
+            "Analytics",
+            &script(Some("https://a.com/a.js"), Some("init()"))
+        )
+        .is_err());
+        assert!(validate_site_plugin("Analytics", &script(None, Some("a</SCRIPT>b"))).is_err());
+    }
+}
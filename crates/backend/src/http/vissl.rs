@@ -4,8 +4,9 @@ use axum::{
     Json, Router,
 };
 use database::{
-    AddonModel, AddonWidgetContent, AddonWidgetPanelContentModel, NewVisslCodeAddonModel,
-    NewVisslCodeAddonPanelModel, VisslCodeAddonModel, VisslCodeAddonPanelModel,
+    AddonCapability, AddonModel, AddonWidgetContent, AddonWidgetPanelContentModel,
+    NewVisslCodeAddonModel, NewVisslCodeAddonPanelModel, VisslCodeAddonModel,
+    VisslCodeAddonPanelModel,
 };
 use eyre::ContextCompat;
 use sqlx::SqlitePool;
@@ -18,6 +19,8 @@ use webby_scripting::json::VisslContent;
 
 use crate::Result;
 
+use super::auth::AuthMember;
+
 pub fn routes() -> Router<SqlitePool> {
     Router::new()
         .route(
@@ -38,12 +41,11 @@ pub fn routes() -> Router<SqlitePool> {
 
 // Addon Scripting
 
-// TODO: Access Checks
-
 // TODO: Code should be compiled on save.
 
 async fn compile_widget_script(
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Path((addon_id, widget_id)): Path<(AddonUuid, AddonWidgetPublicId)>,
 ) -> Result<Json<Option<String>>> {
     let mut acq = db.acquire().await?;
@@ -52,6 +54,10 @@ async fn compile_widget_script(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
     let addon_widget = AddonWidgetContent::find_one_by_public_id_no_data(widget_id, &mut acq)
         .await?
         .context("Addon Widget page not found")?;
@@ -71,6 +77,7 @@ async fn compile_widget_script(
 
 async fn get_widget_script(
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Path((addon_id, widget_id)): Path<(AddonUuid, AddonWidgetPublicId)>,
 ) -> Result<JsonResponse<Option<Either<VisslContent, String>>>> {
     let mut acq = db.acquire().await?;
@@ -79,6 +86,10 @@ async fn get_widget_script(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::View, &mut acq)
+        .await?;
+
     let addon_widget = AddonWidgetContent::find_one_by_public_id_no_data(widget_id, &mut acq)
         .await?
         .context("Addon Widget page not found")?;
@@ -95,6 +106,7 @@ async fn get_widget_script(
 
 async fn update_widget_script(
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Path((addon_id, widget_id)): Path<(AddonUuid, AddonWidgetPublicId)>,
 
     Json(visual_or_script): Json<Either<VisslContent, String>>,
@@ -105,6 +117,10 @@ async fn update_widget_script(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
     let addon_widget = AddonWidgetContent::find_one_by_public_id_no_data(widget_id, &mut acq)
         .await?
         .context("Addon Widget page not found")?;
@@ -172,12 +188,11 @@ async fn update_widget_script(
     Ok(Json(WrappingResponse::okay("ok")))
 }
 
-// TODO: Access Checks
-
 // TODO: Code should be compiled on save.
 
 async fn compile_widget_panel_script(
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Path((addon_id, widget_id, panel_id)): Path<(
         AddonUuid,
         AddonWidgetPublicId,
@@ -190,6 +205,10 @@ async fn compile_widget_panel_script(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
     let _addon_widget = AddonWidgetContent::find_one_by_public_id_no_data(widget_id, &mut acq)
         .await?
         .context("Addon Widget page not found")?;
@@ -217,6 +236,7 @@ async fn compile_widget_panel_script(
 
 async fn get_widget_panel_script(
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Path((addon_id, widget_id, panel_id)): Path<(
         AddonUuid,
         AddonWidgetPublicId,
@@ -229,6 +249,10 @@ async fn get_widget_panel_script(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::View, &mut acq)
+        .await?;
+
     let _addon_widget = AddonWidgetContent::find_one_by_public_id_no_data(widget_id, &mut acq)
         .await?
         .context("Addon Widget page not found")?;
@@ -253,6 +277,7 @@ async fn get_widget_panel_script(
 
 async fn update_widget_panel_script(
     State(db): State<SqlitePool>,
+    member: AuthMember,
     Path((addon_id, widget_id, panel_id)): Path<(
         AddonUuid,
         AddonWidgetPublicId,
@@ -267,6 +292,10 @@ async fn update_widget_panel_script(
         .await?
         .context("Addon not found")?;
 
+    member
+        .addon_access_error(&addon, AddonCapability::EditCode, &mut acq)
+        .await?;
+
     let addon_widget = AddonWidgetContent::find_one_by_public_id_no_data(widget_id, &mut acq)
         .await?
         .context("Addon Widget page not found")?;
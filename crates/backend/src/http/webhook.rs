@@ -0,0 +1,827 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use database::{
+    AddonCapability, AddonModel, AddonWebhookDeliveryModel, AddonWebhookModel,
+    NewAddonWebhookDeliveryModel, NewAddonWebhookModel, WebhookEvent,
+};
+use eyre::ContextCompat;
+use local_common::AddonId;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::{SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use webby_addon_common::{JsonListResponse, JsonResponse, WrappingResponse};
+use webby_api::ListResponse;
+
+use crate::Result;
+
+use super::auth::AuthMember;
+
+/// Delivery is given up on after this many attempts.
+const MAX_DELIVERY_ATTEMPTS: i32 = 8;
+const RETRY_BASE_DELAY: time::Duration = time::Duration::seconds(30);
+const RETRY_MAX_DELAY: time::Duration = time::Duration::hours(6);
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const WORKER_INTERVAL: Duration = Duration::from_secs(5);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub fn routes() -> Router<SqlitePool> {
+    Router::new()
+        .route("/", get(get_webhook).post(update_webhook))
+        .route("/secret", post(rotate_webhook_secret))
+        .route("/deliveries", get(get_delivery_list))
+        .route(
+            "/deliveries/:delivery/redeliver",
+            post(redeliver_webhook_delivery),
+        )
+}
+
+/// Queues the event for the addon if it's subscribed to it.
+pub async fn queue_webhook_event(
+    addon_id: AddonId,
+    event: WebhookEvent,
+    payload: serde_json::Value,
+    db: &mut SqliteConnection,
+) -> Result<()> {
+    let Some(webhook) = AddonWebhookModel::find_one_by_addon_id(addon_id, db).await? else {
+        return Ok(());
+    };
+
+    if webhook.is_subscribed(event) {
+        NewAddonWebhookDeliveryModel {
+            webhook_id: webhook.id,
+            addon_id,
+            event,
+            payload,
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn queue_cms_row_event<T: serde::Serialize>(
+    addon_id: AddonId,
+    event: WebhookEvent,
+    collection: &str,
+    row_ids: &[T],
+    db: &mut SqliteConnection,
+) -> Result<()> {
+    queue_webhook_event(
+        addon_id,
+        event,
+        serde_json::json!({
+            "collection": collection,
+            "rowIds": row_ids,
+        }),
+        db,
+    )
+    .await
+}
+
+/// Sends queued deliveries in the background and sweeps the delivery log.
+pub fn spawn_delivery_worker(pool: SqlitePool) {
+    tokio::spawn(async move {
+        let mut last_sweep: Option<std::time::Instant> = None;
+
+        loop {
+            if let Err(e) = process_due_deliveries(&pool).await {
+                error!("Webhook Delivery Error: {e}");
+            }
+
+            if last_sweep.map_or(true, |v| v.elapsed() >= SWEEP_INTERVAL) {
+                last_sweep = Some(std::time::Instant::now());
+
+                match sweep_delivery_log(&pool).await {
+                    Ok(0) => (),
+                    Ok(count) => debug!("Removed {count} expired webhook deliveries"),
+                    Err(e) => error!("Webhook Sweep Error: {e}"),
+                }
+            }
+
+            tokio::time::sleep(WORKER_INTERVAL).await;
+        }
+    });
+}
+
+pub async fn sweep_delivery_log(db: &SqlitePool) -> Result<u64> {
+    Ok(AddonWebhookDeliveryModel::delete_expired(&mut *db.acquire().await?).await?)
+}
+
+/// Attempts every delivery which is due. Returns how many were attempted.
+///
+/// No connection is held while a request is being sent so slow endpoints don't starve the pool.
+pub async fn process_due_deliveries(db: &SqlitePool) -> Result<usize> {
+    let due = AddonWebhookDeliveryModel::find_due(50, &mut *db.acquire().await?).await?;
+    let count = due.len();
+
+    for mut delivery in due {
+        let webhook =
+            AddonWebhookModel::find_one_by_id(delivery.webhook_id, &mut *db.acquire().await?)
+                .await?;
+
+        match webhook {
+            Some(webhook) if webhook.is_active => {
+                attempt_delivery(&webhook, &mut delivery).await;
+            }
+
+            // Subscription was removed or disabled after the event was queued.
+            _ => delivery.next_attempt_at = None,
+        }
+
+        delivery.update(&mut *db.acquire().await?).await?;
+    }
+
+    Ok(count)
+}
+
+async fn attempt_delivery(webhook: &AddonWebhookModel, delivery: &mut AddonWebhookDeliveryModel) {
+    let now = OffsetDateTime::now_utc();
+
+    delivery.attempts += 1;
+
+    let body = serde_json::json!({
+        "id": delivery.public_id,
+        "event": delivery.event,
+        "createdAt": delivery.created_at,
+        "data": delivery.payload.0,
+    })
+    .to_string();
+
+    let timestamp = now.unix_timestamp().to_string();
+
+    // The domain could resolve somewhere else since it was subscribed.
+    let client = match delivery_client(&webhook.url).await {
+        Ok(v) => v,
+        Err(e) => {
+            delivery.response_status = None;
+            delivery.response_body = Some(e.to_string());
+
+            schedule_retry(delivery, now);
+
+            return;
+        }
+    };
+
+    let resp = client
+        .post(&webhook.url)
+        .header(hyper::header::CONTENT_TYPE.as_str(), "application/json")
+        .header("X-Webby-Event", delivery.event.as_str())
+        .header("X-Webby-Delivery", delivery.public_id.to_string())
+        .header("X-Webby-Timestamp", &timestamp)
+        .header(
+            "X-Webby-Signature",
+            format!(
+                "sha256={}",
+                sign_payload(&webhook.secret, &timestamp, &body)
+            ),
+        )
+        .body(body)
+        .send()
+        .await;
+
+    let is_success = match resp {
+        Ok(resp) => {
+            let status = resp.status();
+
+            delivery.response_status = Some(status.as_u16() as i32);
+            // Only successful responses are kept, anything else could be a page we were
+            // pointed at.
+            delivery.response_body = if status.is_success() {
+                resp.text()
+                    .await
+                    .ok()
+                    .map(|v| v.chars().take(1024).collect())
+            } else {
+                None
+            };
+
+            status.is_success()
+        }
+
+        Err(e) => {
+            delivery.response_status = None;
+            delivery.response_body = Some(e.to_string());
+
+            false
+        }
+    };
+
+    if is_success {
+        delivery.delivered_at = Some(now);
+        delivery.next_attempt_at = None;
+    } else {
+        schedule_retry(delivery, now);
+    }
+}
+
+fn schedule_retry(delivery: &mut AddonWebhookDeliveryModel, now: OffsetDateTime) {
+    if delivery.attempts >= MAX_DELIVERY_ATTEMPTS {
+        delivery.next_attempt_at = None;
+    } else {
+        let delay = RETRY_BASE_DELAY * 2i32.pow(delivery.attempts as u32 - 1);
+
+        delivery.next_attempt_at = Some(now + delay.min(RETRY_MAX_DELAY));
+    }
+}
+
+/// Client which only connects to the addresses the URL resolves to right now, as long as they're
+/// all public. Redirects aren't followed since they could point anywhere.
+async fn delivery_client(value: &str) -> Result<reqwest::Client> {
+    let url = url::Url::parse(value)?;
+    let addrs = resolve_webhook_url(&url, is_deliverable_ip).await?;
+
+    let mut builder = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(DELIVERY_TIMEOUT);
+
+    if let Some(url::Host::Domain(domain)) = url.host() {
+        builder = builder.resolve_to_addrs(domain, &addrs);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Tests deliver to stubs on localhost.
+fn is_deliverable_ip(ip: IpAddr) -> bool {
+    is_public_ip(ip) || (cfg!(test) && ip.is_loopback())
+}
+
+/// Hex encoded HMAC-SHA256 of "{timestamp}.{body}"
+pub fn sign_payload(secret: &str, timestamp: &str, body: &str) -> String {
+    hmac_sha256(
+        secret.as_bytes(),
+        &[timestamp.as_bytes(), b".", body.as_bytes()],
+    )
+}
+
+/// HMAC-SHA256 (RFC 2104) of the concatenated parts, hex encoded.
+fn hmac_sha256(secret: &[u8], parts: &[&[u8]]) -> String {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut key = [0u8; BLOCK_SIZE];
+
+    if secret.len() > BLOCK_SIZE {
+        key[..32].copy_from_slice(&Sha256::digest(secret));
+    } else {
+        key[..secret.len()].copy_from_slice(secret);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(key.map(|v| v ^ 0x36));
+
+    for part in parts {
+        inner.update(part);
+    }
+
+    let mut outer = Sha256::new();
+    outer.update(key.map(|v| v ^ 0x5c));
+    outer.update(inner.finalize());
+
+    format!("{:x}", outer.finalize())
+}
+
+/// Only public http(s) endpoints can be subscribed, otherwise deliveries could be used to reach
+/// services on our own network. Domains are resolved and every address has to be public.
+async fn verify_webhook_url(value: &str) -> Result<()> {
+    resolve_webhook_url(&url::Url::parse(value)?, is_public_ip).await?;
+
+    Ok(())
+}
+
+/// Every address the URL points to, erroring unless all of them are `allowed`.
+async fn resolve_webhook_url(
+    url: &url::Url,
+    allowed: fn(IpAddr) -> bool,
+) -> Result<Vec<SocketAddr>> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(eyre::eyre!("Webhook URL must use http or https"))?;
+    }
+
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let addrs = match url.host() {
+        Some(url::Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+        Some(url::Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+
+        Some(url::Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+
+            if domain == "localhost" || domain.ends_with(".localhost") {
+                Vec::new()
+            } else {
+                tokio::net::lookup_host((domain.as_str(), port))
+                    .await
+                    .map_err(|_| eyre::eyre!("Unable to resolve the Webhook URL"))?
+                    .collect()
+            }
+        }
+
+        None => Vec::new(),
+    };
+
+    if addrs.is_empty() || !addrs.iter().all(|addr| allowed(addr.ip())) {
+        return Err(eyre::eyre!("Webhook URL must point to a public address"))?;
+    }
+
+    Ok(addrs)
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    fn is_public_v4(ip: Ipv4Addr) -> bool {
+        let [a, b, ..] = ip.octets();
+
+        !(ip.is_private()
+            || ip.is_loopback()
+            || ip.is_link_local()
+            || ip.is_unspecified()
+            || ip.is_broadcast()
+            || ip.is_documentation()
+            || ip.is_multicast()
+            // Shared address space (RFC 6598)
+            || (a == 100 && (64..128).contains(&b))
+            || a == 0)
+    }
+
+    fn is_public_v6(ip: Ipv6Addr) -> bool {
+        if let Some(v4) = ip.to_ipv4_mapped() {
+            return is_public_v4(v4);
+        }
+
+        let first = ip.segments()[0];
+
+        !(ip.is_loopback()
+            || ip.is_unspecified()
+            || ip.is_multicast()
+            // Unique local (fc00::/7)
+            || (first & 0xfe00) == 0xfc00
+            // Link local (fe80::/10)
+            || (first & 0xffc0) == 0xfe80)
+    }
+
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+async fn get_webhook(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<Option<AddonWebhookModel>>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    Ok(Json(WrappingResponse::okay(
+        AddonWebhookModel::find_one_by_addon_id(addon.id, &mut acq).await?,
+    )))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWebhookJson {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub is_active: Option<bool>,
+}
+
+async fn update_webhook(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Json(UpdateWebhookJson {
+        url,
+        events,
+        is_active,
+    }): Json<UpdateWebhookJson>,
+) -> Result<JsonResponse<AddonWebhookModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    verify_webhook_url(&url).await?;
+
+    let webhook = match AddonWebhookModel::find_one_by_addon_id(addon.id, &mut acq).await? {
+        Some(mut webhook) => {
+            webhook.url = url;
+            webhook.events.0 = events;
+
+            if let Some(is_active) = is_active {
+                webhook.is_active = is_active;
+            }
+
+            webhook.update(&mut acq).await?;
+
+            webhook
+        }
+
+        None => {
+            NewAddonWebhookModel {
+                addon_id: addon.id,
+                url,
+                events,
+            }
+            .insert(&mut acq)
+            .await?
+        }
+    };
+
+    Ok(Json(WrappingResponse::okay(webhook)))
+}
+
+async fn rotate_webhook_secret(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<AddonWebhookModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    let mut webhook = AddonWebhookModel::find_one_by_addon_id(addon.id, &mut acq)
+        .await?
+        .context("Webhook not found")?;
+
+    webhook.regenerate_secret();
+    webhook.update(&mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(webhook)))
+}
+
+#[derive(Deserialize)]
+pub struct DeliveryListQuery {
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+async fn get_delivery_list(
+    Path(addon_id): Path<Uuid>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+    Query(DeliveryListQuery { offset, limit }): Query<DeliveryListQuery>,
+) -> Result<JsonListResponse<AddonWebhookDeliveryModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    let offset = offset.unwrap_or(0).max(0);
+    let limit = limit.unwrap_or(50).clamp(1, 100);
+
+    let items =
+        AddonWebhookDeliveryModel::find_by_addon_id(addon.id, offset, limit, &mut acq).await?;
+    let total = AddonWebhookDeliveryModel::count_by_addon_id(addon.id, &mut acq).await?;
+
+    Ok(Json(WrappingResponse::okay(ListResponse {
+        items,
+        offset,
+        limit,
+        total,
+    })))
+}
+
+/// Queues a new delivery with the same event and payload.
+async fn redeliver_webhook_delivery(
+    Path((addon_id, delivery_id)): Path<(Uuid, Uuid)>,
+    State(db): State<SqlitePool>,
+    member: AuthMember,
+) -> Result<JsonResponse<AddonWebhookDeliveryModel>> {
+    let mut acq = db.acquire().await?;
+
+    let addon = member
+        .find_owned_addon(addon_id, AddonCapability::ManageSettings, &mut acq)
+        .await?;
+
+    let delivery =
+        AddonWebhookDeliveryModel::find_one_by_public_id(addon.id, delivery_id, &mut acq)
+            .await?
+            .context("Delivery not found")?;
+
+    let webhook = AddonWebhookModel::find_one_by_addon_id(addon.id, &mut acq)
+        .await?
+        .context("Webhook not found")?;
+
+    let delivery = NewAddonWebhookDeliveryModel {
+        webhook_id: webhook.id,
+        addon_id: addon.id,
+        event: delivery.event,
+        payload: delivery.payload.0,
+    }
+    .insert(&mut acq)
+    .await?;
+
+    Ok(Json(WrappingResponse::okay(delivery)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{http::HeaderMap, http::StatusCode, routing::post, Router};
+    use tokio::net::TcpListener;
+
+    use super::{super::tests::test_addon, *};
+
+    #[derive(Clone, Default)]
+    struct Stub {
+        received: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+    }
+
+    /// Local HTTP server which records every request and responds with `status`.
+    async fn start_stub(status: StatusCode) -> (String, Stub) {
+        let stub = Stub::default();
+        let received = stub.received.clone();
+
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| {
+                let received = received.clone();
+
+                async move {
+                    received.lock().unwrap().push((headers, body));
+                    status
+                }
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{addr}/hook"), stub)
+    }
+
+    async fn setup(url: String) -> (SqlitePool, AddonModel, AddonWebhookModel) {
+        let pool = database::init_memory().await.unwrap();
+        let mut acq = pool.acquire().await.unwrap();
+
+        let addon = test_addon(Uuid::new_v4(), "test")
+            .insert(&mut acq)
+            .await
+            .unwrap();
+
+        let webhook = NewAddonWebhookModel {
+            addon_id: addon.id,
+            url,
+            events: vec![WebhookEvent::CmsRowCreated],
+        }
+        .insert(&mut acq)
+        .await
+        .unwrap();
+
+        drop(acq);
+
+        (pool, addon, webhook)
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload() {
+        let (url, stub) = start_stub(StatusCode::OK).await;
+        let (pool, addon, webhook) = setup(url).await;
+        let mut acq = pool.acquire().await.unwrap();
+
+        queue_cms_row_event(
+            addon.id,
+            WebhookEvent::CmsRowCreated,
+            "items",
+            &[Uuid::new_v4()],
+            &mut acq,
+        )
+        .await
+        .unwrap();
+
+        // Not subscribed
+        queue_webhook_event(
+            addon.id,
+            WebhookEvent::AddonPublished,
+            serde_json::json!({}),
+            &mut acq,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(process_due_deliveries(&pool).await.unwrap(), 1);
+
+        let received = stub.received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+
+        let (headers, body) = &received[0];
+        let timestamp = headers["X-Webby-Timestamp"].to_str().unwrap();
+
+        assert_eq!(headers["X-Webby-Event"], "cms.row.created");
+        assert_eq!(
+            headers["X-Webby-Signature"].to_str().unwrap(),
+            format!("sha256={}", sign_payload(&webhook.secret, timestamp, body))
+        );
+
+        let deliveries = AddonWebhookDeliveryModel::find_by_addon_id(addon.id, 0, 10, &mut acq)
+            .await
+            .unwrap();
+
+        assert_eq!(deliveries.len(), 1);
+        assert!(deliveries[0].delivered_at.is_some());
+        assert!(deliveries[0].next_attempt_at.is_none());
+        assert_eq!(deliveries[0].response_status, Some(200));
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_retried_later() {
+        let (url, stub) = start_stub(StatusCode::INTERNAL_SERVER_ERROR).await;
+        let (pool, addon, _) = setup(url).await;
+        let mut acq = pool.acquire().await.unwrap();
+
+        queue_cms_row_event(
+            addon.id,
+            WebhookEvent::CmsRowCreated,
+            "items",
+            &[Uuid::new_v4()],
+            &mut acq,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(process_due_deliveries(&pool).await.unwrap(), 1);
+        // Backoff hasn't elapsed yet.
+        assert_eq!(process_due_deliveries(&pool).await.unwrap(), 0);
+        assert_eq!(stub.received.lock().unwrap().len(), 1);
+
+        let deliveries = AddonWebhookDeliveryModel::find_by_addon_id(addon.id, 0, 10, &mut acq)
+            .await
+            .unwrap();
+
+        assert_eq!(deliveries[0].attempts, 1);
+        assert_eq!(deliveries[0].response_status, Some(500));
+        assert!(deliveries[0].response_body.is_none());
+        assert!(deliveries[0].delivered_at.is_none());
+        assert!(deliveries[0].next_attempt_at.unwrap() > OffsetDateTime::now_utc());
+    }
+
+    #[tokio::test]
+    async fn does_not_follow_redirects() {
+        let stub = Stub::default();
+        let received = stub.received.clone();
+
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(|| async {
+                    (
+                        StatusCode::TEMPORARY_REDIRECT,
+                        [(hyper::header::LOCATION, "/landed")],
+                    )
+                }),
+            )
+            .route(
+                "/landed",
+                post(move |headers: HeaderMap, body: String| {
+                    let received = received.clone();
+
+                    async move {
+                        received.lock().unwrap().push((headers, body));
+                        StatusCode::OK
+                    }
+                }),
+            );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (pool, addon, _) = setup(format!("http://{addr}/hook")).await;
+        let mut acq = pool.acquire().await.unwrap();
+
+        queue_cms_row_event(
+            addon.id,
+            WebhookEvent::CmsRowCreated,
+            "items",
+            &[Uuid::new_v4()],
+            &mut acq,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(process_due_deliveries(&pool).await.unwrap(), 1);
+        assert!(stub.received.lock().unwrap().is_empty());
+
+        let deliveries = AddonWebhookDeliveryModel::find_by_addon_id(addon.id, 0, 10, &mut acq)
+            .await
+            .unwrap();
+
+        assert_eq!(deliveries[0].response_status, Some(307));
+        assert!(deliveries[0].delivered_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn rechecks_address_when_delivering() {
+        // Stored before it was verified, or the domain started resolving to it later.
+        let (pool, addon, _) = setup(String::from("http://10.0.0.5/hook")).await;
+        let mut acq = pool.acquire().await.unwrap();
+
+        queue_cms_row_event(
+            addon.id,
+            WebhookEvent::CmsRowCreated,
+            "items",
+            &[Uuid::new_v4()],
+            &mut acq,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(process_due_deliveries(&pool).await.unwrap(), 1);
+
+        let deliveries = AddonWebhookDeliveryModel::find_by_addon_id(addon.id, 0, 10, &mut acq)
+            .await
+            .unwrap();
+
+        assert_eq!(deliveries[0].attempts, 1);
+        assert!(deliveries[0].response_status.is_none());
+        assert!(deliveries[0].delivered_at.is_none());
+        assert!(deliveries[0].next_attempt_at.is_some());
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        // Test Case 2
+        assert_eq!(
+            hmac_sha256(b"Jefe", &[b"what do ya want ", b"for nothing?"]),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        // Test Case 6, key is larger than the block size.
+        assert_eq!(
+            hmac_sha256(
+                &[0xaa; 131],
+                &[b"Test Using Larger Than Block-Size Key - Hash Key First"]
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_private_webhook_urls() {
+        for url in [
+            "http://127.0.0.1/hook",
+            "http://localhost:8080/hook",
+            "http://10.0.0.5/hook",
+            "http://192.168.1.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "ftp://8.8.8.8/hook",
+        ] {
+            assert!(verify_webhook_url(url).await.is_err(), "{url}");
+        }
+
+        assert!(verify_webhook_url("https://8.8.8.8/hook").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn sweeps_expired_deliveries() {
+        let (url, _stub) = start_stub(StatusCode::OK).await;
+        let (pool, addon, _) = setup(url).await;
+        let mut acq = pool.acquire().await.unwrap();
+
+        queue_cms_row_event(
+            addon.id,
+            WebhookEvent::CmsRowCreated,
+            "items",
+            &[Uuid::new_v4()],
+            &mut acq,
+        )
+        .await
+        .unwrap();
+
+        sqlx::query("UPDATE addon_webhook_delivery SET created_at = $1")
+            .bind(OffsetDateTime::now_utc() - time::Duration::days(31))
+            .execute(&mut *acq)
+            .await
+            .unwrap();
+
+        drop(acq);
+
+        assert_eq!(sweep_delivery_log(&pool).await.unwrap(), 1);
+    }
+}
@@ -6,8 +6,11 @@ use axum::{
     Json, Router,
 };
 use database::{
-    AddonCompiledModel, AddonCompiledWidget, AddonInstanceModel, AddonModel, AddonWidgetContent,
-    NewAddonInstanceModel, NewWebsiteWidgetSettingsModel, WebsiteWidgetSettingsModel, WidgetModel,
+    AddonCompiledModel, AddonCompiledWidget, AddonInstanceGrantModel, AddonInstanceModel,
+    AddonModel, AddonPricingPlanModel, AddonWidgetContent, NewAddonInstallSessionModel,
+    NewAddonInstanceGrantModel, NewAddonInstanceModel, NewAddonSubscriptionModel,
+    NewWebsiteWidgetSettingsModel, SubscriptionStatus, WebhookEvent, WebsiteWidgetSettingsModel,
+    WidgetModel,
 };
 use eyre::ContextCompat;
 use local_common::{MemberModel, WebsiteId, WebsiteModel};
@@ -23,6 +26,13 @@ use webby_storage::{widget::CompiledWidgetSettings, DisplayStore};
 
 use crate::Result;
 
+use super::{
+    addon::member_partial,
+    auth::AuthMember,
+    automation::{spawn_automation_event, AutomationEvent},
+    webhook::queue_webhook_event,
+};
+
 use super::CLIENT;
 
 pub fn routes() -> Router<SqlitePool> {
@@ -53,6 +63,7 @@ struct DuplicateWebsiteJson {
 async fn duplicate_website_addons(
     Path(old_website): Path<Uuid>,
     State(db): State<SqlitePool>,
+    auth: AuthMember,
     Json(DuplicateWebsiteJson {
         new_website_id,
         new_website_uuid,
@@ -60,6 +71,9 @@ async fn duplicate_website_addons(
         new_website,
     }): Json<DuplicateWebsiteJson>,
 ) -> Result<JsonResponse<&'static str>> {
+    auth.website_access_error(old_website).await?;
+    auth.website_access_error(new_website_uuid).await?;
+
     let mut acq = db.acquire().await?;
 
     let instances = AddonInstanceModel::find_by_website_uuid(old_website, &mut acq).await?;
@@ -72,17 +86,69 @@ async fn duplicate_website_addons(
             .context("Addon not found")?;
 
         if let Some(url) = addon.action_url {
+            let granted =
+                AddonInstanceGrantModel::find_permissions_by_instance_id(inst.id, &mut acq).await?;
+
+            let subscription = inst.find_subscription(&mut acq).await?;
+
             // 1. Insert Website Addon
             let mut inst = NewAddonInstanceModel {
                 addon_id: inst.addon_id,
                 website_id: new_website_id,
                 website_uuid: new_website_uuid,
-                // TODO: Set to version we're duplicating
-                version: String::from("latest"),
+                version: inst.version.clone(),
+            }
+            .insert(&mut acq)
+            .await?;
+
+            NewAddonInstanceGrantModel {
+                instance_id: inst.id,
+                permissions: granted.clone(),
             }
             .insert(&mut acq)
             .await?;
 
+            // Paid subscriptions stay with the original website. The copy isn't usable until it's subscribed.
+            if let Some(sub) = subscription {
+                let plan = AddonPricingPlanModel::find_one_by_id(sub.plan_id, &mut acq).await?;
+
+                if plan.is_some_and(|v| v.is_free()) {
+                    NewAddonSubscriptionModel {
+                        instance_id: inst.id,
+                        plan_id: sub.plan_id,
+                        status: SubscriptionStatus::Active,
+                        seats: sub.seats,
+                        provider_subscription_id: None,
+                        trial_ends_at: None,
+                        current_period_end: None,
+                    }
+                    .insert(&mut acq)
+                    .await?;
+                }
+            }
+
+            queue_webhook_event(
+                addon.id,
+                WebhookEvent::InstanceInstalled,
+                serde_json::json!({
+                    "instanceId": inst.public_id,
+                    "websiteId": inst.website_uuid,
+                    "version": inst.version,
+                }),
+                &mut acq,
+            )
+            .await?;
+
+            spawn_automation_event(
+                db.clone(),
+                AutomationEvent::InstanceInstalled {
+                    addon_id: addon.id,
+                    instance_id: inst.public_id,
+                    website_id: inst.website_uuid,
+                    version: inst.version.clone(),
+                },
+            );
+
             // 2. Send install request
             let resp = CLIENT
                 .post(format!("{url}/registration"))
@@ -92,8 +158,7 @@ async fn duplicate_website_addons(
                     "ownerId": member.id,
                     "websiteId": new_website_uuid,
 
-                    // TODO: Use Permissions
-                    "member": member,
+                    "member": member_partial(&member, &granted),
                     "website": new_website,
                 }))
                 .send()
@@ -114,8 +179,13 @@ async fn duplicate_website_addons(
                         inst.update(&mut acq).await?;
                     }
 
-                    WrappingResponse::Resp(InstallResponse::Redirect(_url)) => {
-                        // TODO
+                    WrappingResponse::Resp(InstallResponse::Redirect(url)) => {
+                        NewAddonInstallSessionModel {
+                            instance_id: inst.id,
+                            redirect_url: url,
+                        }
+                        .insert(&mut acq)
+                        .await?;
                     }
 
                     WrappingResponse::Error(e) => return Ok(Json(WrappingResponse::Error(e))),
@@ -303,8 +373,11 @@ async fn update_website_addon_widget(
     // TODO: Use (Instance, Widget)
     Path((website_id, widget_id)): Path<(WebsitePublicId, AddonWidgetPublicId)>,
     Query(CompiledAddonWidgetQuery { object_id }): Query<CompiledAddonWidgetQuery>,
+    member: AuthMember,
     Json(UpdateWebsiteAddonWidget { settings }): Json<UpdateWebsiteAddonWidget>,
 ) -> Result<JsonResponse<&'static str>> {
+    member.website_access_error(*website_id).await?;
+
     let mut acq = db.acquire().await?;
 
     let widget = AddonWidgetContent::find_one_by_public_id(widget_id, &mut acq)
@@ -392,6 +465,19 @@ async fn update_website_addon_widget(
                     .await?;
                 }
             }
+
+            queue_webhook_event(
+                addon.id,
+                WebhookEvent::WidgetSettingsChanged,
+                serde_json::json!({
+                    "instanceId": instance.public_id,
+                    "websiteId": instance.website_uuid,
+                    "widgetId": widget.id,
+                    "objectId": object_id,
+                }),
+                &mut acq,
+            )
+            .await?;
         }
 
         break;
@@ -25,6 +25,12 @@ pub struct AddonPublic {
 
     pub install_count: i32,
 
+    /// Average of the visible reviews, from 1 to 5.
+    pub rating: Option<f32>,
+    pub rating_count: i32,
+
+    pub tags: Option<Vec<TagPublic>>,
+
     pub delete_reason: Option<String>,
 
     pub created_at: OffsetDateTime,
@@ -32,6 +38,32 @@ pub struct AddonPublic {
     pub deleted_at: Option<OffsetDateTime>,
 }
 
+/// The addon with what the marketplace listing shows.
+#[skip_serializing_none]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonExtendedPublic {
+    #[serde(flatten)]
+    pub addon: AddonPublic,
+
+    /// Version of the newest publish.
+    pub latest_version: Option<String>,
+
+    pub widget_count: i32,
+    pub page_count: i32,
+    pub schema_count: i32,
+
+    pub developer: Option<DeveloperPublic>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagPublic {
+    pub slug: String,
+    pub name: String,
+    pub is_category: bool,
+}
+
 #[skip_serializing_none]
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -49,3 +81,26 @@ pub struct DeveloperPublic {
     pub updated_at: OffsetDateTime,
     pub deleted_at: Option<OffsetDateTime>,
 }
+
+/// A permission with what the installer is shown on the consent screen.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionPublic {
+    pub permission: String,
+    pub description: String,
+}
+
+impl PermissionPublic {
+    /// Permissions which can't be parsed are described as themselves.
+    pub fn describe(permission: String) -> Self {
+        let description = permission
+            .parse::<crate::AddonPermission>()
+            .map(|v| v.describe())
+            .unwrap_or_else(|_| permission.clone());
+
+        Self {
+            permission,
+            description,
+        }
+    }
+}
@@ -1,11 +1,8 @@
 use rand::{distributions::Alphanumeric, Rng, SeedableRng};
 use rand_hc::Hc128Rng;
+use sha2::{Digest, Sha256};
 use time::OffsetDateTime;
 
-thread_local! {
-    static RNG: Hc128Rng = Hc128Rng::from_entropy();
-}
-
 pub fn gen_sample_alphanumeric<R: Rng>(amount: usize, rng: &mut R) -> String {
     rng.sample_iter(Alphanumeric)
         .take(amount)
@@ -13,8 +10,14 @@ pub fn gen_sample_alphanumeric<R: Rng>(amount: usize, rng: &mut R) -> String {
         .collect()
 }
 
+/// A new generator seeded from the thread's CSPRNG. Each call returns a different stream.
 pub fn get_rng_secure() -> Hc128Rng {
-    RNG.with(|v| v.clone())
+    Hc128Rng::from_seed(rand::thread_rng().gen())
+}
+
+/// Hex encoded SHA-256 of a secret. Used to store tokens without being able to read them back.
+pub fn hash_secret(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
 }
 
 /// 74 Characters Total. 64 Randomly generated. 10 are current unix time.
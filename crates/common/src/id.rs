@@ -110,3 +110,22 @@ create_id!(AddonCompiledWidgetId, i32);
 create_id!(AddonCompiledPageId, i32);
 create_id!(VisslAddonCodeId, i32);
 create_id!(VisslAddonPanelCodeId, i32);
+create_id!(AddonWebhookId, i32);
+create_id!(AddonWebhookDeliveryId, i64);
+create_id!(AddonAutomationId, i32);
+create_id!(AddonExtensionId, i32);
+create_id!(AddonPricingPlanId, i32);
+create_id!(AddonSubscriptionId, i32);
+create_id!(AddonCollaboratorId, i32);
+create_id!(AddonCommentId, i32);
+create_id!(TagId, i32);
+create_id!(DeveloperId, i32);
+create_id!(AddonDemoId, i32);
+create_id!(AddonDemoSandboxId, i32);
+create_id!(AddonInstallSettingsId, i32);
+create_id!(AddonOAuthClientId, i32);
+create_id!(AddonOAuthTokenId, i64);
+create_id!(AddonInstanceGrantId, i64);
+create_id!(AddonDashboardBundleId, i32);
+create_id!(AddonDashboardBundleAssetId, i64);
+create_id!(AddonSitePluginId, i32);
@@ -1,8 +1,6 @@
 #[macro_use]
 extern crate log;
 
-use std::fmt::Write as _;
-
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use uuid::Uuid;
@@ -10,10 +8,12 @@ use uuid::Uuid;
 pub mod api;
 pub mod generate;
 mod id;
+mod permission;
 pub mod upload;
 mod widget;
 
 pub use id::*;
+pub use permission::*;
 pub use widget::*;
 
 #[derive(Serialize, Deserialize)]
@@ -65,26 +65,3 @@ pub struct WebsiteModel {
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
-
-pub struct AddonPermission {
-    pub scope: String,
-    pub category: String,
-    pub operation: Option<String>,
-    pub info: Option<String>,
-}
-
-impl ToString for AddonPermission {
-    fn to_string(&self) -> String {
-        let mut value = format!("{}.{}", self.scope, self.category);
-
-        if let Some(val) = self.operation.as_deref() {
-            write!(&mut value, ".{val}").unwrap();
-        }
-
-        if let Some(val) = self.info.as_deref() {
-            write!(&mut value, ".{val}").unwrap();
-        }
-
-        value
-    }
-}
@@ -0,0 +1,260 @@
+use std::{fmt, str::FromStr};
+
+/// Matches every remaining segment of a permission.
+pub const PERMISSION_WILDCARD: &str = "*";
+
+/// A permission an addon requests from the installer.
+///
+/// Written as `scope.category[.operation[.info]]`, e.g. `member.info.read.email`.
+/// The last segment can be `*` to match everything under it, e.g. `member.info.*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddonPermission {
+    pub scope: String,
+    pub category: String,
+    pub operation: Option<String>,
+    pub info: Option<String>,
+}
+
+impl AddonPermission {
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        [
+            Some(self.scope.as_str()),
+            Some(self.category.as_str()),
+            self.operation.as_deref(),
+            self.info.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        self.segments().any(|v| v == PERMISSION_WILDCARD)
+    }
+
+    /// Whether holding this permission also allows `other`.
+    ///
+    /// A permission allows everything under it, e.g. `member.info.read` allows `member.info.read.email`.
+    /// A wildcard only allows what's under it, so `member.info.read.*` doesn't allow `member.info.read`.
+    pub fn grants(&self, other: &AddonPermission) -> bool {
+        let mut other = other.segments();
+
+        for segment in self.segments() {
+            if segment == PERMISSION_WILDCARD {
+                return other.next().is_some();
+            }
+
+            if other.next() != Some(segment) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// The known permission this refers to, if it isn't a wildcard.
+    pub fn known(&self) -> Option<&'static KnownPermission> {
+        KNOWN_PERMISSIONS.iter().find(|v| v.permission() == *self)
+    }
+
+    /// Errors unless it refers to at least one known permission.
+    pub fn validate(&self) -> eyre::Result<()> {
+        if KNOWN_PERMISSIONS
+            .iter()
+            .any(|v| self.grants(&v.permission()) || v.permission().grants(self))
+        {
+            Ok(())
+        } else {
+            Err(eyre::eyre!("Unknown permission \"{self}\""))
+        }
+    }
+
+    /// What the installer is shown on the consent screen.
+    pub fn describe(&self) -> String {
+        if let Some(known) = self.known() {
+            return known.description.to_string();
+        }
+
+        // Narrower than a known permission, e.g. a single collection.
+        if !self.is_wildcard() {
+            if let Some(parent) = KNOWN_PERMISSIONS
+                .iter()
+                .rev()
+                .find(|v| v.permission().grants(self))
+            {
+                return format!("{} ({})", parent.description, self);
+            }
+        }
+
+        // Only the broadest of the matched permissions are listed.
+        let mut granted = Vec::<AddonPermission>::new();
+        let mut descriptions = Vec::new();
+
+        for known in KNOWN_PERMISSIONS {
+            let perm = known.permission();
+
+            if self.grants(&perm) && !granted.iter().any(|v| v.grants(&perm)) {
+                granted.push(perm);
+                descriptions.push(known.description);
+            }
+        }
+
+        if descriptions.is_empty() {
+            self.to_string()
+        } else {
+            descriptions.join(", ")
+        }
+    }
+}
+
+impl fmt::Display for AddonPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.scope, self.category)?;
+
+        if let Some(val) = self.operation.as_deref() {
+            write!(f, ".{val}")?;
+        }
+
+        if let Some(val) = self.info.as_deref() {
+            write!(f, ".{val}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for AddonPermission {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let segments = value.split('.').collect::<Vec<_>>();
+
+        if !(2..=4).contains(&segments.len()) {
+            return Err(eyre::eyre!(
+                "Permission \"{value}\" must be written as scope.category[.operation[.info]]"
+            ));
+        }
+
+        for (i, segment) in segments.iter().enumerate() {
+            if *segment == PERMISSION_WILDCARD {
+                if i + 1 != segments.len() {
+                    return Err(eyre::eyre!(
+                        "Permission \"{value}\" can only end with a wildcard"
+                    ));
+                }
+
+                if i == 0 {
+                    return Err(eyre::eyre!("Permission \"{value}\" needs a scope"));
+                }
+
+                continue;
+            }
+
+            if segment.is_empty()
+                || !segment
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+            {
+                return Err(eyre::eyre!(
+                    "Permission \"{value}\" can only contain lowercase letters, numbers, '_' and '-'"
+                ));
+            }
+        }
+
+        let mut segments = segments.into_iter().map(|v| v.to_string());
+
+        Ok(Self {
+            scope: segments.next().unwrap(),
+            category: segments.next().unwrap(),
+            operation: segments.next(),
+            info: segments.next(),
+        })
+    }
+}
+
+/// A permission addons can request.
+pub struct KnownPermission {
+    pub scope: &'static str,
+    pub category: &'static str,
+    pub operation: Option<&'static str>,
+    pub info: Option<&'static str>,
+
+    pub description: &'static str,
+}
+
+impl KnownPermission {
+    pub fn permission(&self) -> AddonPermission {
+        AddonPermission {
+            scope: self.scope.to_string(),
+            category: self.category.to_string(),
+            operation: self.operation.map(|v| v.to_string()),
+            info: self.info.map(|v| v.to_string()),
+        }
+    }
+}
+
+macro_rules! known {
+    ($scope:literal, $category:literal, $operation:literal, $description:literal) => {
+        KnownPermission {
+            scope: $scope,
+            category: $category,
+            operation: Some($operation),
+            info: None,
+            description: $description,
+        }
+    };
+
+    ($scope:literal, $category:literal, $operation:literal, $info:literal, $description:literal) => {
+        KnownPermission {
+            scope: $scope,
+            category: $category,
+            operation: Some($operation),
+            info: Some($info),
+            description: $description,
+        }
+    };
+}
+
+/// Every permission an addon can request. Broader permissions come before the ones under them.
+pub const KNOWN_PERMISSIONS: &[KnownPermission] = &[
+    known!("member", "info", "read", "Read your profile"),
+    known!("member", "info", "read", "email", "Read your email address"),
+    known!("member", "info", "read", "name", "Read your name and tag"),
+    known!("member", "info", "read", "avatar", "Read your avatar"),
+    known!(
+        "website",
+        "info",
+        "read",
+        "Read the website's name and address"
+    ),
+    known!("website", "pages", "read", "Read the website's pages"),
+    known!(
+        "cms",
+        "data",
+        "read",
+        "Read the items of the website's collections"
+    ),
+    known!(
+        "cms",
+        "data",
+        "write",
+        "Create, edit and delete the items of the website's collections"
+    ),
+    known!(
+        "cms",
+        "index",
+        "manage",
+        "Manage the indexes of the website's collections"
+    ),
+    known!(
+        "cms",
+        "collection",
+        "manage",
+        "Create, edit and delete the website's collections"
+    ),
+    known!(
+        "database",
+        "external",
+        "manage",
+        "Access and manage external database connections"
+    ),
+];
@@ -45,6 +45,15 @@ pub fn get_full_file_path(store_path: &str) -> PathBuf {
     path
 }
 
+/// Where the uploaded file can be viewed from. The base is set with `WEBBY_UPLOAD_URL`.
+pub fn get_public_file_url(store_path: &str) -> String {
+    format!(
+        "{}{}",
+        PUBLIC_UPLOAD_URL.as_str(),
+        get_full_file_path(store_path).display()
+    )
+}
+
 pub fn get_thumb_file_path(store_path: &str) -> PathBuf {
     let mut path = PathBuf::from("/addon_member_upload_thumb");
 
@@ -88,6 +97,9 @@ impl AuthWrapper {
 lazy_static! {
     static ref AUTH: EbrCell<Option<AuthWrapper>> = EbrCell::new(None);
     static ref CLIENT: Client = Client::new();
+    static ref PUBLIC_UPLOAD_URL: String = std::env::var("WEBBY_UPLOAD_URL")
+        .map(|v| v.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| String::from("http://127.0.0.1:5940"));
 }
 
 // TODO: Use check_and_update_auth for 401 error.
@@ -193,6 +205,24 @@ impl StorageService {
         Ok(())
     }
 
+    /// Downloads a file by the id it was uploaded with.
+    pub async fn download(&self, file_id: &str) -> Result<Bytes> {
+        let auth = get_auth()?;
+
+        let resp = CLIENT
+            .get(format!(
+                "{}/b2api/v2/b2_download_file_by_id",
+                auth.download_url
+            ))
+            .query(&[("fileId", file_id)])
+            .header(reqwest::header::AUTHORIZATION, &auth.authorization_token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(resp.bytes().await?)
+    }
+
     /// Uploads a file in chunks if it is larger than 10MB.
     pub async fn upload_large(
         &self,
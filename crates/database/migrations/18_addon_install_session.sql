@@ -0,0 +1,20 @@
+CREATE TABLE addon_install_session (
+    pk INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    instance_id INTEGER NOT NULL,
+
+    -- Token the addon has to send back to continue or complete the install
+    state TEXT NOT NULL UNIQUE,
+    redirect_url TEXT NOT NULL,
+    step INTEGER NOT NULL DEFAULT 0,
+
+    expires_at DATETIME NOT NULL,
+    completed_at DATETIME,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(instance_id) REFERENCES addon_instance(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_addon_install_session_instance_id ON addon_install_session (instance_id);
@@ -0,0 +1,25 @@
+-- Namespaces are resolved through the name id so it has to be unique.
+-- Duplicates get their id appended, again for as long as that's taken by an addon keeping its' name id.
+-- The id always ends up last so renamed addons can't collide with each other.
+CREATE TEMP TABLE addon_name_id_dedupe AS
+WITH RECURSIVE
+kept(name_id) AS (
+    SELECT name_id FROM addon WHERE id IN (SELECT MIN(id) FROM addon GROUP BY name_id)
+),
+candidate(id, name_id) AS (
+    SELECT id, name_id || '_' || id FROM addon
+    WHERE id NOT IN (SELECT MIN(id) FROM addon GROUP BY name_id)
+
+    UNION ALL
+
+    SELECT id, name_id || '_' || id FROM candidate
+    WHERE name_id IN (SELECT name_id FROM kept)
+)
+SELECT id, name_id FROM candidate WHERE name_id NOT IN (SELECT name_id FROM kept);
+
+UPDATE addon SET name_id = (SELECT name_id FROM addon_name_id_dedupe WHERE addon_name_id_dedupe.id = addon.id)
+WHERE id IN (SELECT id FROM addon_name_id_dedupe);
+
+DROP TABLE addon_name_id_dedupe;
+
+CREATE UNIQUE INDEX idx_addon_name_id ON addon (name_id);
@@ -0,0 +1,47 @@
+CREATE TABLE addon_webhook (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    addon_id INTEGER NOT NULL UNIQUE,
+
+    url TEXT NOT NULL,
+    -- JSON array of the subscribed events
+    events JSON NOT NULL DEFAULT '[]',
+    -- Used to sign every delivery
+    secret TEXT NOT NULL,
+
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE
+);
+
+CREATE TABLE addon_webhook_delivery (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    public_id BLOB NOT NULL UNIQUE,
+
+    webhook_id INTEGER NOT NULL,
+    addon_id INTEGER NOT NULL,
+
+    event TEXT NOT NULL,
+    payload JSON NOT NULL,
+
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    response_body TEXT,
+
+    -- NULL once delivered or out of attempts
+    next_attempt_at DATETIME,
+    delivered_at DATETIME,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(webhook_id) REFERENCES addon_webhook(id) ON DELETE CASCADE,
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_addon_webhook_delivery_addon_id ON addon_webhook_delivery (addon_id);
+CREATE INDEX idx_addon_webhook_delivery_next_attempt_at ON addon_webhook_delivery (next_attempt_at);
+CREATE INDEX idx_addon_webhook_delivery_created_at ON addon_webhook_delivery (created_at);
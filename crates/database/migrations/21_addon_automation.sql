@@ -0,0 +1,31 @@
+CREATE TABLE addon_automation (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    public_id BLOB NOT NULL UNIQUE,
+
+    addon_id INTEGER NOT NULL,
+    -- NULL while it's a draft. Copied into the compiled version on publish.
+    compiled_id INTEGER,
+
+    name TEXT NOT NULL,
+
+    triggered_by JSON NOT NULL,
+    -- JSON array of filters which the row has to match
+    conditions JSON NOT NULL DEFAULT '[]',
+    actions JSON NOT NULL DEFAULT '[]',
+
+    is_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+
+    -- Only used by scheduled automations
+    next_run_at DATETIME,
+    last_run_at DATETIME,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(compiled_id) REFERENCES addon_compiled(pk) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_addon_automation_addon_id ON addon_automation (addon_id);
+CREATE INDEX idx_addon_automation_compiled_id ON addon_automation (compiled_id);
+CREATE INDEX idx_addon_automation_next_run_at ON addon_automation (next_run_at);
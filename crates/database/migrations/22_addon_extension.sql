@@ -0,0 +1,26 @@
+CREATE TABLE addon_extension (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    addon_id INTEGER NOT NULL,
+    -- NULL for the manifest being edited. Copied into the compiled version on publish.
+    compiled_id INTEGER,
+
+    -- JSON array of what the addon extends
+    extends JSON NOT NULL DEFAULT '[]',
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(compiled_id) REFERENCES addon_compiled(pk) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_addon_extension_draft ON addon_extension (addon_id) WHERE compiled_id IS NULL;
+CREATE UNIQUE INDEX idx_addon_extension_compiled_id ON addon_extension (compiled_id);
+
+-- Existing addons were able to extend everything.
+INSERT INTO addon_extension (addon_id, compiled_id, extends, created_at, updated_at)
+    SELECT id, NULL, '["widget","sitePage","dashboardPage","cmsCollection"]', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP FROM addon;
+
+INSERT INTO addon_extension (addon_id, compiled_id, extends, created_at, updated_at)
+    SELECT addon_id, pk, '["widget","sitePage","dashboardPage","cmsCollection"]', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP FROM addon_compiled;
@@ -0,0 +1,52 @@
+CREATE TABLE addon_pricing_plan (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    public_id BLOB NOT NULL UNIQUE,
+
+    addon_id INTEGER NOT NULL,
+
+    name TEXT NOT NULL,
+
+    -- free, oneTime, monthly, yearly
+    type TEXT NOT NULL,
+    -- In the smallest currency unit
+    price INTEGER NOT NULL DEFAULT 0,
+    currency TEXT NOT NULL DEFAULT 'usd',
+    -- Price is multiplied by the seats
+    is_per_seat BOOLEAN NOT NULL DEFAULT FALSE,
+    trial_days INTEGER NOT NULL DEFAULT 0,
+
+    -- Price ID inside the billing provider
+    provider_price_id TEXT,
+
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_addon_pricing_plan_addon_id ON addon_pricing_plan (addon_id);
+
+CREATE TABLE addon_subscription (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    instance_id INTEGER NOT NULL UNIQUE,
+    plan_id INTEGER NOT NULL,
+
+    -- trialing, active, pastDue, cancelled
+    status TEXT NOT NULL,
+    seats INTEGER NOT NULL DEFAULT 1,
+
+    provider_subscription_id TEXT UNIQUE,
+
+    trial_ends_at DATETIME,
+    current_period_end DATETIME,
+    cancelled_at DATETIME,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(instance_id) REFERENCES addon_instance(id) ON DELETE CASCADE,
+    FOREIGN KEY(plan_id) REFERENCES addon_pricing_plan(id)
+);
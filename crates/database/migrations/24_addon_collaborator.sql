@@ -0,0 +1,21 @@
+CREATE TABLE addon_collaborator (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    addon_id INTEGER NOT NULL,
+    member_uuid BLOB NOT NULL,
+
+    -- owner, developer, designer, viewer
+    role TEXT NOT NULL,
+
+    invited_by BLOB NOT NULL,
+    -- NULL until the member accepts the invite
+    accepted_at DATETIME,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_addon_collaborator_addon_member ON addon_collaborator (addon_id, member_uuid);
+CREATE INDEX idx_addon_collaborator_member_uuid ON addon_collaborator (member_uuid);
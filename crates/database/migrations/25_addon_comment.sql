@@ -0,0 +1,45 @@
+-- Kept up to date from the visible reviews so the marketplace can sort by rating.
+ALTER TABLE addon ADD COLUMN rating_sum INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE addon ADD COLUMN rating_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE addon_comment (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    public_id BLOB NOT NULL UNIQUE,
+
+    addon_id INTEGER NOT NULL,
+    -- NULL for reviews. Set for the developer replies threaded under a review.
+    parent_id INTEGER,
+
+    member_uuid BLOB NOT NULL,
+
+    -- 1 to 5. NULL for replies.
+    rating INTEGER,
+    body TEXT NOT NULL,
+
+    -- The member had the addon installed on one of their websites.
+    is_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    is_hidden BOOLEAN NOT NULL DEFAULT FALSE,
+    report_count INTEGER NOT NULL DEFAULT 0,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(parent_id) REFERENCES addon_comment(id) ON DELETE CASCADE
+);
+
+-- One review per member
+CREATE UNIQUE INDEX idx_addon_comment_review ON addon_comment (addon_id, member_uuid) WHERE parent_id IS NULL;
+CREATE INDEX idx_addon_comment_parent_id ON addon_comment (parent_id);
+
+CREATE TABLE addon_comment_report (
+    comment_id INTEGER NOT NULL,
+    member_uuid BLOB NOT NULL,
+
+    reason TEXT,
+
+    created_at DATETIME NOT NULL,
+
+    PRIMARY KEY(comment_id, member_uuid),
+    FOREIGN KEY(comment_id) REFERENCES addon_comment(id) ON DELETE CASCADE
+);
@@ -0,0 +1,58 @@
+CREATE TABLE tag (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+
+    -- Categories are curated. Other tags are created when an addon uses them.
+    is_category BOOLEAN NOT NULL DEFAULT FALSE,
+
+    created_at DATETIME NOT NULL
+);
+
+CREATE TABLE addon_tag (
+    addon_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+
+    PRIMARY KEY(addon_id, tag_id),
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(tag_id) REFERENCES tag(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_addon_tag_tag_id ON addon_tag (tag_id);
+
+INSERT INTO tag (slug, name, is_category, created_at) VALUES
+    ('analytics', 'Analytics', TRUE, CURRENT_TIMESTAMP),
+    ('communication', 'Communication', TRUE, CURRENT_TIMESTAMP),
+    ('design', 'Design', TRUE, CURRENT_TIMESTAMP),
+    ('ecommerce', 'eCommerce', TRUE, CURRENT_TIMESTAMP),
+    ('forms', 'Forms', TRUE, CURRENT_TIMESTAMP),
+    ('marketing', 'Marketing', TRUE, CURRENT_TIMESTAMP),
+    ('media', 'Media', TRUE, CURRENT_TIMESTAMP),
+    ('seo', 'SEO', TRUE, CURRENT_TIMESTAMP),
+    ('social', 'Social', TRUE, CURRENT_TIMESTAMP),
+    ('utilities', 'Utilities', TRUE, CURRENT_TIMESTAMP);
+
+-- Full-text search over the addon listing
+CREATE VIRTUAL TABLE addon_search USING fts5(
+    name,
+    tag_line,
+    description,
+    content = 'addon',
+    content_rowid = 'id'
+);
+
+INSERT INTO addon_search (addon_search) VALUES ('rebuild');
+
+CREATE TRIGGER addon_search_insert AFTER INSERT ON addon BEGIN
+    INSERT INTO addon_search (rowid, name, tag_line, description) VALUES (new.id, new.name, new.tag_line, new.description);
+END;
+
+CREATE TRIGGER addon_search_delete AFTER DELETE ON addon BEGIN
+    INSERT INTO addon_search (addon_search, rowid, name, tag_line, description) VALUES ('delete', old.id, old.name, old.tag_line, old.description);
+END;
+
+CREATE TRIGGER addon_search_update AFTER UPDATE OF name, tag_line, description ON addon BEGIN
+    INSERT INTO addon_search (addon_search, rowid, name, tag_line, description) VALUES ('delete', old.id, old.name, old.tag_line, old.description);
+    INSERT INTO addon_search (rowid, name, tag_line, description) VALUES (new.id, new.name, new.tag_line, new.description);
+END;
@@ -0,0 +1,27 @@
+CREATE TABLE developer (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guid BLOB NOT NULL UNIQUE,
+
+    -- A member has a single developer profile
+    member_id INTEGER NOT NULL,
+    member_uuid BLOB NOT NULL UNIQUE,
+
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    icon INTEGER,
+
+    -- Addons which aren't deleted
+    addon_count INTEGER NOT NULL DEFAULT 0,
+
+    delete_reason TEXT,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    deleted_at DATETIME,
+
+    FOREIGN KEY(icon) REFERENCES media_upload(id) ON DELETE SET NULL
+);
+
+ALTER TABLE addon ADD COLUMN developer_id INTEGER REFERENCES developer(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_addon_developer_id ON addon (developer_id);
@@ -0,0 +1,39 @@
+CREATE TABLE addon_demo (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    public_id BLOB NOT NULL UNIQUE,
+
+    addon_id INTEGER NOT NULL,
+
+    title TEXT NOT NULL,
+    -- Demo website or where the screenshot links to
+    url TEXT NOT NULL,
+    -- Screenshot
+    media_id INTEGER,
+
+    idx INTEGER NOT NULL DEFAULT 0,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(media_id) REFERENCES media_upload(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_addon_demo_addon_id ON addon_demo (addon_id);
+
+-- Ephemeral instances installed on a placeholder website to preview an addon.
+-- Placeholder websites use negative ids so they never collide with real websites.
+CREATE TABLE addon_demo_sandbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    instance_id INTEGER NOT NULL UNIQUE,
+    member_uuid BLOB NOT NULL,
+
+    expires_at DATETIME NOT NULL,
+    created_at DATETIME NOT NULL,
+
+    FOREIGN KEY(instance_id) REFERENCES addon_instance(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_addon_demo_sandbox_member_uuid ON addon_demo_sandbox (member_uuid);
+CREATE INDEX idx_addon_demo_sandbox_expires_at ON addon_demo_sandbox (expires_at);
@@ -0,0 +1,19 @@
+CREATE TABLE addon_install_settings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    addon_id INTEGER NOT NULL,
+    -- NULL for the schema being edited. Copied into the compiled version on publish.
+    compiled_id INTEGER,
+
+    -- JSON array of the settings an instance can be configured with
+    fields JSON NOT NULL DEFAULT '[]',
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(compiled_id) REFERENCES addon_compiled(pk) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_addon_install_settings_draft ON addon_install_settings (addon_id) WHERE compiled_id IS NULL;
+CREATE UNIQUE INDEX idx_addon_install_settings_compiled_id ON addon_install_settings (compiled_id);
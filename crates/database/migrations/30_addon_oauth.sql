@@ -0,0 +1,40 @@
+-- Credentials the addon's server exchanges for instance tokens.
+CREATE TABLE addon_oauth_client (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    addon_id INTEGER NOT NULL UNIQUE,
+
+    -- SHA-256 of the client secret. The secret itself is only shown once.
+    secret_hash TEXT NOT NULL,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE
+);
+
+-- Bearer tokens the addon's server calls the API with. Scoped to a single instance.
+CREATE TABLE addon_oauth_token (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    addon_id INTEGER NOT NULL,
+    instance_id INTEGER NOT NULL,
+
+    -- SHA-256 of the tokens
+    access_hash TEXT NOT NULL UNIQUE,
+    refresh_hash TEXT NOT NULL UNIQUE,
+
+    -- JSON array of the granted addon permissions
+    scopes JSON NOT NULL DEFAULT '[]',
+
+    access_expires_at DATETIME NOT NULL,
+    refresh_expires_at DATETIME NOT NULL,
+    revoked_at DATETIME,
+
+    created_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(instance_id) REFERENCES addon_instance(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_addon_oauth_token_instance_id ON addon_oauth_token (instance_id);
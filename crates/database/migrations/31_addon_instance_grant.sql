@@ -0,0 +1,28 @@
+-- JSON array of the permissions the version requests. Copied from addon_permission on publish.
+ALTER TABLE addon_compiled ADD COLUMN permissions JSON NOT NULL DEFAULT '[]';
+
+UPDATE addon_compiled SET permissions = (
+    SELECT json_group_array(p.scope || '.' || p.category || COALESCE('.' || p.operation, '') || COALESCE('.' || p.info, ''))
+    FROM addon_permission p WHERE p.addon_id = addon_compiled.addon_id
+);
+
+-- The permissions the installer consented to for an instance.
+CREATE TABLE addon_instance_grant (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    instance_id INTEGER NOT NULL UNIQUE,
+
+    -- JSON array of the granted permissions
+    permissions JSON NOT NULL DEFAULT '[]',
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(instance_id) REFERENCES addon_instance(id) ON DELETE CASCADE
+);
+
+-- Existing instances were installed with every permission of their version.
+INSERT INTO addon_instance_grant (instance_id, permissions, created_at, updated_at)
+    SELECT i.id, c.permissions, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+    FROM addon_instance i INNER JOIN addon_compiled c ON c.addon_id = i.addon_id AND c.version = i.version
+    WHERE i.deleted_at IS NULL;
@@ -0,0 +1,44 @@
+-- The SPA the addon's dashboard pages are rendered with.
+CREATE TABLE addon_dashboard_bundle (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    addon_id INTEGER NOT NULL,
+    -- NULL for the bundle being edited. Copied into the compiled version on publish.
+    compiled_id INTEGER,
+
+    -- Hash of every asset path and hash. Assets are served under it.
+    hash TEXT NOT NULL,
+    -- Path of the asset which is loaded first
+    entry TEXT NOT NULL,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(compiled_id) REFERENCES addon_compiled(pk) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_addon_dashboard_bundle_draft ON addon_dashboard_bundle (addon_id) WHERE compiled_id IS NULL;
+CREATE UNIQUE INDEX idx_addon_dashboard_bundle_compiled_id ON addon_dashboard_bundle (compiled_id);
+CREATE INDEX idx_addon_dashboard_bundle_hash ON addon_dashboard_bundle (addon_id, hash);
+
+CREATE TABLE addon_dashboard_bundle_asset (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    bundle_id INTEGER NOT NULL,
+
+    -- Relative to the root of the bundle, e.g. "assets/index.js"
+    path TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+
+    -- Where it's kept in storage. Shared by the copies of the bundle.
+    store_path TEXT NOT NULL,
+    file_id TEXT NOT NULL,
+
+    created_at DATETIME NOT NULL,
+
+    FOREIGN KEY(bundle_id) REFERENCES addon_dashboard_bundle(id) ON DELETE CASCADE,
+    UNIQUE(bundle_id, path)
+);
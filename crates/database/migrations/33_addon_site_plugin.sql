@@ -0,0 +1,29 @@
+-- Scripts and styles an addon injects into every page of the published website.
+CREATE TABLE addon_site_plugin (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    public_id BLOB NOT NULL UNIQUE,
+
+    addon_id INTEGER NOT NULL,
+    -- NULL while it's a draft. Copied into the compiled version on publish.
+    compiled_id INTEGER,
+
+    name TEXT NOT NULL,
+
+    -- "head" or "bodyEnd"
+    placement JSON NOT NULL,
+    -- The script or style. Can contain {{settings.name}} placeholders.
+    content JSON NOT NULL,
+    -- Lower is injected first
+    sort_order INTEGER NOT NULL DEFAULT 0,
+
+    is_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    FOREIGN KEY(addon_id) REFERENCES addon(id) ON DELETE CASCADE,
+    FOREIGN KEY(compiled_id) REFERENCES addon_compiled(pk) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_addon_site_plugin_addon_id ON addon_site_plugin (addon_id);
+CREATE INDEX idx_addon_site_plugin_compiled_id ON addon_site_plugin (compiled_id);
@@ -0,0 +1,4 @@
+-- Content hash of the imported line. Prevents the same file from creating rows twice.
+ALTER TABLE schema_data ADD COLUMN import_id TEXT;
+
+CREATE UNIQUE INDEX idx_schema_data_import_id ON schema_data (schema_id, import_id) WHERE import_id IS NOT NULL;
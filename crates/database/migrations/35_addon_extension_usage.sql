@@ -0,0 +1,4 @@
+-- How many dashboard pages and CMS collections the compiled version shipped with. Neither is versioned
+-- so they're counted on publish. NULL on the draft and on versions published before.
+ALTER TABLE addon_extension ADD COLUMN dashboard_page_count INTEGER;
+ALTER TABLE addon_extension ADD COLUMN cms_collection_count INTEGER;
@@ -0,0 +1,9 @@
+-- Hands out the placeholder website ids of sandboxes. The id is negated so it never collides with
+-- a real website and AUTOINCREMENT ensures it's never handed out twice.
+CREATE TABLE addon_demo_website (
+    id INTEGER PRIMARY KEY AUTOINCREMENT
+);
+
+-- Continue after the placeholder ids already in use.
+INSERT INTO addon_demo_website (id)
+SELECT -MIN(website_id) FROM addon_instance WHERE website_id < 0 HAVING MIN(website_id) IS NOT NULL;
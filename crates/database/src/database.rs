@@ -1,5 +1,5 @@
 use eyre::Result;
-use sqlx::{migrate::MigrateDatabase, Sqlite, SqlitePool};
+use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Sqlite, SqlitePool};
 
 const DATABASE_PATH: &str = "./app/addons.db";
 
@@ -27,3 +27,21 @@ pub async fn init() -> Result<(bool, SqlitePool)> {
 
     Ok((!does_db_exist, pool))
 }
+
+/// Creates a fresh in-memory database with all migrations applied.
+pub async fn init_memory() -> Result<SqlitePool> {
+    let pool = SqlitePoolOptions::new()
+        // The database is dropped once its' last connection is closed.
+        .min_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect(&format!(
+            "sqlite:{}?mode=memory&cache=shared",
+            uuid::Uuid::new_v4()
+        ))
+        .await?;
+
+    sqlx::migrate!("./migrations").run(&pool).await?;
+
+    Ok(pool)
+}
@@ -1,14 +1,21 @@
 use eyre::Result;
-use local_common::{api::AddonPublic, AddonId, MediaId, MemberId};
+use local_common::{api::AddonPublic, AddonId, DeveloperId, MediaId, MemberId};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
-use serde::Serialize;
-use sqlx::{FromRow, SqliteConnection};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    query::QueryAs,
+    sqlite::{SqliteArguments, SqliteRow},
+    FromRow, Sqlite, SqliteConnection,
+};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+use crate::DeveloperModel;
+
 pub struct NewAddonModel {
     pub member_id: MemberId,
     pub member_uuid: Uuid,
+    pub developer_id: Option<DeveloperId>,
 
     pub name: String,
     pub name_id: String,
@@ -27,9 +34,9 @@ pub struct AddonModel {
 
     pub member_id: MemberId,
     pub member_uuid: Uuid,
+    pub developer_id: Option<DeveloperId>,
 
     pub guid: Uuid,
-    // TODO: Secret Key
     // TODO: App URL Redirect After Install (w/ auth code)
     // TODO: App URL Redirect After Authorization (w/ temp auth code)
     pub name: String,
@@ -47,6 +54,9 @@ pub struct AddonModel {
 
     pub install_count: i32,
 
+    pub rating_sum: i64,
+    pub rating_count: i32,
+
     pub delete_reason: Option<String>,
 
     pub created_at: OffsetDateTime,
@@ -60,11 +70,12 @@ impl NewAddonModel {
         let guid = Uuid::now_v7();
 
         let resp = sqlx::query(
-            "INSERT INTO addon (member_id, member_uuid, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, created_at, updated_at)
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $12, $13, $14, $14)",
+            "INSERT INTO addon (member_id, member_uuid, developer_id, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, created_at, updated_at)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $13, $14, $15, $15)",
         )
         .bind(self.member_id)
         .bind(self.member_uuid)
+        .bind(self.developer_id)
         .bind(guid)
         .bind(&self.name)
         .bind(&self.name_id)
@@ -84,6 +95,7 @@ VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $12, $13, $14, $14)",
             id: AddonId::from(resp.last_insert_rowid() as i32),
             member_id: self.member_id,
             member_uuid: self.member_uuid,
+            developer_id: self.developer_id,
             guid,
             name: self.name,
             name_id: self.name_id,
@@ -96,6 +108,8 @@ VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $12, $13, $14, $14)",
             is_accepted: false,
             is_visible: false,
             install_count: 0,
+            rating_sum: 0,
+            rating_count: 0,
             delete_reason: None,
             created_at: now,
             updated_at: now,
@@ -105,10 +119,11 @@ VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $12, $13, $14, $14)",
 }
 
 impl AddonModel {
+    /// Also recounts the addons of its' developer since the visibility may have changed.
     pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
         self.updated_at = OffsetDateTime::now_utc();
 
-        Ok(sqlx::query("UPDATE addon SET name = $1, name_id = $2, tag_line = $3, description = $4, icon = $5, version = $6, action_url = $7, root_dashboard_page = $8, is_visible = $9, is_accepted = $10, updated_at = $11 WHERE id = $12")
+        let res = sqlx::query("UPDATE addon SET name = $1, name_id = $2, tag_line = $3, description = $4, icon = $5, version = $6, action_url = $7, root_dashboard_page = $8, is_visible = $9, is_accepted = $10, updated_at = $11 WHERE id = $12")
             .bind(&self.name)
             .bind(&self.name_id)
             .bind(&self.tag_line)
@@ -121,13 +136,19 @@ impl AddonModel {
             .bind(self.is_accepted)
             .bind(self.updated_at)
             .bind(self.id)
-            .execute(db)
-            .await?.rows_affected())
+            .execute(&mut *db)
+            .await?;
+
+        if let Some(developer_id) = self.developer_id {
+            DeveloperModel::refresh_addon_count(developer_id, db).await?;
+        }
+
+        Ok(res.rows_affected())
     }
 
     pub async fn find_one_by_id(id: AddonId, db: &mut SqliteConnection) -> Result<Option<Self>> {
         Ok(sqlx::query_as(
-            "SELECT id, member_id, member_uuid, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE id = $1"
+            "SELECT id, member_id, member_uuid, developer_id, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, rating_sum, rating_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE id = $1"
         )
         .bind(id)
         .fetch_optional(db)
@@ -136,7 +157,7 @@ impl AddonModel {
 
     pub async fn find_one_by_guid(guid: Uuid, db: &mut SqliteConnection) -> Result<Option<Self>> {
         Ok(sqlx::query_as(
-            "SELECT id, member_id, member_uuid, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE guid = $1"
+            "SELECT id, member_id, member_uuid, developer_id, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, rating_sum, rating_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE guid = $1"
         )
         .bind(guid)
         .fetch_optional(db)
@@ -148,7 +169,7 @@ impl AddonModel {
         db: &mut SqliteConnection,
     ) -> Result<Option<Self>> {
         Ok(sqlx::query_as(
-            "SELECT id, member_id, member_uuid, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE name_id = $1"
+            "SELECT id, member_id, member_uuid, developer_id, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, rating_sum, rating_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE name_id = $1"
         )
         .bind(name_id)
         .fetch_optional(db)
@@ -157,7 +178,7 @@ impl AddonModel {
 
     pub async fn find_all(db: &mut SqliteConnection) -> Result<Vec<Self>> {
         Ok(sqlx::query_as(
-            "SELECT id, member_id, member_uuid, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, delete_reason, created_at, updated_at, deleted_at FROM addon"
+            "SELECT id, member_id, member_uuid, developer_id, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, rating_sum, rating_count, delete_reason, created_at, updated_at, deleted_at FROM addon"
         )
         .fetch_all(db)
         .await?)
@@ -165,21 +186,72 @@ impl AddonModel {
 
     pub async fn find_all_by_member(guid: Uuid, db: &mut SqliteConnection) -> Result<Vec<Self>> {
         Ok(sqlx::query_as(
-            "SELECT id, member_id, member_uuid, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE member_uuid = $1"
+            "SELECT id, member_id, member_uuid, developer_id, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, rating_sum, rating_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE member_uuid = $1"
         )
         .bind(guid)
         .fetch_all(db)
         .await?)
     }
 
+    /// Visible addons listed on the developer's page, newest first.
+    pub async fn find_visible_by_developer_id(
+        developer_id: DeveloperId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, member_id, member_uuid, developer_id, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, rating_sum, rating_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE developer_id = $1 AND is_visible = TRUE AND deleted_at IS NULL ORDER BY created_at DESC"
+        )
+        .bind(developer_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn search(
+        search: &AddonSearch,
+        offset: i64,
+        limit: i64,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        let filter = search.filter();
+
+        let sql = format!(
+            "SELECT id, member_id, member_uuid, developer_id, guid, name, name_id, tag_line, description, icon, version, action_url, root_dashboard_page, is_visible, is_accepted, install_count, rating_sum, rating_count, delete_reason, created_at, updated_at, deleted_at FROM addon WHERE {} ORDER BY {} LIMIT {limit} OFFSET {offset}",
+            filter.sql,
+            search.sort.order_by(),
+        );
+
+        Ok(filter.bind(sqlx::query_as(&sql)).fetch_all(db).await?)
+    }
+
+    pub async fn count_search(search: &AddonSearch, db: &mut SqliteConnection) -> Result<i64> {
+        let filter = search.filter();
+
+        let sql = format!("SELECT COUNT(*) FROM addon WHERE {}", filter.sql);
+
+        let (count,): (i64,) = filter.bind(sqlx::query_as(&sql)).fetch_one(db).await?;
+
+        Ok(count)
+    }
+
+    /// Also recounts the addons of its' developer.
     pub async fn delete(id: AddonId, reason: String, db: &mut SqliteConnection) -> Result<u64> {
         let res = sqlx::query("UPDATE addon SET deleted_at = $2, delete_reason = $3 WHERE id = $1")
             .bind(id)
             .bind(OffsetDateTime::now_utc())
             .bind(reason)
-            .execute(db)
+            .execute(&mut *db)
             .await?;
 
+        let developer_id: Option<DeveloperId> =
+            sqlx::query_scalar("SELECT developer_id FROM addon WHERE id = $1")
+                .bind(id)
+                .fetch_one(&mut *db)
+                .await?;
+
+        if let Some(developer_id) = developer_id {
+            DeveloperModel::refresh_addon_count(developer_id, db).await?;
+        }
+
         Ok(res.rows_affected())
     }
 
@@ -203,6 +275,10 @@ impl AddonModel {
             is_visible: self.is_visible,
             is_accepted: self.is_accepted,
             install_count: self.install_count,
+            rating: (self.rating_count != 0)
+                .then(|| self.rating_sum as f32 / self.rating_count as f32),
+            rating_count: self.rating_count,
+            tags: None,
             delete_reason: self.delete_reason,
             created_at: self.created_at,
             updated_at: self.updated_at,
@@ -211,6 +287,109 @@ impl AddonModel {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AddonSort {
+    #[default]
+    Newest,
+    Installs,
+    Rating,
+}
+
+impl AddonSort {
+    fn order_by(self) -> &'static str {
+        match self {
+            Self::Newest => "created_at DESC",
+            Self::Installs => "install_count DESC, created_at DESC",
+            Self::Rating => "(CASE WHEN rating_count = 0 THEN 0 ELSE CAST(rating_sum AS REAL) / rating_count END) DESC, rating_count DESC, created_at DESC",
+        }
+    }
+}
+
+/// Filters for the marketplace list.
+#[derive(Debug, Default)]
+pub struct AddonSearch {
+    /// Full-text search over the name, tag line and description.
+    pub query: Option<String>,
+    /// Tag slugs. Addons need to have all of them.
+    pub tags: Vec<String>,
+    pub member: Option<Uuid>,
+    pub sort: AddonSort,
+}
+
+pub(crate) enum SearchBind {
+    Text(String),
+    Uuid(Uuid),
+}
+
+/// WHERE clause on the `addon` table along with its' binds.
+pub(crate) struct SearchFilter {
+    pub sql: String,
+    binds: Vec<SearchBind>,
+}
+
+impl SearchFilter {
+    pub fn bind<'q, O>(
+        &self,
+        mut query: QueryAs<'q, Sqlite, O, SqliteArguments<'q>>,
+    ) -> QueryAs<'q, Sqlite, O, SqliteArguments<'q>>
+    where
+        O: for<'r> FromRow<'r, SqliteRow>,
+    {
+        for bind in &self.binds {
+            query = match bind {
+                SearchBind::Text(v) => query.bind(v.clone()),
+                SearchBind::Uuid(v) => query.bind(*v),
+            };
+        }
+
+        query
+    }
+}
+
+impl AddonSearch {
+    /// Quotes every word so the FTS query syntax can't be used. Words are matched as prefixes.
+    fn fts_query(&self) -> Option<String> {
+        let words = self
+            .query
+            .as_deref()?
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|v| !v.is_empty())
+            .map(|v| format!("\"{v}\"*"))
+            .collect::<Vec<_>>();
+
+        (!words.is_empty()).then(|| words.join(" "))
+    }
+
+    pub(crate) fn filter(&self) -> SearchFilter {
+        let mut sql = String::from("addon.deleted_at IS NULL");
+        let mut binds = Vec::new();
+
+        if let Some(member) = self.member {
+            binds.push(SearchBind::Uuid(member));
+            sql.push_str(&format!(" AND addon.member_uuid = ${}", binds.len()));
+        }
+
+        if let Some(query) = self.fts_query() {
+            binds.push(SearchBind::Text(query));
+            sql.push_str(&format!(
+                " AND addon.id IN (SELECT rowid FROM addon_search WHERE addon_search MATCH ${})",
+                binds.len()
+            ));
+        }
+
+        for tag in &self.tags {
+            binds.push(SearchBind::Text(tag.clone()));
+            sql.push_str(&format!(
+                " AND addon.id IN (SELECT addon_tag.addon_id FROM addon_tag INNER JOIN tag ON tag.id = addon_tag.tag_id WHERE tag.slug = ${})",
+                binds.len()
+            ));
+        }
+
+        SearchFilter { sql, binds }
+    }
+}
+
 #[derive(Debug, Clone, Copy, serde::Serialize, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
 pub enum AddonType {
@@ -1 +1,271 @@
 // Create triggers and automation's that come with your app.
+
+use eyre::Result;
+use local_common::{AddonAutomationId, AddonCompiledId, AddonId};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow, SqliteConnection};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+use webby_global_common::{filter::Filter, value::SimpleValue};
+
+/// Scheduled automations can't run more often than this.
+pub const MIN_SCHEDULE_INTERVAL_MINUTES: i64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CmsRowEvent {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AutomationTrigger {
+    /// A row in one of the addons' collections changed.
+    #[serde(rename_all = "camelCase")]
+    CmsRow {
+        collection: String,
+        events: Vec<CmsRowEvent>,
+    },
+
+    /// Runs every X minutes.
+    #[serde(rename_all = "camelCase")]
+    Schedule { interval_minutes: i64 },
+
+    /// The addon was installed onto a website.
+    InstanceInstalled,
+}
+
+impl AutomationTrigger {
+    pub fn next_run_at(&self, from: OffsetDateTime) -> Option<OffsetDateTime> {
+        match self {
+            Self::Schedule { interval_minutes } => Some(
+                from + Duration::minutes((*interval_minutes).max(MIN_SCHEDULE_INTERVAL_MINUTES)),
+            ),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AutomationAction {
+    /// POST to "{action_url}/{path}". Defaults to "{action_url}/automation"
+    CallActionUrl { path: Option<String> },
+
+    /// Sets a field on the row which triggered the automation.
+    UpdateRow {
+        field: String,
+        value: Option<SimpleValue>,
+    },
+
+    /// Queues an "automation.triggered" event for the addons' webhook.
+    SendWebhook,
+}
+
+pub struct NewAddonAutomationModel {
+    pub addon_id: AddonId,
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub name: String,
+
+    pub triggered_by: AutomationTrigger,
+    pub conditions: Vec<Filter>,
+    pub actions: Vec<AutomationAction>,
+
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonAutomationModel {
+    #[serde(skip)]
+    pub id: AddonAutomationId,
+    #[serde(rename = "id")]
+    pub public_id: Uuid,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+    #[serde(skip)]
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub name: String,
+
+    #[serde(rename = "trigger")]
+    pub triggered_by: Json<AutomationTrigger>,
+    pub conditions: Json<Vec<Filter>>,
+    pub actions: Json<Vec<AutomationAction>>,
+
+    pub is_enabled: bool,
+
+    pub next_run_at: Option<OffsetDateTime>,
+    pub last_run_at: Option<OffsetDateTime>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonAutomationModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonAutomationModel> {
+        let now = OffsetDateTime::now_utc();
+        let public_id = Uuid::now_v7();
+
+        // Drafts are never ran.
+        let next_run_at = self
+            .compiled_id
+            .and_then(|_| self.triggered_by.next_run_at(now));
+
+        let triggered_by = Json(self.triggered_by);
+        let conditions = Json(self.conditions);
+        let actions = Json(self.actions);
+
+        let res = sqlx::query(
+            "INSERT INTO addon_automation (public_id, addon_id, compiled_id, name, triggered_by, conditions, actions, is_enabled, next_run_at, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10)",
+        )
+        .bind(public_id)
+        .bind(self.addon_id)
+        .bind(self.compiled_id)
+        .bind(&self.name)
+        .bind(&triggered_by)
+        .bind(&conditions)
+        .bind(&actions)
+        .bind(self.is_enabled)
+        .bind(next_run_at)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonAutomationModel {
+            id: AddonAutomationId::from(res.last_insert_rowid() as i32),
+            public_id,
+            addon_id: self.addon_id,
+            compiled_id: self.compiled_id,
+            name: self.name,
+            triggered_by,
+            conditions,
+            actions,
+            is_enabled: self.is_enabled,
+            next_run_at,
+            last_run_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonAutomationModel {
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_automation SET name = $2, triggered_by = $3, conditions = $4, actions = $5, is_enabled = $6, next_run_at = $7, last_run_at = $8, updated_at = $9 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.name)
+        .bind(&self.triggered_by)
+        .bind(&self.conditions)
+        .bind(&self.actions)
+        .bind(self.is_enabled)
+        .bind(self.next_run_at)
+        .bind(self.last_run_at)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn delete(self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM addon_automation WHERE id = $1")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Copies every draft into the compiled version.
+    pub async fn publish_drafts(
+        addon_id: AddonId,
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        let drafts = Self::find_drafts_by_addon_id(addon_id, db).await?;
+
+        let mut items = Vec::new();
+
+        for draft in drafts {
+            items.push(
+                NewAddonAutomationModel {
+                    addon_id,
+                    compiled_id: Some(compiled_id),
+                    name: draft.name,
+                    triggered_by: draft.triggered_by.0,
+                    conditions: draft.conditions.0,
+                    actions: draft.actions.0,
+                    is_enabled: draft.is_enabled,
+                }
+                .insert(db)
+                .await?,
+            );
+        }
+
+        Ok(items)
+    }
+
+    pub async fn find_one_draft_by_public_id(
+        addon_id: AddonId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, compiled_id, name, triggered_by, conditions, actions, is_enabled, next_run_at, last_run_at, created_at, updated_at FROM addon_automation WHERE addon_id = $1 AND public_id = $2 AND compiled_id IS NULL",
+        )
+        .bind(addon_id)
+        .bind(public_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_drafts_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, compiled_id, name, triggered_by, conditions, actions, is_enabled, next_run_at, last_run_at, created_at, updated_at FROM addon_automation WHERE addon_id = $1 AND compiled_id IS NULL ORDER BY id ASC",
+        )
+        .bind(addon_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn find_by_compiled_id(
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, compiled_id, name, triggered_by, conditions, actions, is_enabled, next_run_at, last_run_at, created_at, updated_at FROM addon_automation WHERE compiled_id = $1 ORDER BY id ASC",
+        )
+        .bind(compiled_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    /// Scheduled automations of the addons' current version which should be ran.
+    pub async fn find_due_scheduled(limit: i64, db: &mut SqliteConnection) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT auto.id, auto.public_id, auto.addon_id, auto.compiled_id, auto.name, auto.triggered_by, auto.conditions, auto.actions, auto.is_enabled, auto.next_run_at, auto.last_run_at, auto.created_at, auto.updated_at
+            FROM addon_automation auto
+            INNER JOIN addon_compiled comp ON comp.pk = auto.compiled_id
+            INNER JOIN addon ON addon.id = auto.addon_id
+            WHERE auto.is_enabled = TRUE AND auto.next_run_at IS NOT NULL AND auto.next_run_at <= $1 AND comp.version = addon.version
+            ORDER BY auto.next_run_at ASC
+            LIMIT $2",
+        )
+        .bind(OffsetDateTime::now_utc())
+        .bind(limit)
+        .fetch_all(db)
+        .await?)
+    }
+}
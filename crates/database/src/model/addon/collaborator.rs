@@ -1 +1,244 @@
+// Members who work on an addon besides its' owner.
 
+use eyre::Result;
+use local_common::{AddonCollaboratorId, AddonId};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    database::{HasArguments, HasValueRef},
+    encode::IsNull,
+    error::BoxDynError,
+    sqlite::SqliteTypeInfo,
+    Decode, Encode, FromRow, Sqlite, SqliteConnection, Type,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::AddonModel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AddonRole {
+    Owner,
+    Developer,
+    Designer,
+    Viewer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AddonCapability {
+    View,
+    /// Widgets, pages, templates and media.
+    EditDesign,
+    /// Scripts, CMS collections, automations and the extension manifest.
+    EditCode,
+    /// CMS rows.
+    EditData,
+    Publish,
+    /// Replying to and hiding reviews.
+    ManageReviews,
+    /// Pricing plans and webhooks.
+    ManageSettings,
+    ManageCollaborators,
+}
+
+impl AddonRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Developer => "developer",
+            Self::Designer => "designer",
+            Self::Viewer => "viewer",
+        }
+    }
+
+    pub fn capabilities(self) -> &'static [AddonCapability] {
+        use AddonCapability::*;
+
+        match self {
+            Self::Owner => &[
+                View,
+                EditDesign,
+                EditCode,
+                EditData,
+                Publish,
+                ManageReviews,
+                ManageSettings,
+                ManageCollaborators,
+            ],
+            Self::Developer => &[View, EditDesign, EditCode, EditData, Publish, ManageReviews],
+            Self::Designer => &[View, EditDesign],
+            Self::Viewer => &[View],
+        }
+    }
+
+    pub fn can(self, capability: AddonCapability) -> bool {
+        self.capabilities().contains(&capability)
+    }
+}
+
+impl Encode<'_, Sqlite> for AddonRole {
+    fn encode_by_ref(&self, buf: &mut <Sqlite as HasArguments<'_>>::ArgumentBuffer) -> IsNull {
+        Encode::<Sqlite>::encode_by_ref(&String::from(self.as_str()), buf)
+    }
+}
+
+impl Decode<'_, Sqlite> for AddonRole {
+    fn decode(value: <Sqlite as HasValueRef<'_>>::ValueRef) -> Result<Self, BoxDynError> {
+        Ok(match <String as Decode<Sqlite>>::decode(value)?.as_str() {
+            "owner" => Self::Owner,
+            "developer" => Self::Developer,
+            "designer" => Self::Designer,
+            "viewer" => Self::Viewer,
+            v => return Err(format!("Unknown Addon Role: {v}").into()),
+        })
+    }
+}
+
+impl Type<Sqlite> for AddonRole {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+}
+
+pub struct NewAddonCollaboratorModel {
+    pub addon_id: AddonId,
+    pub member_uuid: Uuid,
+
+    pub role: AddonRole,
+    pub invited_by: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonCollaboratorModel {
+    #[serde(skip)]
+    pub id: AddonCollaboratorId,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+    #[serde(rename = "memberId")]
+    pub member_uuid: Uuid,
+
+    pub role: AddonRole,
+
+    pub invited_by: Uuid,
+    pub accepted_at: Option<OffsetDateTime>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonCollaboratorModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonCollaboratorModel> {
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "INSERT INTO addon_collaborator (addon_id, member_uuid, role, invited_by, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $5)",
+        )
+        .bind(self.addon_id)
+        .bind(self.member_uuid)
+        .bind(self.role)
+        .bind(self.invited_by)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonCollaboratorModel {
+            id: AddonCollaboratorId::from(res.last_insert_rowid() as i32),
+            addon_id: self.addon_id,
+            member_uuid: self.member_uuid,
+            role: self.role,
+            invited_by: self.invited_by,
+            accepted_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonCollaboratorModel {
+    pub fn is_accepted(&self) -> bool {
+        self.accepted_at.is_some()
+    }
+
+    /// The role of the member. The member who created the addon is always its' owner.
+    pub async fn find_role(
+        addon: &AddonModel,
+        member_uuid: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<AddonRole>> {
+        if addon.member_uuid == member_uuid {
+            return Ok(Some(AddonRole::Owner));
+        }
+
+        Ok(Self::find_one_by_addon_member(addon.id, member_uuid, db)
+            .await?
+            .filter(|v| v.is_accepted())
+            .map(|v| v.role))
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_collaborator SET role = $2, accepted_at = $3, updated_at = $4 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(self.role)
+        .bind(self.accepted_at)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn delete(self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM addon_collaborator WHERE id = $1")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_addon_member(
+        addon_id: AddonId,
+        member_uuid: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, member_uuid, role, invited_by, accepted_at, created_at, updated_at FROM addon_collaborator WHERE addon_id = $1 AND member_uuid = $2",
+        )
+        .bind(addon_id)
+        .bind(member_uuid)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, member_uuid, role, invited_by, accepted_at, created_at, updated_at FROM addon_collaborator WHERE addon_id = $1 ORDER BY created_at",
+        )
+        .bind(addon_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    /// Invites the member hasn't accepted yet.
+    pub async fn find_pending_by_member_uuid(
+        member_uuid: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, member_uuid, role, invited_by, accepted_at, created_at, updated_at FROM addon_collaborator WHERE member_uuid = $1 AND accepted_at IS NULL ORDER BY created_at",
+        )
+        .bind(member_uuid)
+        .fetch_all(db)
+        .await?)
+    }
+}
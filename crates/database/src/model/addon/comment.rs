@@ -1 +1,221 @@
+// Marketplace reviews of an addon.
+// A review is a comment with a rating. Developer replies are threaded under it.
 
+use eyre::Result;
+use local_common::{AddonCommentId, AddonId};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Reviews are hidden once this many members reported them.
+pub const REPORT_HIDE_THRESHOLD: i32 = 5;
+
+pub struct NewAddonCommentModel {
+    pub addon_id: AddonId,
+    pub parent_id: Option<AddonCommentId>,
+
+    pub member_uuid: Uuid,
+
+    pub rating: Option<i32>,
+    pub body: String,
+
+    pub is_verified: bool,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonCommentModel {
+    #[serde(skip)]
+    pub id: AddonCommentId,
+    #[serde(rename = "id")]
+    pub public_id: Uuid,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+    #[serde(skip)]
+    pub parent_id: Option<AddonCommentId>,
+
+    #[serde(rename = "memberId")]
+    pub member_uuid: Uuid,
+
+    pub rating: Option<i32>,
+    pub body: String,
+
+    pub is_verified: bool,
+    pub is_hidden: bool,
+    #[serde(skip)]
+    pub report_count: i32,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonCommentModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonCommentModel> {
+        let public_id = Uuid::now_v7();
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "INSERT INTO addon_comment (public_id, addon_id, parent_id, member_uuid, rating, body, is_verified, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)",
+        )
+        .bind(public_id)
+        .bind(self.addon_id)
+        .bind(self.parent_id)
+        .bind(self.member_uuid)
+        .bind(self.rating)
+        .bind(&self.body)
+        .bind(self.is_verified)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonCommentModel {
+            id: AddonCommentId::from(res.last_insert_rowid() as i32),
+            public_id,
+            addon_id: self.addon_id,
+            parent_id: self.parent_id,
+            member_uuid: self.member_uuid,
+            rating: self.rating,
+            body: self.body,
+            is_verified: self.is_verified,
+            is_hidden: false,
+            report_count: 0,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonCommentModel {
+    pub fn is_review(&self) -> bool {
+        self.parent_id.is_none()
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_comment SET rating = $2, body = $3, is_verified = $4, is_hidden = $5, updated_at = $6 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(self.rating)
+        .bind(&self.body)
+        .bind(self.is_verified)
+        .bind(self.is_hidden)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Also deletes the replies.
+    pub async fn delete(self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM addon_comment WHERE id = $1 OR parent_id = $1")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Records the report once per member. Returns false if the member already reported it.
+    ///
+    /// Hides the comment once it reaches [`REPORT_HIDE_THRESHOLD`].
+    pub async fn report(
+        &mut self,
+        member_uuid: Uuid,
+        reason: Option<String>,
+        db: &mut SqliteConnection,
+    ) -> Result<bool> {
+        let res = sqlx::query(
+            "INSERT OR IGNORE INTO addon_comment_report (comment_id, member_uuid, reason, created_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(self.id)
+        .bind(member_uuid)
+        .bind(reason)
+        .bind(OffsetDateTime::now_utc())
+        .execute(&mut *db)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        // Counted in the query so concurrent reports aren't lost.
+        let (report_count, is_hidden): (i32, bool) = sqlx::query_as(
+            "UPDATE addon_comment SET report_count = report_count + 1, is_hidden = (is_hidden OR report_count + 1 >= $2) WHERE id = $1 RETURNING report_count, is_hidden",
+        )
+        .bind(self.id)
+        .bind(REPORT_HIDE_THRESHOLD)
+        .fetch_one(db)
+        .await?;
+
+        self.report_count = report_count;
+        self.is_hidden = is_hidden;
+
+        Ok(true)
+    }
+
+    /// Recounts the rating of the addon from its' reviews.
+    ///
+    /// Only reviews hidden by reports are left out. Hiding a review as the developer keeps its' rating.
+    pub async fn refresh_addon_rating(addon_id: AddonId, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query(
+            "UPDATE addon SET
+                rating_sum = (SELECT COALESCE(SUM(rating), 0) FROM addon_comment WHERE addon_id = $1 AND parent_id IS NULL AND report_count < $2),
+                rating_count = (SELECT COUNT(*) FROM addon_comment WHERE addon_id = $1 AND parent_id IS NULL AND report_count < $2)
+            WHERE id = $1",
+        )
+        .bind(addon_id)
+        .bind(REPORT_HIDE_THRESHOLD)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_public_id(
+        addon_id: AddonId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, parent_id, member_uuid, rating, body, is_verified, is_hidden, report_count, created_at, updated_at FROM addon_comment WHERE addon_id = $1 AND public_id = $2",
+        )
+        .bind(addon_id)
+        .bind(public_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_one_review_by_member(
+        addon_id: AddonId,
+        member_uuid: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, parent_id, member_uuid, rating, body, is_verified, is_hidden, report_count, created_at, updated_at FROM addon_comment WHERE addon_id = $1 AND member_uuid = $2 AND parent_id IS NULL",
+        )
+        .bind(addon_id)
+        .bind(member_uuid)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// Reviews and replies, newest first.
+    pub async fn find_by_addon_id(
+        addon_id: AddonId,
+        include_hidden: bool,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, parent_id, member_uuid, rating, body, is_verified, is_hidden, report_count, created_at, updated_at FROM addon_comment WHERE addon_id = $1 AND ($2 OR is_hidden = FALSE) ORDER BY created_at DESC",
+        )
+        .bind(addon_id)
+        .bind(include_hidden)
+        .fetch_all(db)
+        .await?)
+    }
+}
@@ -22,6 +22,9 @@ pub struct NewAddonCompiledModel {
 
     pub type_of: AddonPublishType,
     pub version: String,
+
+    /// Permissions the version requests
+    pub permissions: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, FromRow)]
@@ -39,6 +42,8 @@ pub struct AddonCompiledModel {
     pub type_of: AddonPublishType,
     pub version: String,
 
+    pub permissions: Json<Vec<String>>,
+
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
     pub deleted_at: Option<OffsetDateTime>,
@@ -49,15 +54,17 @@ impl NewAddonCompiledModel {
         let id = AddonCompiledPublicId::new();
         let now = OffsetDateTime::now_utc();
         let settings = Json(self.settings);
+        let permissions = Json(self.permissions);
 
         let res = sqlx::query(
-            "INSERT INTO addon_compiled (id, addon_id, settings, type, version, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $6)",
+            "INSERT INTO addon_compiled (id, addon_id, settings, type, version, permissions, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $7)",
         )
         .bind(id)
         .bind(self.addon_id)
         .bind(&settings)
         .bind(self.type_of)
         .bind(&self.version)
+        .bind(&permissions)
         .bind(now)
         .execute(db)
         .await?;
@@ -72,6 +79,8 @@ impl NewAddonCompiledModel {
             type_of: self.type_of,
             version: self.version,
 
+            permissions,
+
             created_at: now,
             updated_at: now,
             deleted_at: None,
@@ -86,7 +95,7 @@ impl AddonCompiledModel {
     ) -> Result<Option<Self>> {
         Ok(
             sqlx::query_as(
-                "SELECT pk, id, addon_id, settings, type, version, created_at, updated_at, deleted_at FROM addon_compiled WHERE id = $1",
+                "SELECT pk, id, addon_id, settings, type, version, permissions, created_at, updated_at, deleted_at FROM addon_compiled WHERE id = $1",
             )
             .bind(id)
             .fetch_optional(db)
@@ -101,7 +110,7 @@ impl AddonCompiledModel {
     ) -> Result<Option<Self>> {
         Ok(
             sqlx::query_as(
-                "SELECT pk, id, addon_id, settings, type, version, created_at, updated_at, deleted_at FROM addon_compiled WHERE addon_id = $1 AND version = $2",
+                "SELECT pk, id, addon_id, settings, type, version, permissions, created_at, updated_at, deleted_at FROM addon_compiled WHERE addon_id = $1 AND version = $2",
             )
             .bind(uuid)
             .bind(version)
@@ -110,6 +119,31 @@ impl AddonCompiledModel {
         )
     }
 
+    pub async fn find_latest_published(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT pk, id, addon_id, settings, type, version, permissions, created_at, updated_at, deleted_at FROM addon_compiled WHERE addon_id = $1 AND type = 'publish' AND deleted_at IS NULL ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(addon_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// The version of the newest publish of each addon.
+    pub async fn find_latest_published_versions(
+        ids: &[AddonId],
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<(AddonId, String)>> {
+        Ok(sqlx::query_as(&format!(
+            "SELECT addon_id, version FROM addon_compiled c WHERE addon_id IN ({}) AND type = 'publish' AND deleted_at IS NULL AND created_at = (SELECT MAX(created_at) FROM addon_compiled WHERE addon_id = c.addon_id AND type = 'publish' AND deleted_at IS NULL)",
+            ids.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+        ))
+        .fetch_all(db)
+        .await?)
+    }
+
     pub async fn get_all(
         uuid: AddonId,
         offset: usize,
@@ -118,7 +152,7 @@ impl AddonCompiledModel {
     ) -> Result<Vec<Self>> {
         Ok(
             sqlx::query_as(
-                "SELECT pk, id, addon_id, settings, type, version, created_at, updated_at, deleted_at FROM addon_compiled WHERE addon_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3",
+                "SELECT pk, id, addon_id, settings, type, version, permissions, created_at, updated_at, deleted_at FROM addon_compiled WHERE addon_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3",
             )
             .bind(uuid)
             .bind(limit as i64)
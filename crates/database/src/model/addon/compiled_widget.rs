@@ -112,6 +112,20 @@ impl AddonCompiledWidget {
         )
     }
 
+    pub async fn find_by_compiled_id(
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(
+            sqlx::query_as(
+                "SELECT pk, id, addon_id, widget_id, compiled_id, data, script, version, title, description, thumbnail, settings, hash, created_at, updated_at FROM addon_compiled_widget WHERE compiled_id = $1",
+            )
+            .bind(compiled_id)
+            .fetch_all(db)
+            .await?,
+        )
+    }
+
     pub async fn find_one_by_compiled_id_and_widget_id(
         compiled_id: AddonCompiledId,
         widget_id: AddonWidgetId,
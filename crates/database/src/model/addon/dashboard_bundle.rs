@@ -0,0 +1,255 @@
+// Dashboard SPA Bundle
+// The built assets the addon's dashboard pages are rendered with. Uploaded while editing and copied
+// into the compiled version on publish. Bundles are immutable, a new upload replaces the draft.
+
+use eyre::Result;
+use local_common::{AddonCompiledId, AddonDashboardBundleAssetId, AddonDashboardBundleId, AddonId};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection};
+use time::OffsetDateTime;
+
+pub struct NewAddonDashboardBundleModel {
+    pub addon_id: AddonId,
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub hash: String,
+    pub entry: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonDashboardBundleModel {
+    #[serde(skip)]
+    pub id: AddonDashboardBundleId,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+    #[serde(skip)]
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub hash: String,
+    pub entry: String,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonDashboardBundleModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonDashboardBundleModel> {
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "INSERT INTO addon_dashboard_bundle (addon_id, compiled_id, hash, entry, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $5)",
+        )
+        .bind(self.addon_id)
+        .bind(self.compiled_id)
+        .bind(&self.hash)
+        .bind(&self.entry)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonDashboardBundleModel {
+            id: AddonDashboardBundleId::from(res.last_insert_rowid() as i32),
+            addon_id: self.addon_id,
+            compiled_id: self.compiled_id,
+            hash: self.hash,
+            entry: self.entry,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonDashboardBundleModel {
+    pub async fn delete(self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM addon_dashboard_bundle WHERE id = $1")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Copies the bundle being edited into the compiled version. The assets aren't re-uploaded.
+    pub async fn publish_draft(
+        addon_id: AddonId,
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        let Some(draft) = Self::find_one_draft_by_addon_id(addon_id, &mut *db).await? else {
+            return Ok(None);
+        };
+
+        let bundle = NewAddonDashboardBundleModel {
+            addon_id,
+            compiled_id: Some(compiled_id),
+            hash: draft.hash,
+            entry: draft.entry,
+        }
+        .insert(&mut *db)
+        .await?;
+
+        sqlx::query(
+            r#"INSERT INTO addon_dashboard_bundle_asset (bundle_id, path, content_type, file_size, hash, store_path, file_id, created_at)
+                SELECT $2, path, content_type, file_size, hash, store_path, file_id, created_at
+                FROM addon_dashboard_bundle_asset WHERE bundle_id = $1"#,
+        )
+        .bind(draft.id)
+        .bind(bundle.id)
+        .execute(db)
+        .await?;
+
+        Ok(Some(bundle))
+    }
+
+    pub async fn find_one_draft_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, compiled_id, hash, entry, created_at, updated_at FROM addon_dashboard_bundle WHERE addon_id = $1 AND compiled_id IS NULL",
+        )
+        .bind(addon_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_compiled_id(
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, compiled_id, hash, entry, created_at, updated_at FROM addon_dashboard_bundle WHERE compiled_id = $1",
+        )
+        .bind(compiled_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// Any copy of the bundle. They all contain the same assets.
+    pub async fn find_one_by_addon_id_and_hash(
+        addon_id: AddonId,
+        hash: &str,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, compiled_id, hash, entry, created_at, updated_at FROM addon_dashboard_bundle WHERE addon_id = $1 AND hash = $2 LIMIT 1",
+        )
+        .bind(addon_id)
+        .bind(hash)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// The bundle of the newest compiled version which has one.
+    pub async fn find_one_newest_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            r#"SELECT b.id, b.addon_id, b.compiled_id, b.hash, b.entry, b.created_at, b.updated_at
+                FROM addon_dashboard_bundle b
+                INNER JOIN addon_compiled c ON c.pk = b.compiled_id
+                WHERE b.addon_id = $1 AND c.deleted_at IS NULL
+                ORDER BY c.created_at DESC LIMIT 1"#,
+        )
+        .bind(addon_id)
+        .fetch_optional(db)
+        .await?)
+    }
+}
+
+pub struct NewAddonDashboardBundleAssetModel {
+    pub bundle_id: AddonDashboardBundleId,
+
+    pub path: String,
+    pub content_type: String,
+    pub file_size: i64,
+    pub hash: String,
+
+    pub store_path: String,
+    pub file_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonDashboardBundleAssetModel {
+    #[serde(skip)]
+    pub id: AddonDashboardBundleAssetId,
+
+    #[serde(skip)]
+    pub bundle_id: AddonDashboardBundleId,
+
+    pub path: String,
+    pub content_type: String,
+    pub file_size: i64,
+    pub hash: String,
+
+    #[serde(skip)]
+    pub store_path: String,
+    #[serde(skip)]
+    pub file_id: String,
+
+    pub created_at: OffsetDateTime,
+}
+
+impl NewAddonDashboardBundleAssetModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonDashboardBundleAssetModel> {
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "INSERT INTO addon_dashboard_bundle_asset (bundle_id, path, content_type, file_size, hash, store_path, file_id, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(self.bundle_id)
+        .bind(&self.path)
+        .bind(&self.content_type)
+        .bind(self.file_size)
+        .bind(&self.hash)
+        .bind(&self.store_path)
+        .bind(&self.file_id)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonDashboardBundleAssetModel {
+            id: AddonDashboardBundleAssetId::from(res.last_insert_rowid()),
+            bundle_id: self.bundle_id,
+            path: self.path,
+            content_type: self.content_type,
+            file_size: self.file_size,
+            hash: self.hash,
+            store_path: self.store_path,
+            file_id: self.file_id,
+            created_at: now,
+        })
+    }
+}
+
+impl AddonDashboardBundleAssetModel {
+    pub async fn find_by_bundle_id(
+        bundle_id: AddonDashboardBundleId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, bundle_id, path, content_type, file_size, hash, store_path, file_id, created_at FROM addon_dashboard_bundle_asset WHERE bundle_id = $1 ORDER BY path",
+        )
+        .bind(bundle_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_bundle_id_and_path(
+        bundle_id: AddonDashboardBundleId,
+        path: &str,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, bundle_id, path, content_type, file_size, hash, store_path, file_id, created_at FROM addon_dashboard_bundle_asset WHERE bundle_id = $1 AND path = $2",
+        )
+        .bind(bundle_id)
+        .bind(path)
+        .fetch_optional(db)
+        .await?)
+    }
+}
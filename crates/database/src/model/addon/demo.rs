@@ -1 +1,240 @@
+// Lets members preview an addon before installing it.
+// Demos are websites or screenshots the developer links to. Sandboxes are ephemeral instances
+// installed on a placeholder website.
 
+use eyre::Result;
+use local_common::{AddonDemoId, AddonDemoSandboxId, AddonId, AddonInstanceId, MediaId, WebsiteId};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+/// How long a sandbox lives for.
+pub const SANDBOX_LIFETIME: Duration = Duration::hours(1);
+
+pub struct NewAddonDemoModel {
+    pub addon_id: AddonId,
+
+    pub title: String,
+    pub url: String,
+    pub media_id: Option<MediaId>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonDemoModel {
+    #[serde(skip)]
+    pub id: AddonDemoId,
+    #[serde(rename = "id")]
+    pub public_id: Uuid,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+
+    pub title: String,
+    pub url: String,
+    #[serde(skip)]
+    pub media_id: Option<MediaId>,
+
+    pub idx: i32,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonDemoModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonDemoModel> {
+        let public_id = Uuid::now_v7();
+        let now = OffsetDateTime::now_utc();
+
+        // Newest demo goes last.
+        let idx: i32 = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(idx) + 1, 0) FROM addon_demo WHERE addon_id = $1",
+        )
+        .bind(self.addon_id)
+        .fetch_one(&mut *db)
+        .await?;
+
+        let res = sqlx::query(
+            "INSERT INTO addon_demo (public_id, addon_id, title, url, media_id, idx, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $7)",
+        )
+        .bind(public_id)
+        .bind(self.addon_id)
+        .bind(&self.title)
+        .bind(&self.url)
+        .bind(self.media_id)
+        .bind(idx)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonDemoModel {
+            id: AddonDemoId::from(res.last_insert_rowid() as i32),
+            public_id,
+            addon_id: self.addon_id,
+            title: self.title,
+            url: self.url,
+            media_id: self.media_id,
+            idx,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonDemoModel {
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_demo SET title = $2, url = $3, media_id = $4, idx = $5, updated_at = $6 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.title)
+        .bind(&self.url)
+        .bind(self.media_id)
+        .bind(self.idx)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn delete(self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM addon_demo WHERE id = $1")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_public_id(
+        addon_id: AddonId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, title, url, media_id, idx, created_at, updated_at FROM addon_demo WHERE addon_id = $1 AND public_id = $2",
+        )
+        .bind(addon_id)
+        .bind(public_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, title, url, media_id, idx, created_at, updated_at FROM addon_demo WHERE addon_id = $1 ORDER BY idx, id",
+        )
+        .bind(addon_id)
+        .fetch_all(db)
+        .await?)
+    }
+}
+
+pub struct NewAddonDemoSandboxModel {
+    pub instance_id: AddonInstanceId,
+    pub member_uuid: Uuid,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AddonDemoSandboxModel {
+    pub id: AddonDemoSandboxId,
+
+    pub instance_id: AddonInstanceId,
+    pub member_uuid: Uuid,
+
+    pub expires_at: OffsetDateTime,
+    pub created_at: OffsetDateTime,
+}
+
+impl NewAddonDemoSandboxModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonDemoSandboxModel> {
+        let now = OffsetDateTime::now_utc();
+        let expires_at = now + SANDBOX_LIFETIME;
+
+        let res = sqlx::query(
+            "INSERT INTO addon_demo_sandbox (instance_id, member_uuid, expires_at, created_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(self.instance_id)
+        .bind(self.member_uuid)
+        .bind(expires_at)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonDemoSandboxModel {
+            id: AddonDemoSandboxId::from(res.last_insert_rowid() as i32),
+            instance_id: self.instance_id,
+            member_uuid: self.member_uuid,
+            expires_at,
+            created_at: now,
+        })
+    }
+}
+
+impl AddonDemoSandboxModel {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= OffsetDateTime::now_utc()
+    }
+
+    /// A website id no real website or other sandbox uses.
+    pub async fn next_placeholder_website_id(db: &mut SqliteConnection) -> Result<WebsiteId> {
+        let id: i32 =
+            sqlx::query_scalar("INSERT INTO addon_demo_website DEFAULT VALUES RETURNING id")
+                .fetch_one(db)
+                .await?;
+
+        Ok(WebsiteId::from(-id))
+    }
+
+    /// Deletes the expired sandboxes along with their instances.
+    pub async fn delete_expired(db: &mut SqliteConnection) -> Result<u64> {
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "DELETE FROM addon_instance WHERE id IN (SELECT instance_id FROM addon_demo_sandbox WHERE expires_at <= $1)",
+        )
+        .bind(now)
+        .execute(&mut *db)
+        .await?;
+
+        sqlx::query("DELETE FROM addon_demo_sandbox WHERE expires_at <= $1")
+            .bind(now)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_instance_id(
+        instance_id: AddonInstanceId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, instance_id, member_uuid, expires_at, created_at FROM addon_demo_sandbox WHERE instance_id = $1",
+        )
+        .bind(instance_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// Sandboxes of the member which haven't expired.
+    pub async fn count_active_by_member_uuid(
+        member_uuid: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<i64> {
+        Ok(sqlx::query_scalar(
+            "SELECT COUNT(*) FROM addon_demo_sandbox WHERE member_uuid = $1 AND expires_at > $2",
+        )
+        .bind(member_uuid)
+        .bind(OffsetDateTime::now_utc())
+        .fetch_one(db)
+        .await?)
+    }
+}
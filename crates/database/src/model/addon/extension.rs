@@ -1,2 +1,205 @@
 // Defines What does the addon extends
-// - Custom Widget, New Site Page, New Dashboard Page, New CMS Data Collection
+// - Custom Widget, New Site Page, New Dashboard Page, New CMS Data Collection, Site Plugin
+
+use eyre::Result;
+use local_common::{AddonCompiledId, AddonExtensionId, AddonId};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow, SqliteConnection};
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExtensionKind {
+    Widget,
+    SitePage,
+    DashboardPage,
+    CmsCollection,
+    SitePlugin,
+}
+
+impl ExtensionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Widget => "widget",
+            Self::SitePage => "sitePage",
+            Self::DashboardPage => "dashboardPage",
+            Self::CmsCollection => "cmsCollection",
+            Self::SitePlugin => "sitePlugin",
+        }
+    }
+}
+
+/// How many of each item the addon has.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExtensionUsage {
+    pub widgets: usize,
+    pub site_pages: usize,
+    pub dashboard_pages: usize,
+    pub cms_collections: usize,
+    pub site_plugins: usize,
+}
+
+impl ExtensionUsage {
+    /// Kinds which are in use but not declared in the manifest.
+    pub fn undeclared(&self, extends: &[ExtensionKind]) -> Vec<ExtensionKind> {
+        [
+            (ExtensionKind::Widget, self.widgets),
+            (ExtensionKind::SitePage, self.site_pages),
+            (ExtensionKind::DashboardPage, self.dashboard_pages),
+            (ExtensionKind::CmsCollection, self.cms_collections),
+            (ExtensionKind::SitePlugin, self.site_plugins),
+        ]
+        .into_iter()
+        .filter(|(kind, count)| *count != 0 && !extends.contains(kind))
+        .map(|(kind, _)| kind)
+        .collect()
+    }
+}
+
+pub struct NewAddonExtensionModel {
+    pub addon_id: AddonId,
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub extends: Vec<ExtensionKind>,
+
+    pub dashboard_page_count: Option<i32>,
+    pub cms_collection_count: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonExtensionModel {
+    #[serde(skip)]
+    pub id: AddonExtensionId,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+    #[serde(skip)]
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub extends: Json<Vec<ExtensionKind>>,
+
+    /// Dashboard pages and CMS collections aren't versioned so they're counted when it's published.
+    #[serde(skip)]
+    pub dashboard_page_count: Option<i32>,
+    #[serde(skip)]
+    pub cms_collection_count: Option<i32>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonExtensionModel {
+    pub async fn insert(mut self, db: &mut SqliteConnection) -> Result<AddonExtensionModel> {
+        let now = OffsetDateTime::now_utc();
+
+        dedup_kinds(&mut self.extends);
+
+        let extends = Json(self.extends);
+
+        let res = sqlx::query(
+            "INSERT INTO addon_extension (addon_id, compiled_id, extends, dashboard_page_count, cms_collection_count, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $6)",
+        )
+        .bind(self.addon_id)
+        .bind(self.compiled_id)
+        .bind(&extends)
+        .bind(self.dashboard_page_count)
+        .bind(self.cms_collection_count)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonExtensionModel {
+            id: AddonExtensionId::from(res.last_insert_rowid() as i32),
+            addon_id: self.addon_id,
+            compiled_id: self.compiled_id,
+            extends,
+            dashboard_page_count: self.dashboard_page_count,
+            cms_collection_count: self.cms_collection_count,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonExtensionModel {
+    pub fn declares(&self, kind: ExtensionKind) -> bool {
+        self.extends.contains(&kind)
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        dedup_kinds(&mut self.extends.0);
+
+        let res =
+            sqlx::query("UPDATE addon_extension SET extends = $2, updated_at = $3 WHERE id = $1")
+                .bind(self.id)
+                .bind(&self.extends)
+                .bind(self.updated_at)
+                .execute(db)
+                .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Copies the manifest being edited into the compiled version along with the current usage.
+    pub async fn publish_draft(
+        addon_id: AddonId,
+        compiled_id: AddonCompiledId,
+        usage: ExtensionUsage,
+        db: &mut SqliteConnection,
+    ) -> Result<AddonExtensionModel> {
+        let extends = Self::find_one_draft_by_addon_id(addon_id, db)
+            .await?
+            .map(|v| v.extends.0)
+            .unwrap_or_default();
+
+        NewAddonExtensionModel {
+            addon_id,
+            compiled_id: Some(compiled_id),
+            extends,
+            dashboard_page_count: Some(usage.dashboard_pages as i32),
+            cms_collection_count: Some(usage.cms_collections as i32),
+        }
+        .insert(db)
+        .await
+    }
+
+    pub async fn find_one_draft_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, compiled_id, extends, dashboard_page_count, cms_collection_count, created_at, updated_at FROM addon_extension WHERE addon_id = $1 AND compiled_id IS NULL",
+        )
+        .bind(addon_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_compiled_id(
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, compiled_id, extends, dashboard_page_count, cms_collection_count, created_at, updated_at FROM addon_extension WHERE compiled_id = $1",
+        )
+        .bind(compiled_id)
+        .fetch_optional(db)
+        .await?)
+    }
+}
+
+fn dedup_kinds(kinds: &mut Vec<ExtensionKind>) {
+    let mut seen = Vec::new();
+
+    kinds.retain(|v| {
+        if seen.contains(v) {
+            false
+        } else {
+            seen.push(*v);
+            true
+        }
+    });
+}
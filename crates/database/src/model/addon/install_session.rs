@@ -0,0 +1,126 @@
+//! Multi-step installs where the addon redirects the user to finish the setup on another site.
+
+use eyre::Result;
+use local_common::{
+    generate::{gen_sample_alphanumeric, get_rng_secure},
+    AddonInstanceId,
+};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection};
+use time::{Duration, OffsetDateTime};
+
+/// How long the user has to finish a single install step.
+pub const INSTALL_SESSION_DURATION: Duration = Duration::minutes(30);
+
+pub struct NewAddonInstallSessionModel {
+    pub instance_id: AddonInstanceId,
+
+    pub redirect_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonInstallSessionModel {
+    pub pk: i64,
+
+    pub instance_id: AddonInstanceId,
+
+    #[serde(skip)]
+    pub state: String,
+    pub redirect_url: String,
+    pub step: i32,
+
+    pub expires_at: OffsetDateTime,
+    pub completed_at: Option<OffsetDateTime>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonInstallSessionModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonInstallSessionModel> {
+        let now = OffsetDateTime::now_utc();
+        let state = gen_sample_alphanumeric(48, &mut get_rng_secure());
+        let expires_at = now + INSTALL_SESSION_DURATION;
+
+        let res = sqlx::query(
+            "INSERT INTO addon_install_session (instance_id, state, redirect_url, step, expires_at, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $6)",
+        )
+        .bind(self.instance_id)
+        .bind(&state)
+        .bind(&self.redirect_url)
+        .bind(0)
+        .bind(expires_at)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonInstallSessionModel {
+            pk: res.last_insert_rowid(),
+            instance_id: self.instance_id,
+            state,
+            redirect_url: self.redirect_url,
+            step: 0,
+            expires_at,
+            completed_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonInstallSessionModel {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < OffsetDateTime::now_utc()
+    }
+
+    /// Moves onto the next install step. The expiration is refreshed.
+    pub async fn next_step(
+        &mut self,
+        redirect_url: String,
+        db: &mut SqliteConnection,
+    ) -> Result<u64> {
+        self.step += 1;
+        self.redirect_url = redirect_url;
+        self.expires_at = OffsetDateTime::now_utc() + INSTALL_SESSION_DURATION;
+
+        self.update(db).await
+    }
+
+    pub async fn complete(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.completed_at = Some(OffsetDateTime::now_utc());
+
+        self.update(db).await
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_install_session SET redirect_url = $2, step = $3, expires_at = $4, completed_at = $5, updated_at = $6 WHERE pk = $1",
+        )
+        .bind(self.pk)
+        .bind(&self.redirect_url)
+        .bind(self.step)
+        .bind(self.expires_at)
+        .bind(self.completed_at)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Finds the newest session of the instance which hasn't been completed. It may be expired.
+    pub async fn find_one_pending_by_instance_id(
+        instance_id: AddonInstanceId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT pk, instance_id, state, redirect_url, step, expires_at, completed_at, created_at, updated_at FROM addon_install_session WHERE instance_id = $1 AND completed_at IS NULL ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(instance_id)
+        .fetch_optional(db)
+        .await?)
+    }
+}
@@ -91,11 +91,35 @@ impl AddonInstanceModel {
         Self::delete_by_id(self.id, db).await
     }
 
+    /// Marks the instance as uninstalled.
+    pub async fn soft_delete(
+        &mut self,
+        reason: Option<String>,
+        db: &mut SqliteConnection,
+    ) -> Result<u64> {
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_instance SET delete_reason = $2, deleted_at = $3, updated_at = $3 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&reason)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        self.delete_reason = reason;
+        self.deleted_at = Some(now);
+        self.updated_at = now;
+
+        Ok(res.rows_affected())
+    }
+
     //
 
     pub async fn find_by_uuid(uuid: Uuid, db: &mut SqliteConnection) -> Result<Option<Self>> {
         sqlx::query_as(
-            "SELECT id, public_id, addon_id, website_id, website_uuid, is_setup, settings, version, delete_reason, created_at, updated_at, deleted_at FROM addon_instance WHERE public_id = $1",
+            "SELECT id, public_id, addon_id, website_id, website_uuid, is_setup, settings, version, delete_reason, created_at, updated_at, deleted_at FROM addon_instance WHERE public_id = $1 AND deleted_at IS NULL",
         )
         .bind(uuid)
         .fetch_optional(db)
@@ -120,7 +144,7 @@ impl AddonInstanceModel {
         db: &mut SqliteConnection,
     ) -> Result<Option<Self>> {
         sqlx::query_as(
-            "SELECT id, public_id, addon_id, website_id, website_uuid, is_setup, settings, version, delete_reason, created_at, updated_at, deleted_at FROM addon_instance WHERE addon_id = $1 AND website_uuid = $2",
+            "SELECT id, public_id, addon_id, website_id, website_uuid, is_setup, settings, version, delete_reason, created_at, updated_at, deleted_at FROM addon_instance WHERE addon_id = $1 AND website_uuid = $2 AND deleted_at IS NULL",
         )
         .bind(addon_id)
         .bind(website_id)
@@ -130,13 +154,26 @@ impl AddonInstanceModel {
 
     pub async fn find_by_website_uuid(uuid: Uuid, db: &mut SqliteConnection) -> Result<Vec<Self>> {
         sqlx::query_as(
-            "SELECT id, public_id, addon_id, website_id, website_uuid, is_setup, settings, version, delete_reason, created_at, updated_at, deleted_at FROM addon_instance WHERE website_uuid = $1",
+            "SELECT id, public_id, addon_id, website_id, website_uuid, is_setup, settings, version, delete_reason, created_at, updated_at, deleted_at FROM addon_instance WHERE website_uuid = $1 AND deleted_at IS NULL",
         )
         .bind(uuid)
         .fetch_all(db)
         .await
     }
 
+    /// Instances on real websites, demo sandboxes aren't counted.
+    pub async fn count_active_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<i64> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM addon_instance WHERE addon_id = $1 AND deleted_at IS NULL AND id NOT IN (SELECT instance_id FROM addon_demo_sandbox)",
+        )
+        .bind(addon_id)
+        .fetch_one(db)
+        .await
+    }
+
     pub async fn delete_by_id(id: AddonInstanceId, db: &mut SqliteConnection) -> Result<u64> {
         let res = sqlx::query("DELETE FROM addon_instance WHERE id = $1")
             .bind(id)
@@ -0,0 +1,146 @@
+// The permissions the installer consented to for an instance.
+// Versions which request more permissions can't be upgraded to until the new ones are consented to.
+
+use eyre::Result;
+use local_common::{AddonInstanceGrantId, AddonInstanceId, AddonPermission};
+use serde::Serialize;
+use sqlx::{types::Json, FromRow, SqliteConnection};
+use time::OffsetDateTime;
+
+/// Whether the permission is in the granted set.
+///
+/// Granting a permission also grants everything under it. E.g. `member.info.read` and `member.info.*`
+/// grant `member.info.read.email`.
+pub fn is_permission_granted(granted: &[String], permission: &str) -> bool {
+    let Ok(permission) = permission.parse::<AddonPermission>() else {
+        return false;
+    };
+
+    granted
+        .iter()
+        .filter_map(|v| v.parse::<AddonPermission>().ok())
+        .any(|v| v.grants(&permission))
+}
+
+/// The requested permissions which aren't granted.
+pub fn find_missing_permissions(granted: &[String], requested: &[String]) -> Vec<String> {
+    requested
+        .iter()
+        .filter(|v| !is_permission_granted(granted, v))
+        .cloned()
+        .collect()
+}
+
+pub struct NewAddonInstanceGrantModel {
+    pub instance_id: AddonInstanceId,
+
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonInstanceGrantModel {
+    #[serde(skip)]
+    pub id: AddonInstanceGrantId,
+
+    #[serde(skip)]
+    pub instance_id: AddonInstanceId,
+
+    pub permissions: Json<Vec<String>>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonInstanceGrantModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonInstanceGrantModel> {
+        let now = OffsetDateTime::now_utc();
+        let permissions = Json(self.permissions);
+
+        let res = sqlx::query(
+            "INSERT INTO addon_instance_grant (instance_id, permissions, created_at, updated_at) VALUES ($1, $2, $3, $3)",
+        )
+        .bind(self.instance_id)
+        .bind(&permissions)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonInstanceGrantModel {
+            id: AddonInstanceGrantId::from(res.last_insert_rowid()),
+            instance_id: self.instance_id,
+            permissions,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonInstanceGrantModel {
+    pub fn is_granted(&self, permission: &str) -> bool {
+        is_permission_granted(&self.permissions, permission)
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_instance_grant SET permissions = $2, updated_at = $3 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.permissions)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Replaces the granted permissions of the instance.
+    pub async fn set_for_instance(
+        instance_id: AddonInstanceId,
+        permissions: Vec<String>,
+        db: &mut SqliteConnection,
+    ) -> Result<Self> {
+        match Self::find_one_by_instance_id(instance_id, &mut *db).await? {
+            Some(mut grant) => {
+                grant.permissions.0 = permissions;
+                grant.update(db).await?;
+
+                Ok(grant)
+            }
+
+            None => {
+                NewAddonInstanceGrantModel {
+                    instance_id,
+                    permissions,
+                }
+                .insert(db)
+                .await
+            }
+        }
+    }
+
+    /// The granted permissions of the instance. Empty if it was never granted any.
+    pub async fn find_permissions_by_instance_id(
+        instance_id: AddonInstanceId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<String>> {
+        Ok(Self::find_one_by_instance_id(instance_id, db)
+            .await?
+            .map(|v| v.permissions.0)
+            .unwrap_or_default())
+    }
+
+    pub async fn find_one_by_instance_id(
+        instance_id: AddonInstanceId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, instance_id, permissions, created_at, updated_at FROM addon_instance_grant WHERE instance_id = $1",
+        )
+        .bind(instance_id)
+        .fetch_optional(db)
+        .await?)
+    }
+}
@@ -92,12 +92,24 @@ impl NewAddonMediaModel {
 impl AddonMediaModel {
     pub async fn find_by_addon(addon_id: AddonId, db: &mut SqliteConnection) -> Result<Vec<Self>> {
         sqlx::query_as(
-            "SELECT id, addon_id, type_of, upload_id, embed_url, idx, created_at, deleted_at FROM media_upload WHERE addon_id = $1",
+            "SELECT id, addon_id, type_of, upload_id AS uploader_id, embed_url, idx, created_at, deleted_at FROM addon_media WHERE addon_id = $1 AND deleted_at IS NULL ORDER BY idx, id",
         )
         .bind(addon_id)
         .fetch_all(db)
         .await
     }
+
+    pub async fn find_by_addon_ids(
+        ids: &[AddonId],
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        sqlx::query_as(&format!(
+            "SELECT id, addon_id, type_of, upload_id AS uploader_id, embed_url, idx, created_at, deleted_at FROM addon_media WHERE addon_id IN ({}) AND deleted_at IS NULL ORDER BY idx, id",
+            ids.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+        ))
+        .fetch_all(db)
+        .await
+    }
 }
 
 impl FromRow<'_, SqliteRow> for AddonMediaType {
@@ -5,13 +5,18 @@ mod comment;
 mod compiled_addon;
 mod compiled_page;
 mod compiled_widget;
+mod dashboard_bundle;
 mod dashboard_page;
 mod demo;
 mod extension;
+mod install_session;
 mod instance;
+mod instance_grant;
 mod media;
+mod oauth_token;
 mod permission;
 mod pricing;
+mod site_plugin;
 mod site_template;
 mod site_template_content;
 mod site_widget;
@@ -22,25 +27,30 @@ mod widget_content;
 mod widget_panel;
 
 pub use addon::*;
+pub use automation::*;
+pub use collaborator::*;
+pub use comment::*;
 pub use compiled_addon::*;
 pub use compiled_page::*;
 pub use compiled_widget::*;
+pub use dashboard_bundle::*;
 pub use dashboard_page::*;
+pub use demo::*;
+pub use extension::*;
+pub use install_session::*;
 pub use instance::*;
+pub use instance_grant::*;
 pub use media::*;
+pub use oauth_token::*;
 pub use permission::*;
+pub use pricing::*;
+pub use site_plugin::*;
 pub use site_template::*;
 pub use site_template_content::*;
 pub use site_widget::*;
+pub use tag::*;
+pub use webhook::*;
 pub use website_widget_settings::*;
 pub use widget_content::*;
 pub use widget_panel::*;
 
-// pub use automation::*;
-// pub use collaborator::*;
-// pub use comment::*;
-// pub use demo::*;
-// pub use extension::*;
-// pub use pricing::*;
-// pub use tag::*;
-// pub use webhook::*;
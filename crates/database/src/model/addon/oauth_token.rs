@@ -0,0 +1,290 @@
+// Lets the addon's server call back into the API for one of its' instances.
+// The server exchanges its' client secret for an access token scoped to the instance. The token
+// can only be used for the permissions granted to the instance.
+
+use eyre::Result;
+use local_common::{
+    generate::{gen_sample_alphanumeric, get_rng_secure, hash_secret},
+    AddonId, AddonInstanceId, AddonOAuthClientId, AddonOAuthTokenId,
+};
+use serde::Serialize;
+use sqlx::{types::Json, FromRow, SqliteConnection};
+use time::{Duration, OffsetDateTime};
+
+use super::is_permission_granted;
+
+/// How long an access token can be used for.
+pub const ACCESS_TOKEN_LIFETIME: Duration = Duration::hours(1);
+/// How long a refresh token can be exchanged for a new access token.
+pub const REFRESH_TOKEN_LIFETIME: Duration = Duration::days(30);
+
+pub struct NewAddonOAuthClientModel {
+    pub addon_id: AddonId,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonOAuthClientModel {
+    #[serde(skip)]
+    pub id: AddonOAuthClientId,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+
+    #[serde(skip)]
+    pub secret_hash: String,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonOAuthClientModel {
+    /// Returns the client along with its' secret. The secret can't be retrieved afterwards.
+    pub async fn insert(
+        self,
+        db: &mut SqliteConnection,
+    ) -> Result<(AddonOAuthClientModel, String)> {
+        let now = OffsetDateTime::now_utc();
+        let secret = gen_client_secret();
+        let secret_hash = hash_secret(&secret);
+
+        let res = sqlx::query(
+            "INSERT INTO addon_oauth_client (addon_id, secret_hash, created_at, updated_at) VALUES ($1, $2, $3, $3)",
+        )
+        .bind(self.addon_id)
+        .bind(&secret_hash)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok((
+            AddonOAuthClientModel {
+                id: AddonOAuthClientId::from(res.last_insert_rowid() as i32),
+                addon_id: self.addon_id,
+                secret_hash,
+                created_at: now,
+                updated_at: now,
+            },
+            secret,
+        ))
+    }
+}
+
+impl AddonOAuthClientModel {
+    pub fn is_secret(&self, secret: &str) -> bool {
+        self.secret_hash == hash_secret(secret)
+    }
+
+    /// Returns the new secret.
+    pub fn regenerate_secret(&mut self) -> String {
+        let secret = gen_client_secret();
+        self.secret_hash = hash_secret(&secret);
+        secret
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_oauth_client SET secret_hash = $2, updated_at = $3 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.secret_hash)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn delete(self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM addon_oauth_client WHERE id = $1")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, secret_hash, created_at, updated_at FROM addon_oauth_client WHERE addon_id = $1",
+        )
+        .bind(addon_id)
+        .fetch_optional(db)
+        .await?)
+    }
+}
+
+pub struct NewAddonOAuthTokenModel {
+    pub addon_id: AddonId,
+    pub instance_id: AddonInstanceId,
+
+    pub scopes: Vec<String>,
+}
+
+/// The unhashed tokens. Only available when they're issued.
+#[derive(Debug, Clone)]
+pub struct IssuedOAuthToken {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AddonOAuthTokenModel {
+    pub id: AddonOAuthTokenId,
+
+    pub addon_id: AddonId,
+    pub instance_id: AddonInstanceId,
+
+    pub access_hash: String,
+    pub refresh_hash: String,
+
+    pub scopes: Json<Vec<String>>,
+
+    pub access_expires_at: OffsetDateTime,
+    pub refresh_expires_at: OffsetDateTime,
+    pub revoked_at: Option<OffsetDateTime>,
+
+    pub created_at: OffsetDateTime,
+}
+
+impl NewAddonOAuthTokenModel {
+    pub async fn insert(
+        self,
+        db: &mut SqliteConnection,
+    ) -> Result<(AddonOAuthTokenModel, IssuedOAuthToken)> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut rng = get_rng_secure();
+
+        let issued = IssuedOAuthToken {
+            access_token: format!("wat_{}", gen_sample_alphanumeric(48, &mut rng)),
+            refresh_token: format!("wrt_{}", gen_sample_alphanumeric(48, &mut rng)),
+        };
+
+        let access_hash = hash_secret(&issued.access_token);
+        let refresh_hash = hash_secret(&issued.refresh_token);
+        let scopes = Json(self.scopes);
+        let access_expires_at = now + ACCESS_TOKEN_LIFETIME;
+        let refresh_expires_at = now + REFRESH_TOKEN_LIFETIME;
+
+        let res = sqlx::query(
+            "INSERT INTO addon_oauth_token (addon_id, instance_id, access_hash, refresh_hash, scopes, access_expires_at, refresh_expires_at, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(self.addon_id)
+        .bind(self.instance_id)
+        .bind(&access_hash)
+        .bind(&refresh_hash)
+        .bind(&scopes)
+        .bind(access_expires_at)
+        .bind(refresh_expires_at)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok((
+            AddonOAuthTokenModel {
+                id: AddonOAuthTokenId::from(res.last_insert_rowid()),
+                addon_id: self.addon_id,
+                instance_id: self.instance_id,
+                access_hash,
+                refresh_hash,
+                scopes,
+                access_expires_at,
+                refresh_expires_at,
+                revoked_at: None,
+                created_at: now,
+            },
+            issued,
+        ))
+    }
+}
+
+impl AddonOAuthTokenModel {
+    pub fn is_access_valid(&self) -> bool {
+        self.revoked_at.is_none() && self.access_expires_at > OffsetDateTime::now_utc()
+    }
+
+    pub fn is_refresh_valid(&self) -> bool {
+        self.revoked_at.is_none() && self.refresh_expires_at > OffsetDateTime::now_utc()
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        is_permission_granted(&self.scopes, scope)
+    }
+
+    pub async fn revoke(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_oauth_token SET revoked_at = $2 WHERE id = $1 AND revoked_at IS NULL",
+        )
+        .bind(self.id)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        self.revoked_at.get_or_insert(now);
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn revoke_by_instance_id(
+        instance_id: AddonInstanceId,
+        db: &mut SqliteConnection,
+    ) -> Result<u64> {
+        let res = sqlx::query(
+            "UPDATE addon_oauth_token SET revoked_at = $2 WHERE instance_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(instance_id)
+        .bind(OffsetDateTime::now_utc())
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn revoke_by_addon_id(addon_id: AddonId, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query(
+            "UPDATE addon_oauth_token SET revoked_at = $2 WHERE addon_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(addon_id)
+        .bind(OffsetDateTime::now_utc())
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_access_token(
+        token: &str,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, instance_id, access_hash, refresh_hash, scopes, access_expires_at, refresh_expires_at, revoked_at, created_at FROM addon_oauth_token WHERE access_hash = $1",
+        )
+        .bind(hash_secret(token))
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_refresh_token(
+        token: &str,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, instance_id, access_hash, refresh_hash, scopes, access_expires_at, refresh_expires_at, revoked_at, created_at FROM addon_oauth_token WHERE refresh_hash = $1",
+        )
+        .bind(hash_secret(token))
+        .fetch_optional(db)
+        .await?)
+    }
+}
+
+fn gen_client_secret() -> String {
+    format!("wcs_{}", gen_sample_alphanumeric(48, &mut get_rng_secure()))
+}
@@ -22,7 +22,14 @@ pub struct AddonPermissionModel {
 }
 
 impl AddonPermissionModel {
-    // pub fn try_from(addon_id: AddonId, permission: String) {}
+    /// Parses the permission. Errors unless it's a known permission.
+    pub fn try_from(addon_id: AddonId, permission: &str) -> eyre::Result<Self> {
+        let perm = permission.parse::<AddonPermission>()?;
+
+        perm.validate()?;
+
+        Ok(Self { addon_id, perm })
+    }
 
     pub async fn insert(&self, db: &mut SqliteConnection) -> Result<()> {
         sqlx::query(
@@ -48,6 +55,18 @@ impl AddonPermissionModel {
         .await
     }
 
+    pub async fn find_by_addon_ids(
+        ids: &[AddonId],
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        sqlx::query_as(&format!(
+            "SELECT addon_id, scope, category, operation, info FROM addon_permission WHERE addon_id IN ({})",
+            ids.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+        ))
+        .fetch_all(db)
+        .await
+    }
+
     pub async fn find_by_scope_addon_id(
         id: AddonId,
         scope: &str,
@@ -62,6 +81,23 @@ impl AddonPermissionModel {
         .await
     }
 
+    /// Replaces the permissions the addon requests.
+    ///
+    /// Should be called inside of a transaction.
+    pub async fn set_for_addon(
+        id: AddonId,
+        perms: &[Self],
+        db: &mut SqliteConnection,
+    ) -> Result<()> {
+        Self::delete_by_addon_id(id, &mut *db).await?;
+
+        for perm in perms {
+            perm.insert(&mut *db).await?;
+        }
+
+        Ok(())
+    }
+
     pub async fn delete_by_addon_id(id: AddonId, db: &mut SqliteConnection) -> Result<u64> {
         let res = sqlx::query("DELETE FROM addon_permission WHERE addon_id = $1")
             .bind(id)
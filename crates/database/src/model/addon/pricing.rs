@@ -1 +1,431 @@
+// Pricing plans of an addon and the subscription of each instance.
 
+use eyre::Result;
+use local_common::{AddonId, AddonInstanceId, AddonPricingPlanId, AddonSubscriptionId};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    database::{HasArguments, HasValueRef},
+    encode::IsNull,
+    error::BoxDynError,
+    sqlite::SqliteTypeInfo,
+    Decode, Encode, FromRow, Sqlite, SqliteConnection, Type,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::AddonInstanceModel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PricingType {
+    Free,
+    OneTime,
+    Monthly,
+    Yearly,
+}
+
+impl PricingType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Free => "free",
+            Self::OneTime => "oneTime",
+            Self::Monthly => "monthly",
+            Self::Yearly => "yearly",
+        }
+    }
+}
+
+impl Encode<'_, Sqlite> for PricingType {
+    fn encode_by_ref(&self, buf: &mut <Sqlite as HasArguments<'_>>::ArgumentBuffer) -> IsNull {
+        Encode::<Sqlite>::encode_by_ref(&String::from(self.as_str()), buf)
+    }
+}
+
+impl Decode<'_, Sqlite> for PricingType {
+    fn decode(value: <Sqlite as HasValueRef<'_>>::ValueRef) -> Result<Self, BoxDynError> {
+        Ok(match <String as Decode<Sqlite>>::decode(value)?.as_str() {
+            "free" => Self::Free,
+            "oneTime" => Self::OneTime,
+            "monthly" => Self::Monthly,
+            "yearly" => Self::Yearly,
+            v => return Err(format!("Unknown Pricing Type: {v}").into()),
+        })
+    }
+}
+
+impl Type<Sqlite> for PricingType {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionStatus {
+    Trialing,
+    Active,
+    PastDue,
+    Cancelled,
+}
+
+impl SubscriptionStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Trialing => "trialing",
+            Self::Active => "active",
+            Self::PastDue => "pastDue",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl Encode<'_, Sqlite> for SubscriptionStatus {
+    fn encode_by_ref(&self, buf: &mut <Sqlite as HasArguments<'_>>::ArgumentBuffer) -> IsNull {
+        Encode::<Sqlite>::encode_by_ref(&String::from(self.as_str()), buf)
+    }
+}
+
+impl Decode<'_, Sqlite> for SubscriptionStatus {
+    fn decode(value: <Sqlite as HasValueRef<'_>>::ValueRef) -> Result<Self, BoxDynError> {
+        Ok(match <String as Decode<Sqlite>>::decode(value)?.as_str() {
+            "trialing" => Self::Trialing,
+            "active" => Self::Active,
+            "pastDue" => Self::PastDue,
+            "cancelled" => Self::Cancelled,
+            v => return Err(format!("Unknown Subscription Status: {v}").into()),
+        })
+    }
+}
+
+impl Type<Sqlite> for SubscriptionStatus {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+}
+
+// Plan
+
+pub struct NewAddonPricingPlanModel {
+    pub addon_id: AddonId,
+
+    pub name: String,
+
+    pub type_of: PricingType,
+    pub price: i64,
+    pub currency: String,
+    pub is_per_seat: bool,
+    pub trial_days: i32,
+
+    pub provider_price_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonPricingPlanModel {
+    #[serde(skip)]
+    pub id: AddonPricingPlanId,
+    #[serde(rename = "id")]
+    pub public_id: Uuid,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+
+    pub name: String,
+
+    #[serde(rename = "type")]
+    #[sqlx(rename = "type")]
+    pub type_of: PricingType,
+    pub price: i64,
+    pub currency: String,
+    pub is_per_seat: bool,
+    pub trial_days: i32,
+
+    #[serde(skip)]
+    pub provider_price_id: Option<String>,
+
+    pub is_active: bool,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonPricingPlanModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonPricingPlanModel> {
+        let now = OffsetDateTime::now_utc();
+        let public_id = Uuid::now_v7();
+
+        let res = sqlx::query(
+            "INSERT INTO addon_pricing_plan (public_id, addon_id, name, type, price, currency, is_per_seat, trial_days, provider_price_id, is_active, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, TRUE, $10, $10)",
+        )
+        .bind(public_id)
+        .bind(self.addon_id)
+        .bind(&self.name)
+        .bind(self.type_of)
+        .bind(self.price)
+        .bind(&self.currency)
+        .bind(self.is_per_seat)
+        .bind(self.trial_days)
+        .bind(&self.provider_price_id)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonPricingPlanModel {
+            id: AddonPricingPlanId::from(res.last_insert_rowid() as i32),
+            public_id,
+            addon_id: self.addon_id,
+            name: self.name,
+            type_of: self.type_of,
+            price: self.price,
+            currency: self.currency,
+            is_per_seat: self.is_per_seat,
+            trial_days: self.trial_days,
+            provider_price_id: self.provider_price_id,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonPricingPlanModel {
+    /// Free plans don't go through the billing provider.
+    pub fn is_free(&self) -> bool {
+        self.type_of == PricingType::Free || self.price == 0
+    }
+
+    pub fn is_recurring(&self) -> bool {
+        matches!(self.type_of, PricingType::Monthly | PricingType::Yearly)
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_pricing_plan SET name = $2, price = $3, currency = $4, is_per_seat = $5, trial_days = $6, provider_price_id = $7, is_active = $8, updated_at = $9 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.name)
+        .bind(self.price)
+        .bind(&self.currency)
+        .bind(self.is_per_seat)
+        .bind(self.trial_days)
+        .bind(&self.provider_price_id)
+        .bind(self.is_active)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_id(
+        id: AddonPricingPlanId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, name, type, price, currency, is_per_seat, trial_days, provider_price_id, is_active, created_at, updated_at FROM addon_pricing_plan WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_public_id(
+        addon_id: AddonId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, name, type, price, currency, is_per_seat, trial_days, provider_price_id, is_active, created_at, updated_at FROM addon_pricing_plan WHERE addon_id = $1 AND public_id = $2",
+        )
+        .bind(addon_id)
+        .bind(public_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, name, type, price, currency, is_per_seat, trial_days, provider_price_id, is_active, created_at, updated_at FROM addon_pricing_plan WHERE addon_id = $1 ORDER BY price ASC",
+        )
+        .bind(addon_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn find_active_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, name, type, price, currency, is_per_seat, trial_days, provider_price_id, is_active, created_at, updated_at FROM addon_pricing_plan WHERE addon_id = $1 AND is_active = TRUE ORDER BY price ASC",
+        )
+        .bind(addon_id)
+        .fetch_all(db)
+        .await?)
+    }
+}
+
+// Subscription
+
+pub struct NewAddonSubscriptionModel {
+    pub instance_id: AddonInstanceId,
+    pub plan_id: AddonPricingPlanId,
+
+    pub status: SubscriptionStatus,
+    pub seats: i32,
+
+    pub provider_subscription_id: Option<String>,
+
+    pub trial_ends_at: Option<OffsetDateTime>,
+    pub current_period_end: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonSubscriptionModel {
+    #[serde(skip)]
+    pub id: AddonSubscriptionId,
+
+    #[serde(skip)]
+    pub instance_id: AddonInstanceId,
+    #[serde(skip)]
+    pub plan_id: AddonPricingPlanId,
+
+    pub status: SubscriptionStatus,
+    pub seats: i32,
+
+    #[serde(skip)]
+    pub provider_subscription_id: Option<String>,
+
+    pub trial_ends_at: Option<OffsetDateTime>,
+    pub current_period_end: Option<OffsetDateTime>,
+    pub cancelled_at: Option<OffsetDateTime>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonSubscriptionModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonSubscriptionModel> {
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "INSERT INTO addon_subscription (instance_id, plan_id, status, seats, provider_subscription_id, trial_ends_at, current_period_end, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)",
+        )
+        .bind(self.instance_id)
+        .bind(self.plan_id)
+        .bind(self.status)
+        .bind(self.seats)
+        .bind(&self.provider_subscription_id)
+        .bind(self.trial_ends_at)
+        .bind(self.current_period_end)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonSubscriptionModel {
+            id: AddonSubscriptionId::from(res.last_insert_rowid() as i32),
+            instance_id: self.instance_id,
+            plan_id: self.plan_id,
+            status: self.status,
+            seats: self.seats,
+            provider_subscription_id: self.provider_subscription_id,
+            trial_ends_at: self.trial_ends_at,
+            current_period_end: self.current_period_end,
+            cancelled_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonSubscriptionModel {
+    /// Whether the instance is allowed to be used.
+    ///
+    /// A period which ended counts as lapsed until the subscription is synced with the provider again.
+    pub fn is_usable(&self) -> bool {
+        let now = OffsetDateTime::now_utc();
+
+        let is_in_period = self.current_period_end.map_or(true, |v| v > now);
+
+        match self.status {
+            SubscriptionStatus::Active => is_in_period,
+            SubscriptionStatus::Trialing => {
+                is_in_period && self.trial_ends_at.map_or(true, |v| v > now)
+            }
+            SubscriptionStatus::PastDue | SubscriptionStatus::Cancelled => false,
+        }
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_subscription SET plan_id = $2, status = $3, seats = $4, provider_subscription_id = $5, trial_ends_at = $6, current_period_end = $7, cancelled_at = $8, updated_at = $9 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(self.plan_id)
+        .bind(self.status)
+        .bind(self.seats)
+        .bind(&self.provider_subscription_id)
+        .bind(self.trial_ends_at)
+        .bind(self.current_period_end)
+        .bind(self.cancelled_at)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Subscriptions still billed through the provider whose period has ended. Least recently synced first.
+    pub async fn find_lapsed(limit: i64, db: &mut SqliteConnection) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, instance_id, plan_id, status, seats, provider_subscription_id, trial_ends_at, current_period_end, cancelled_at, created_at, updated_at FROM addon_subscription WHERE provider_subscription_id IS NOT NULL AND status != 'cancelled' AND current_period_end <= $1 ORDER BY updated_at ASC LIMIT $2",
+        )
+        .bind(OffsetDateTime::now_utc())
+        .bind(limit)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_instance_id(
+        instance_id: AddonInstanceId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, instance_id, plan_id, status, seats, provider_subscription_id, trial_ends_at, current_period_end, cancelled_at, created_at, updated_at FROM addon_subscription WHERE instance_id = $1",
+        )
+        .bind(instance_id)
+        .fetch_optional(db)
+        .await?)
+    }
+}
+
+impl AddonInstanceModel {
+    pub async fn find_subscription(
+        &self,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<AddonSubscriptionModel>> {
+        AddonSubscriptionModel::find_one_by_instance_id(self.id, db).await
+    }
+
+    /// Instances without a subscription are only usable while the addon has no paid plans.
+    pub async fn is_usable(&self, db: &mut SqliteConnection) -> Result<bool> {
+        if let Some(sub) = self.find_subscription(db).await? {
+            return Ok(sub.is_usable());
+        }
+
+        Ok(
+            AddonPricingPlanModel::find_active_by_addon_id(self.addon_id, db)
+                .await?
+                .iter()
+                .all(|v| v.is_free()),
+        )
+    }
+}
@@ -0,0 +1,222 @@
+// Site Plugins
+// Scripts and styles injected into every page of the websites the addon is installed on.
+// E.g. analytics, chat widgets, fonts.
+
+use eyre::Result;
+use local_common::{AddonCompiledId, AddonId, AddonSitePluginId};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow, SqliteConnection};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SitePluginPlacement {
+    Head,
+    /// Before the closing body tag
+    BodyEnd,
+}
+
+/// How the script is loaded. Scripts without one block rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScriptStrategy {
+    Async,
+    Defer,
+    Module,
+}
+
+/// Either `src`/`href` or `inline` is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SitePluginContent {
+    Script {
+        #[serde(default)]
+        src: Option<String>,
+        #[serde(default)]
+        inline: Option<String>,
+        #[serde(default)]
+        strategy: Option<ScriptStrategy>,
+    },
+
+    Style {
+        #[serde(default)]
+        href: Option<String>,
+        #[serde(default)]
+        inline: Option<String>,
+    },
+}
+
+pub struct NewAddonSitePluginModel {
+    pub addon_id: AddonId,
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub name: String,
+
+    pub placement: SitePluginPlacement,
+    pub content: SitePluginContent,
+    pub sort_order: i32,
+
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonSitePluginModel {
+    #[serde(skip)]
+    pub id: AddonSitePluginId,
+    #[serde(rename = "id")]
+    pub public_id: Uuid,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+    #[serde(skip)]
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub name: String,
+
+    pub placement: Json<SitePluginPlacement>,
+    pub content: Json<SitePluginContent>,
+    pub sort_order: i32,
+
+    pub is_enabled: bool,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonSitePluginModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonSitePluginModel> {
+        let now = OffsetDateTime::now_utc();
+        let public_id = Uuid::now_v7();
+
+        let placement = Json(self.placement);
+        let content = Json(self.content);
+
+        let res = sqlx::query(
+            "INSERT INTO addon_site_plugin (public_id, addon_id, compiled_id, name, placement, content, sort_order, is_enabled, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $9)",
+        )
+        .bind(public_id)
+        .bind(self.addon_id)
+        .bind(self.compiled_id)
+        .bind(&self.name)
+        .bind(&placement)
+        .bind(&content)
+        .bind(self.sort_order)
+        .bind(self.is_enabled)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonSitePluginModel {
+            id: AddonSitePluginId::from(res.last_insert_rowid() as i32),
+            public_id,
+            addon_id: self.addon_id,
+            compiled_id: self.compiled_id,
+            name: self.name,
+            placement,
+            content,
+            sort_order: self.sort_order,
+            is_enabled: self.is_enabled,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonSitePluginModel {
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_site_plugin SET name = $2, placement = $3, content = $4, sort_order = $5, is_enabled = $6, updated_at = $7 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.name)
+        .bind(&self.placement)
+        .bind(&self.content)
+        .bind(self.sort_order)
+        .bind(self.is_enabled)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn delete(self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM addon_site_plugin WHERE id = $1")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Copies every draft into the compiled version.
+    pub async fn publish_drafts(
+        addon_id: AddonId,
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        let drafts = Self::find_drafts_by_addon_id(addon_id, db).await?;
+
+        let mut items = Vec::new();
+
+        for draft in drafts {
+            items.push(
+                NewAddonSitePluginModel {
+                    addon_id,
+                    compiled_id: Some(compiled_id),
+                    name: draft.name,
+                    placement: draft.placement.0,
+                    content: draft.content.0,
+                    sort_order: draft.sort_order,
+                    is_enabled: draft.is_enabled,
+                }
+                .insert(db)
+                .await?,
+            );
+        }
+
+        Ok(items)
+    }
+
+    pub async fn find_one_draft_by_public_id(
+        addon_id: AddonId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, compiled_id, name, placement, content, sort_order, is_enabled, created_at, updated_at FROM addon_site_plugin WHERE addon_id = $1 AND public_id = $2 AND compiled_id IS NULL",
+        )
+        .bind(addon_id)
+        .bind(public_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_drafts_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, compiled_id, name, placement, content, sort_order, is_enabled, created_at, updated_at FROM addon_site_plugin WHERE addon_id = $1 AND compiled_id IS NULL ORDER BY sort_order ASC, id ASC",
+        )
+        .bind(addon_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn find_by_compiled_id(
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, addon_id, compiled_id, name, placement, content, sort_order, is_enabled, created_at, updated_at FROM addon_site_plugin WHERE compiled_id = $1 ORDER BY sort_order ASC, id ASC",
+        )
+        .bind(compiled_id)
+        .fetch_all(db)
+        .await?)
+    }
+}
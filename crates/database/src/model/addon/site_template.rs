@@ -151,6 +151,21 @@ impl AddonTemplatePageModel {
         )
     }
 
+    pub async fn count_by_addon_ids(
+        ids: &[AddonId],
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<(AddonId, i32)>> {
+        Ok(sqlx::query_as(&format!(
+            "SELECT addon_id, COUNT(*) FROM template_page WHERE addon_id IN ({}) GROUP BY addon_id",
+            ids.iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        ))
+        .fetch_all(db)
+        .await?)
+    }
+
     pub async fn find_by_public_id(
         public_id: Uuid,
         db: &mut SqliteConnection,
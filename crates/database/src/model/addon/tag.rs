@@ -1 +1,73 @@
+// Tags and categories of an addon.
 
+use eyre::Result;
+use local_common::{AddonId, TagId};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection};
+
+use crate::TagModel;
+
+use super::AddonSearch;
+
+/// How many addons of the search have the tag.
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TagFacet {
+    pub slug: String,
+    pub name: String,
+    pub is_category: bool,
+    pub count: i64,
+}
+
+pub struct AddonTagModel;
+
+impl AddonTagModel {
+    /// Replaces the tags of the addon.
+    pub async fn set_for_addon(
+        addon_id: AddonId,
+        tag_ids: &[TagId],
+        db: &mut SqliteConnection,
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM addon_tag WHERE addon_id = $1")
+            .bind(addon_id)
+            .execute(&mut *db)
+            .await?;
+
+        for tag_id in tag_ids {
+            sqlx::query("INSERT OR IGNORE INTO addon_tag (addon_id, tag_id) VALUES ($1, $2)")
+                .bind(addon_id)
+                .bind(tag_id)
+                .execute(&mut *db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn find_tags_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<TagModel>> {
+        Ok(sqlx::query_as(
+            "SELECT tag.id, tag.slug, tag.name, tag.is_category, tag.created_at FROM tag INNER JOIN addon_tag ON addon_tag.tag_id = tag.id WHERE addon_tag.addon_id = $1 ORDER BY tag.is_category DESC, tag.name",
+        )
+        .bind(addon_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    /// Tag counts over every addon the search matches, ignoring pagination.
+    pub async fn count_facets(
+        search: &AddonSearch,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<TagFacet>> {
+        let filter = search.filter();
+
+        let sql = format!(
+            "SELECT tag.slug, tag.name, tag.is_category, COUNT(*) AS count FROM addon_tag INNER JOIN tag ON tag.id = addon_tag.tag_id INNER JOIN addon ON addon.id = addon_tag.addon_id WHERE {} GROUP BY tag.id ORDER BY count DESC, tag.name",
+            filter.sql
+        );
+
+        Ok(filter.bind(sqlx::query_as(&sql)).fetch_all(db).await?)
+    }
+}
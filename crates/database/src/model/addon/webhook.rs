@@ -1 +1,354 @@
 // Subscribe to events and track webhooks for 30 days in the log.
+
+use eyre::Result;
+use local_common::{
+    generate::{gen_sample_alphanumeric, get_rng_secure},
+    AddonId, AddonWebhookDeliveryId, AddonWebhookId,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    database::{HasArguments, HasValueRef},
+    encode::IsNull,
+    error::BoxDynError,
+    sqlite::SqliteTypeInfo,
+    types::Json,
+    Decode, Encode, FromRow, Sqlite, SqliteConnection, Type,
+};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+/// How long deliveries are kept in the log.
+pub const WEBHOOK_DELIVERY_RETENTION: Duration = Duration::days(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "instance.installed")]
+    InstanceInstalled,
+    #[serde(rename = "instance.uninstalled")]
+    InstanceUninstalled,
+    #[serde(rename = "addon.published")]
+    AddonPublished,
+    #[serde(rename = "cms.row.created")]
+    CmsRowCreated,
+    #[serde(rename = "cms.row.updated")]
+    CmsRowUpdated,
+    #[serde(rename = "cms.row.deleted")]
+    CmsRowDeleted,
+    #[serde(rename = "widget.settings.changed")]
+    WidgetSettingsChanged,
+    #[serde(rename = "automation.triggered")]
+    AutomationTriggered,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::InstanceInstalled => "instance.installed",
+            Self::InstanceUninstalled => "instance.uninstalled",
+            Self::AddonPublished => "addon.published",
+            Self::CmsRowCreated => "cms.row.created",
+            Self::CmsRowUpdated => "cms.row.updated",
+            Self::CmsRowDeleted => "cms.row.deleted",
+            Self::WidgetSettingsChanged => "widget.settings.changed",
+            Self::AutomationTriggered => "automation.triggered",
+        }
+    }
+}
+
+impl Encode<'_, Sqlite> for WebhookEvent {
+    fn encode_by_ref(&self, buf: &mut <Sqlite as HasArguments<'_>>::ArgumentBuffer) -> IsNull {
+        Encode::<Sqlite>::encode_by_ref(&String::from(self.as_str()), buf)
+    }
+}
+
+impl Decode<'_, Sqlite> for WebhookEvent {
+    fn decode(value: <Sqlite as HasValueRef<'_>>::ValueRef) -> Result<Self, BoxDynError> {
+        Ok(match <String as Decode<Sqlite>>::decode(value)?.as_str() {
+            "instance.installed" => Self::InstanceInstalled,
+            "instance.uninstalled" => Self::InstanceUninstalled,
+            "addon.published" => Self::AddonPublished,
+            "cms.row.created" => Self::CmsRowCreated,
+            "cms.row.updated" => Self::CmsRowUpdated,
+            "cms.row.deleted" => Self::CmsRowDeleted,
+            "widget.settings.changed" => Self::WidgetSettingsChanged,
+            "automation.triggered" => Self::AutomationTriggered,
+            v => return Err(format!("Unknown Webhook Event: {v}").into()),
+        })
+    }
+}
+
+impl Type<Sqlite> for WebhookEvent {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+}
+
+// Webhook
+
+pub struct NewAddonWebhookModel {
+    pub addon_id: AddonId,
+
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonWebhookModel {
+    pub id: AddonWebhookId,
+
+    pub addon_id: AddonId,
+
+    pub url: String,
+    pub events: Json<Vec<WebhookEvent>>,
+    pub secret: String,
+
+    pub is_active: bool,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonWebhookModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonWebhookModel> {
+        let now = OffsetDateTime::now_utc();
+        let secret = gen_webhook_secret();
+        let events = Json(self.events);
+
+        let res = sqlx::query(
+            "INSERT INTO addon_webhook (addon_id, url, events, secret, is_active, created_at, updated_at) VALUES ($1, $2, $3, $4, TRUE, $5, $5)",
+        )
+        .bind(self.addon_id)
+        .bind(&self.url)
+        .bind(&events)
+        .bind(&secret)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonWebhookModel {
+            id: AddonWebhookId::from(res.last_insert_rowid() as i32),
+            addon_id: self.addon_id,
+            url: self.url,
+            events,
+            secret,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonWebhookModel {
+    pub fn is_subscribed(&self, event: WebhookEvent) -> bool {
+        self.is_active && self.events.contains(&event)
+    }
+
+    pub fn regenerate_secret(&mut self) {
+        self.secret = gen_webhook_secret();
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_webhook SET url = $2, events = $3, secret = $4, is_active = $5, updated_at = $6 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.url)
+        .bind(&self.events)
+        .bind(&self.secret)
+        .bind(self.is_active)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, url, events, secret, is_active, created_at, updated_at FROM addon_webhook WHERE addon_id = $1",
+        )
+        .bind(addon_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_id(
+        id: AddonWebhookId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, url, events, secret, is_active, created_at, updated_at FROM addon_webhook WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(db)
+        .await?)
+    }
+}
+
+fn gen_webhook_secret() -> String {
+    format!(
+        "whsec_{}",
+        gen_sample_alphanumeric(40, &mut get_rng_secure())
+    )
+}
+
+// Delivery
+
+pub struct NewAddonWebhookDeliveryModel {
+    pub webhook_id: AddonWebhookId,
+    pub addon_id: AddonId,
+
+    pub event: WebhookEvent,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonWebhookDeliveryModel {
+    #[serde(skip)]
+    pub id: AddonWebhookDeliveryId,
+    #[serde(rename = "id")]
+    pub public_id: Uuid,
+
+    #[serde(skip)]
+    pub webhook_id: AddonWebhookId,
+    #[serde(skip)]
+    pub addon_id: AddonId,
+
+    pub event: WebhookEvent,
+    pub payload: Json<serde_json::Value>,
+
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+
+    pub next_attempt_at: Option<OffsetDateTime>,
+    pub delivered_at: Option<OffsetDateTime>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonWebhookDeliveryModel {
+    /// Queues the delivery to be sent as soon as possible.
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonWebhookDeliveryModel> {
+        let now = OffsetDateTime::now_utc();
+        let public_id = Uuid::now_v7();
+        let payload = Json(self.payload);
+
+        let res = sqlx::query(
+            "INSERT INTO addon_webhook_delivery (public_id, webhook_id, addon_id, event, payload, attempts, next_attempt_at, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, 0, $6, $6, $6)",
+        )
+        .bind(public_id)
+        .bind(self.webhook_id)
+        .bind(self.addon_id)
+        .bind(self.event)
+        .bind(&payload)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonWebhookDeliveryModel {
+            id: AddonWebhookDeliveryId::from(res.last_insert_rowid()),
+            public_id,
+            webhook_id: self.webhook_id,
+            addon_id: self.addon_id,
+            event: self.event,
+            payload,
+            attempts: 0,
+            response_status: None,
+            response_body: None,
+            next_attempt_at: Some(now),
+            delivered_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonWebhookDeliveryModel {
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_webhook_delivery SET attempts = $2, response_status = $3, response_body = $4, next_attempt_at = $5, delivered_at = $6, updated_at = $7 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(self.attempts)
+        .bind(self.response_status)
+        .bind(&self.response_body)
+        .bind(self.next_attempt_at)
+        .bind(self.delivered_at)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_one_by_public_id(
+        addon_id: AddonId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, webhook_id, addon_id, event, payload, attempts, response_status, response_body, next_attempt_at, delivered_at, created_at, updated_at FROM addon_webhook_delivery WHERE addon_id = $1 AND public_id = $2",
+        )
+        .bind(addon_id)
+        .bind(public_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_by_addon_id(
+        addon_id: AddonId,
+        offset: i64,
+        limit: i64,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, webhook_id, addon_id, event, payload, attempts, response_status, response_body, next_attempt_at, delivered_at, created_at, updated_at FROM addon_webhook_delivery WHERE addon_id = $1 ORDER BY id DESC LIMIT $2 OFFSET $3",
+        )
+        .bind(addon_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn count_by_addon_id(addon_id: AddonId, db: &mut SqliteConnection) -> Result<i64> {
+        Ok(
+            sqlx::query_scalar("SELECT COUNT(*) FROM addon_webhook_delivery WHERE addon_id = $1")
+                .bind(addon_id)
+                .fetch_one(db)
+                .await?,
+        )
+    }
+
+    /// Deliveries which are waiting on their next attempt.
+    pub async fn find_due(limit: i64, db: &mut SqliteConnection) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, public_id, webhook_id, addon_id, event, payload, attempts, response_status, response_body, next_attempt_at, delivered_at, created_at, updated_at FROM addon_webhook_delivery WHERE next_attempt_at IS NOT NULL AND next_attempt_at <= $1 ORDER BY next_attempt_at ASC LIMIT $2",
+        )
+        .bind(OffsetDateTime::now_utc())
+        .bind(limit)
+        .fetch_all(db)
+        .await?)
+    }
+
+    /// Removes deliveries which are older than [`WEBHOOK_DELIVERY_RETENTION`].
+    pub async fn delete_expired(db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM addon_webhook_delivery WHERE created_at < $1")
+            .bind(OffsetDateTime::now_utc() - WEBHOOK_DELIVERY_RETENTION)
+            .execute(db)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+}
@@ -93,6 +93,33 @@ WHERE pk = $1 AND website_id = $7"#,
         Ok(res.rows_affected())
     }
 
+    pub async fn delete(pk: i32, website_id: WebsiteId, db: &mut SqliteConnection) -> Result<u64> {
+        let res =
+            sqlx::query("DELETE FROM addon_widget_settings WHERE pk = $1 AND website_id = $2")
+                .bind(pk)
+                .bind(website_id)
+                .execute(db)
+                .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn delete_by_website_id_and_addon_id(
+        website_id: WebsiteId,
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<u64> {
+        let res = sqlx::query(
+            "DELETE FROM addon_widget_settings WHERE website_id = $1 AND addon_id = $2",
+        )
+        .bind(website_id)
+        .bind(addon_id)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
     pub async fn find_one_by_pk(
         pk: i32,
         website_id: WebsiteId,
@@ -139,6 +166,30 @@ WHERE website_id = $1"#,
         .await?)
     }
 
+    pub async fn find_all_by_website_id_and_addon_id(
+        website_id: WebsiteId,
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            r#"SELECT
+    pk,
+    website_id,
+    addon_id,
+    addon_widget_id,
+    object_id,
+    settings,
+    created_at,
+    updated_at
+FROM addon_widget_settings
+WHERE website_id = $1 AND addon_id = $2"#,
+        )
+        .bind(website_id)
+        .bind(addon_id)
+        .fetch_all(db)
+        .await?)
+    }
+
     pub async fn find_one_by_website_id_and_object_id(
         website_id: WebsiteId,
         widget_id: AddonWidgetId,
@@ -158,6 +158,18 @@ impl AddonWidgetContent {
         )
     }
 
+    pub async fn count_by_addon_ids(
+        ids: &[AddonId],
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<(AddonId, i32)>> {
+        Ok(sqlx::query_as(&format!(
+            "SELECT addon_id, COUNT(*) FROM addon_widget_content WHERE addon_id IN ({}) GROUP BY addon_id",
+            ids.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+        ))
+        .fetch_all(db)
+        .await?)
+    }
+
     pub async fn find_by_addon_id(id: AddonId, db: &mut SqliteConnection) -> Result<Vec<Self>> {
         Ok(
             sqlx::query_as(
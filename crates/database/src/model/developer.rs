@@ -0,0 +1,164 @@
+// The public profile addons are published under. Each member has at most one.
+
+use eyre::Result;
+use local_common::{api::DeveloperPublic, DeveloperId, MediaId, MemberId};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+pub struct NewDeveloperModel {
+    pub member_id: MemberId,
+    pub member_uuid: Uuid,
+
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DeveloperModel {
+    #[serde(skip)]
+    pub id: DeveloperId,
+    pub guid: Uuid,
+
+    #[serde(skip)]
+    pub member_id: MemberId,
+    #[serde(rename = "memberId")]
+    pub member_uuid: Uuid,
+
+    pub name: String,
+    pub description: String,
+    pub icon: Option<MediaId>,
+
+    pub addon_count: i32,
+
+    pub delete_reason: Option<String>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+impl NewDeveloperModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<DeveloperModel> {
+        let guid = Uuid::now_v7();
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "INSERT INTO developer (guid, member_id, member_uuid, name, description, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $6)",
+        )
+        .bind(guid)
+        .bind(self.member_id)
+        .bind(self.member_uuid)
+        .bind(&self.name)
+        .bind(&self.description)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(DeveloperModel {
+            id: DeveloperId::from(res.last_insert_rowid() as i32),
+            guid,
+            member_id: self.member_id,
+            member_uuid: self.member_uuid,
+            name: self.name,
+            description: self.description,
+            icon: None,
+            addon_count: 0,
+            delete_reason: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        })
+    }
+}
+
+impl DeveloperModel {
+    pub fn into_public(self, icon: Option<String>) -> DeveloperPublic {
+        DeveloperPublic {
+            guid: self.guid,
+            name: self.name,
+            description: self.description,
+            icon,
+            addon_count: self.addon_count,
+            delete_reason: self.delete_reason,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            deleted_at: self.deleted_at,
+        }
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE developer SET name = $2, description = $3, icon = $4, updated_at = $5 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.name)
+        .bind(&self.description)
+        .bind(self.icon)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Links the addons the member created before having a profile.
+    pub async fn claim_member_addons(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query(
+            "UPDATE addon SET developer_id = $1 WHERE member_uuid = $2 AND developer_id IS NULL",
+        )
+        .bind(self.id)
+        .bind(self.member_uuid)
+        .execute(&mut *db)
+        .await?;
+
+        self.addon_count = Self::refresh_addon_count(self.id, db).await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Recounts the addons of the developer which are visible and aren't deleted, the same ones
+    /// listed on their page.
+    pub async fn refresh_addon_count(id: DeveloperId, db: &mut SqliteConnection) -> Result<i32> {
+        Ok(sqlx::query_scalar(
+            "UPDATE developer SET addon_count = (SELECT COUNT(*) FROM addon WHERE developer_id = $1 AND is_visible = TRUE AND deleted_at IS NULL) WHERE id = $1 RETURNING addon_count",
+        )
+        .bind(id)
+        .fetch_one(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_guid(guid: Uuid, db: &mut SqliteConnection) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, guid, member_id, member_uuid, name, description, icon, addon_count, delete_reason, created_at, updated_at, deleted_at FROM developer WHERE guid = $1",
+        )
+        .bind(guid)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_member_uuid(
+        member_uuid: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, guid, member_id, member_uuid, name, description, icon, addon_count, delete_reason, created_at, updated_at, deleted_at FROM developer WHERE member_uuid = $1",
+        )
+        .bind(member_uuid)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_by_ids(ids: &[DeveloperId], db: &mut SqliteConnection) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(&format!(
+            "SELECT id, guid, member_id, member_uuid, name, description, icon, addon_count, delete_reason, created_at, updated_at, deleted_at FROM developer WHERE id IN ({})",
+            ids.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+        ))
+        .fetch_all(db)
+        .await?)
+    }
+}
@@ -12,20 +12,24 @@ use sqlx::{
 };
 
 mod addon;
+mod developer;
 mod media_upload;
 mod schema;
 mod schema_data;
 mod schema_data_tag;
 mod settings;
+mod tag;
 mod vissl;
 
 pub use addon::*;
+pub use developer::*;
 pub use media_upload::*;
 pub use schema::*;
 pub use schema_data::*;
 pub use schema_data_tag::*;
+pub use settings::*;
+pub use tag::*;
 pub use vissl::*;
-// pub use settings::*;
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(transparent)]
@@ -188,6 +188,21 @@ impl SchemaModel {
         )
     }
 
+    pub async fn count_by_addon_ids(
+        ids: &[AddonId],
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<(AddonId, i32)>> {
+        Ok(sqlx::query_as(&format!(
+            "SELECT addon_id, COUNT(*) FROM schema WHERE addon_id IN ({}) GROUP BY addon_id",
+            ids.iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        ))
+        .fetch_all(db)
+        .await?)
+    }
+
     pub async fn find_one_by_id(id: SchemaId, db: &mut SqliteConnection) -> Result<Option<Self>> {
         Ok(sqlx::query_as(
             "SELECT id, name, addon_id, primary_field, display_name, permissions, version, allowed_operations, ttl, default_sort, views, store, fields, created_at, updated_at, deleted_at FROM schema WHERE schema.id = $1",
@@ -23,6 +23,8 @@ pub struct NewSchemaDataModel {
     pub schema_id: SchemaId,
 
     pub public_id: Uuid,
+    /// Set when the row came from a file import.
+    pub import_id: Option<String>,
 
     pub field_text: Option<Json<HashMap<String, String>>>,
     pub field_number: Option<Json<HashMap<String, Number>>>,
@@ -165,6 +167,7 @@ impl NewSchemaDataModel {
             addon_id,
             schema_id,
             public_id: Uuid::now_v7(),
+            import_id: None,
             field_text: None,
             field_number: None,
             field_url: None,
@@ -239,9 +242,9 @@ impl NewSchemaDataModel {
                     field_text, field_number, field_url, field_email, field_address, field_phone, field_bool, field_datetime, field_date,
                     field_time, field_rich_content, field_rich_text, field_reference, field_multi_reference, field_gallery, field_document,
                     field_multi_document, field_image, field_video, field_audio, field_tags, field_array, field_object,
-                    created_at, updated_at
+                    created_at, updated_at, import_id
                 )
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29)
             "#,
         )
         .bind(self.addon_id)
@@ -272,6 +275,7 @@ impl NewSchemaDataModel {
         .bind(&self.field_object)
         .bind(self.created_at)
         .bind(self.updated_at)
+        .bind(&self.import_id)
         .execute(db)
         .await?;
 
@@ -434,9 +438,117 @@ impl NewSchemaDataModel {
 
         Ok(())
     }
+
+    pub fn remove_field(
+        &mut self,
+        field_name: &str,
+        is_field_in_duplicator: bool,
+        field_type: SchematicFieldType,
+    ) {
+        fn remove<V>(data_value: &mut Option<Json<HashMap<String, V>>>, field_name: &str) {
+            if let Some(data_value) = data_value.as_mut() {
+                data_value.remove(field_name);
+            }
+        }
+
+        if is_field_in_duplicator {
+            return remove(&mut self.field_array, field_name);
+        }
+
+        match field_type {
+            SchematicFieldType::Text => remove(&mut self.field_text, field_name),
+            SchematicFieldType::Number => remove(&mut self.field_number, field_name),
+            SchematicFieldType::URL => remove(&mut self.field_url, field_name),
+            SchematicFieldType::Email => remove(&mut self.field_email, field_name),
+            SchematicFieldType::Address => remove(&mut self.field_address, field_name),
+            SchematicFieldType::Phone => remove(&mut self.field_phone, field_name),
+            SchematicFieldType::Boolean => remove(&mut self.field_bool, field_name),
+            SchematicFieldType::DateTime => remove(&mut self.field_datetime, field_name),
+            SchematicFieldType::Date => remove(&mut self.field_date, field_name),
+            SchematicFieldType::Time => remove(&mut self.field_time, field_name),
+            SchematicFieldType::RichContent => remove(&mut self.field_rich_content, field_name),
+            SchematicFieldType::RichText => remove(&mut self.field_rich_text, field_name),
+            SchematicFieldType::Reference => remove(&mut self.field_reference, field_name),
+            SchematicFieldType::MultiReference => {
+                remove(&mut self.field_multi_reference, field_name)
+            }
+            SchematicFieldType::MediaGallery => remove(&mut self.field_gallery, field_name),
+            SchematicFieldType::Document => remove(&mut self.field_document, field_name),
+            SchematicFieldType::MultiDocument => remove(&mut self.field_multi_document, field_name),
+            SchematicFieldType::Image => remove(&mut self.field_image, field_name),
+            SchematicFieldType::Video => remove(&mut self.field_video, field_name),
+            SchematicFieldType::Audio => remove(&mut self.field_audio, field_name),
+            SchematicFieldType::Tags => remove(&mut self.field_tags, field_name),
+            SchematicFieldType::Array => remove(&mut self.field_array, field_name),
+            SchematicFieldType::Object => remove(&mut self.field_object, field_name),
+        }
+    }
+
+    /// Overwrites every field of an existing row.
+    pub async fn update(
+        mut self,
+        id: SchemaDataId,
+        db: &mut SqliteConnection,
+    ) -> Result<SchemaDataModel> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        sqlx::query(
+            r#"
+                UPDATE schema_data SET
+                    field_text = $2, field_number = $3, field_url = $4, field_email = $5, field_address = $6, field_phone = $7,
+                    field_bool = $8, field_datetime = $9, field_date = $10, field_time = $11, field_rich_content = $12,
+                    field_rich_text = $13, field_reference = $14, field_multi_reference = $15, field_gallery = $16,
+                    field_document = $17, field_multi_document = $18, field_image = $19, field_video = $20, field_audio = $21,
+                    field_tags = $22, field_array = $23, field_object = $24,
+                    updated_at = $25
+                WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(&self.field_text)
+        .bind(&self.field_number)
+        .bind(&self.field_url)
+        .bind(&self.field_email)
+        .bind(&self.field_address)
+        .bind(&self.field_phone)
+        .bind(&self.field_bool)
+        .bind(&self.field_datetime)
+        .bind(&self.field_date)
+        .bind(&self.field_time)
+        .bind(&self.field_rich_content)
+        .bind(&self.field_rich_text)
+        .bind(&self.field_reference)
+        .bind(&self.field_multi_reference)
+        .bind(&self.field_gallery)
+        .bind(&self.field_document)
+        .bind(&self.field_multi_document)
+        .bind(&self.field_image)
+        .bind(&self.field_video)
+        .bind(&self.field_audio)
+        .bind(&self.field_tags)
+        .bind(&self.field_array)
+        .bind(&self.field_object)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(self.into_self(id))
+    }
 }
 
 impl SchemaDataModel {
+    /// Same as [`Self::into_new`] but keeps the row's identity so it can be passed to [`NewSchemaDataModel::update`].
+    pub fn into_editing(self) -> NewSchemaDataModel {
+        let public_id = self.public_id;
+        let created_at = self.created_at;
+
+        let mut new = self.into_new();
+        new.public_id = public_id;
+        new.created_at = created_at;
+
+        new
+    }
+
     pub fn into_new(self) -> NewSchemaDataModel {
         let now = OffsetDateTime::now_utc();
 
@@ -444,6 +556,7 @@ impl SchemaDataModel {
             addon_id: self.addon_id,
             schema_id: self.schema_id,
             public_id: Uuid::now_v7(),
+            import_id: None,
 
             field_text: self.field_text,
             field_number: self.field_number,
@@ -544,7 +657,7 @@ impl SchemaDataModel {
                         created_at, updated_at, deleted_at
                     FROM schema_data
                     WHERE
-                        schema_id = $1
+                        schema_id = $1 AND deleted_at IS NULL
                     LIMIT $2 OFFSET $3",
                 )
                 .bind(schema_id)
@@ -632,7 +745,7 @@ FROM schema_data");
 
                 writeln!(
                     &mut sql_building,
-                    "WHERE addon_id = $1 AND schema_id = $2"
+                    "WHERE addon_id = $1 AND schema_id = $2 AND deleted_at IS NULL"
                 )?;
 
                 let mut pos = 3;
@@ -818,7 +931,10 @@ FROM schema_data");
                     )?;
                 }
 
-                writeln!(&mut sql_building, "WHERE addon_id = $1 AND schema_id = $2")?;
+                writeln!(
+                    &mut sql_building,
+                    "WHERE addon_id = $1 AND schema_id = $2 AND deleted_at IS NULL"
+                )?;
 
                 let mut pos = 3;
 
@@ -889,7 +1005,7 @@ FROM schema_data");
             }
 
             None => Ok(sqlx::query_scalar(
-                "SELECT COUNT(id) FROM schema_data WHERE schema_id = $1",
+                "SELECT COUNT(id) FROM schema_data WHERE schema_id = $1 AND deleted_at IS NULL",
             )
             .bind(schema_id)
             .fetch_one(db)
@@ -902,16 +1018,119 @@ FROM schema_data");
     // FROM schema_data, json_tree(schema_data.field_text, '$.text')
     // WHERE addon_id = $1 AND schema_id = $2 AND field_text IS NOT NULL AND json_tree.value LIKE $3'%%'
 
-    pub async fn delete(id: AddonId, db: &mut SqliteConnection) -> Result<u64> {
-        let res = sqlx::query("UPDATE schema_data SET deleted_at = $2 WHERE id = $1")
-            .bind(id)
-            .bind(OffsetDateTime::now_utc())
+    /// Moves the row into the trash. Returns false if it isn't found or is already in the trash.
+    pub async fn soft_delete_by_public_id(
+        schema_id: SchemaId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<bool> {
+        let res = sqlx::query(
+            "UPDATE schema_data SET deleted_at = $3 WHERE schema_id = $1 AND public_id = $2 AND deleted_at IS NULL",
+        )
+        .bind(schema_id)
+        .bind(public_id)
+        .bind(OffsetDateTime::now_utc())
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected() != 0)
+    }
+
+    /// Takes the row out of the trash. Returns false if it isn't in the trash.
+    pub async fn restore_by_public_id(
+        schema_id: SchemaId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<bool> {
+        let res = sqlx::query(
+            "UPDATE schema_data SET deleted_at = NULL WHERE schema_id = $1 AND public_id = $2 AND deleted_at IS NOT NULL",
+        )
+        .bind(schema_id)
+        .bind(public_id)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected() != 0)
+    }
+
+    /// Permanently removes the row.
+    pub async fn delete(self, db: &mut SqliteConnection) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM schema_data WHERE id = $1")
+            .bind(self.id)
             .execute(db)
             .await?;
 
         Ok(res.rows_affected())
     }
 
+    pub async fn find_one_deleted_by_public_id(
+        schema_id: SchemaId,
+        public_id: Uuid,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, schema_id, public_id,
+            field_text, field_number, field_url, field_email, field_address, field_phone, field_bool, field_datetime, field_date,
+            field_time, field_rich_content, field_rich_text, field_reference, field_multi_reference, field_gallery, field_document,
+            field_multi_document, field_image, field_video, field_audio, field_tags, field_array, field_object,
+            created_at, updated_at, deleted_at FROM schema_data WHERE schema_id = $1 AND public_id = $2 AND deleted_at IS NOT NULL",
+        )
+        .bind(schema_id)
+        .bind(public_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// Rows in the trash, most recently deleted first.
+    pub async fn find_deleted_by_schema_id(
+        schema_id: SchemaId,
+        offset: i64,
+        limit: i64,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, schema_id, public_id,
+            field_text, field_number, field_url, field_email, field_address, field_phone, field_bool, field_datetime, field_date,
+            field_time, field_rich_content, field_rich_text, field_reference, field_multi_reference, field_gallery, field_document,
+            field_multi_document, field_image, field_video, field_audio, field_tags, field_array, field_object,
+            created_at, updated_at, deleted_at FROM schema_data WHERE schema_id = $1 AND deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC, id DESC LIMIT $2 OFFSET $3",
+        )
+        .bind(schema_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn find_all_deleted_by_schema_id(
+        schema_id: SchemaId,
+        db: &mut SqliteConnection,
+    ) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, schema_id, public_id,
+            field_text, field_number, field_url, field_email, field_address, field_phone, field_bool, field_datetime, field_date,
+            field_time, field_rich_content, field_rich_text, field_reference, field_multi_reference, field_gallery, field_document,
+            field_multi_document, field_image, field_video, field_audio, field_tags, field_array, field_object,
+            created_at, updated_at, deleted_at FROM schema_data WHERE schema_id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(schema_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub async fn count_deleted_by_schema_id(
+        schema_id: SchemaId,
+        db: &mut SqliteConnection,
+    ) -> Result<i64> {
+        Ok(sqlx::query_scalar(
+            "SELECT COUNT(*) FROM schema_data WHERE schema_id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(schema_id)
+        .fetch_one(db)
+        .await?)
+    }
+
     pub async fn count_by_website_id(addon_id: AddonId, db: &mut SqliteConnection) -> Result<i32> {
         Ok(sqlx::query_scalar(
             "SELECT COUNT(*) FROM schema_data where addon_id = $1 AND deleted_at IS NULL",
@@ -930,6 +1149,21 @@ FROM schema_data");
         .await?)
     }
 
+    /// Includes rows in the trash, restoring them is preferred over importing them again.
+    pub async fn exists_by_import_id(
+        schema_id: SchemaId,
+        import_id: &str,
+        db: &mut SqliteConnection,
+    ) -> Result<bool> {
+        Ok(sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM schema_data WHERE schema_id = $1 AND import_id = $2)",
+        )
+        .bind(schema_id)
+        .bind(import_id)
+        .fetch_one(db)
+        .await?)
+    }
+
     pub async fn get_id_from_public_id(
         uuid: Uuid,
         db: &mut SqliteConnection,
@@ -65,6 +65,32 @@ impl SchemaDataTagModel {
         Ok(res.rows_affected())
     }
 
+    /// Removes the tags which are no longer used by any row of the schema, including rows in the trash.
+    pub async fn delete_unused(
+        schema_id: SchemaId,
+        ids: &[SchemaDataTagId],
+        db: &mut SqliteConnection,
+    ) -> Result<u64> {
+        let mut removed = 0;
+
+        for id in ids {
+            let res = sqlx::query(
+                r#"DELETE FROM schema_data_tag WHERE id = $1 AND schema_id = $2 AND NOT EXISTS (
+                    SELECT 1 FROM schema_data, json_each(schema_data.field_tags) AS col, json_each(col.value) AS tag
+                    WHERE schema_data.schema_id = $2 AND tag.value = $1
+                )"#,
+            )
+            .bind(*id)
+            .bind(schema_id)
+            .execute(&mut *db)
+            .await?;
+
+            removed += res.rows_affected();
+        }
+
+        Ok(removed)
+    }
+
     pub async fn find_one(
         schema_id: SchemaId,
         row_id: &str,
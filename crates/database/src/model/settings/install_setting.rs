@@ -1 +1,291 @@
 // Widget Install Settings
+// The settings an addon instance can be configured with when installed on a website.
+// Declared per compiled version, the same as the extension manifest.
+
+use eyre::Result;
+use local_common::{AddonCompiledId, AddonId, AddonInstallSettingsId};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{types::Json, FromRow, SqliteConnection};
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum InstallSettingType {
+    Text {
+        #[serde(default, rename = "maxLength")]
+        max_length: Option<usize>,
+    },
+    Number {
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    Boolean,
+    Enum {
+        options: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallSettingField {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+
+    #[serde(flatten)]
+    pub type_of: InstallSettingType,
+
+    #[serde(default)]
+    pub default: Option<Value>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl InstallSettingField {
+    /// Checks the value is of the field's type.
+    pub fn check(&self, value: &Value) -> std::result::Result<(), String> {
+        let name = &self.name;
+
+        match &self.type_of {
+            InstallSettingType::Text { max_length } => {
+                let Some(text) = value.as_str() else {
+                    return Err(format!("\"{name}\" must be text"));
+                };
+
+                if let Some(max_length) = max_length {
+                    if text.chars().count() > *max_length {
+                        return Err(format!(
+                            "\"{name}\" can't be longer than {max_length} characters"
+                        ));
+                    }
+                }
+            }
+
+            InstallSettingType::Number { min, max } => {
+                let Some(number) = value.as_f64() else {
+                    return Err(format!("\"{name}\" must be a number"));
+                };
+
+                if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                    return Err(format!("\"{name}\" is out of range"));
+                }
+            }
+
+            InstallSettingType::Boolean => {
+                if !value.is_boolean() {
+                    return Err(format!("\"{name}\" must be true or false"));
+                }
+            }
+
+            InstallSettingType::Enum { options } => {
+                if !value
+                    .as_str()
+                    .is_some_and(|v| options.iter().any(|o| o == v))
+                {
+                    return Err(format!("\"{name}\" must be one of: {}", options.join(", ")));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Errors if the declared fields aren't usable.
+pub fn validate_install_settings(fields: &[InstallSettingField]) -> Result<()> {
+    let mut errors = Vec::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        if field.name.trim().is_empty() {
+            errors.push(String::from("Setting names can't be empty"));
+            continue;
+        }
+
+        if fields[..i].iter().any(|v| v.name == field.name) {
+            errors.push(format!("\"{}\" is declared twice", field.name));
+        }
+
+        if let InstallSettingType::Enum { options } = &field.type_of {
+            if options.is_empty() {
+                errors.push(format!("\"{}\" needs at least one option", field.name));
+            }
+        }
+
+        if let Some(default) = field.default.as_ref().filter(|v| !v.is_null()) {
+            if let Err(e) = field.check(default) {
+                errors.push(format!("Default of {e}"));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(eyre::eyre!(
+            "Invalid install settings: {}",
+            errors.join(", ")
+        ))
+    }
+}
+
+/// The defaults of the fields merged with the overrides.
+///
+/// Errors on unknown settings, values of the wrong type and required settings without a value.
+pub fn resolve_install_settings(
+    fields: &[InstallSettingField],
+    overrides: &Map<String, Value>,
+) -> Result<Map<String, Value>> {
+    let mut errors = Vec::new();
+    let mut resolved = Map::new();
+
+    for name in overrides.keys() {
+        if !fields.iter().any(|v| &v.name == name) {
+            errors.push(format!("\"{name}\" isn't a setting"));
+        }
+    }
+
+    for field in fields {
+        let value = overrides
+            .get(&field.name)
+            .or(field.default.as_ref())
+            .filter(|v| !v.is_null());
+
+        match value {
+            Some(value) => {
+                if let Err(e) = field.check(value) {
+                    errors.push(e);
+                } else {
+                    resolved.insert(field.name.clone(), value.clone());
+                }
+            }
+
+            None if field.required => errors.push(format!("\"{}\" is required", field.name)),
+            None => (),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(eyre::eyre!("Invalid settings: {}", errors.join(", ")))
+    }
+}
+
+pub struct NewAddonInstallSettingsModel {
+    pub addon_id: AddonId,
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub fields: Vec<InstallSettingField>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonInstallSettingsModel {
+    #[serde(skip)]
+    pub id: AddonInstallSettingsId,
+
+    #[serde(skip)]
+    pub addon_id: AddonId,
+    #[serde(skip)]
+    pub compiled_id: Option<AddonCompiledId>,
+
+    pub fields: Json<Vec<InstallSettingField>>,
+
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl NewAddonInstallSettingsModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<AddonInstallSettingsModel> {
+        let now = OffsetDateTime::now_utc();
+
+        let fields = Json(self.fields);
+
+        let res = sqlx::query(
+            "INSERT INTO addon_install_settings (addon_id, compiled_id, fields, created_at, updated_at) VALUES ($1, $2, $3, $4, $4)",
+        )
+        .bind(self.addon_id)
+        .bind(self.compiled_id)
+        .bind(&fields)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(AddonInstallSettingsModel {
+            id: AddonInstallSettingsId::from(res.last_insert_rowid() as i32),
+            addon_id: self.addon_id,
+            compiled_id: self.compiled_id,
+            fields,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+impl AddonInstallSettingsModel {
+    pub fn resolve(&self, overrides: &Map<String, Value>) -> Result<Map<String, Value>> {
+        resolve_install_settings(&self.fields, overrides)
+    }
+
+    pub async fn update(&mut self, db: &mut SqliteConnection) -> Result<u64> {
+        self.updated_at = OffsetDateTime::now_utc();
+
+        let res = sqlx::query(
+            "UPDATE addon_install_settings SET fields = $2, updated_at = $3 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(&self.fields)
+        .bind(self.updated_at)
+        .execute(db)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Copies the schema being edited into the compiled version.
+    pub async fn publish_draft(
+        addon_id: AddonId,
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<AddonInstallSettingsModel> {
+        let fields = Self::find_one_draft_by_addon_id(addon_id, db)
+            .await?
+            .map(|v| v.fields.0)
+            .unwrap_or_default();
+
+        NewAddonInstallSettingsModel {
+            addon_id,
+            compiled_id: Some(compiled_id),
+            fields,
+        }
+        .insert(db)
+        .await
+    }
+
+    pub async fn find_one_draft_by_addon_id(
+        addon_id: AddonId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, compiled_id, fields, created_at, updated_at FROM addon_install_settings WHERE addon_id = $1 AND compiled_id IS NULL",
+        )
+        .bind(addon_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_one_by_compiled_id(
+        compiled_id: AddonCompiledId,
+        db: &mut SqliteConnection,
+    ) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, addon_id, compiled_id, fields, created_at, updated_at FROM addon_install_settings WHERE compiled_id = $1",
+        )
+        .bind(compiled_id)
+        .fetch_optional(db)
+        .await?)
+    }
+}
@@ -1 +1,3 @@
 mod install_setting;
+
+pub use install_setting::*;
@@ -0,0 +1,112 @@
+// Marketplace taxonomy. Categories are curated, other tags are created when an addon uses them.
+
+use eyre::Result;
+use local_common::{api::TagPublic, TagId};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection};
+use time::OffsetDateTime;
+
+pub struct NewTagModel {
+    pub slug: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TagModel {
+    #[serde(skip)]
+    pub id: TagId,
+
+    pub slug: String,
+    pub name: String,
+
+    pub is_category: bool,
+
+    #[serde(skip)]
+    pub created_at: OffsetDateTime,
+}
+
+impl NewTagModel {
+    pub async fn insert(self, db: &mut SqliteConnection) -> Result<TagModel> {
+        let now = OffsetDateTime::now_utc();
+
+        let res = sqlx::query("INSERT INTO tag (slug, name, created_at) VALUES ($1, $2, $3)")
+            .bind(&self.slug)
+            .bind(&self.name)
+            .bind(now)
+            .execute(db)
+            .await?;
+
+        Ok(TagModel {
+            id: TagId::from(res.last_insert_rowid() as i32),
+            slug: self.slug,
+            name: self.name,
+            is_category: false,
+            created_at: now,
+        })
+    }
+}
+
+impl TagModel {
+    pub fn into_public(self) -> TagPublic {
+        TagPublic {
+            slug: self.slug,
+            name: self.name,
+            is_category: self.is_category,
+        }
+    }
+
+    /// Lowercase alphanumerics separated by a single dash.
+    pub fn slugify(name: &str) -> String {
+        let mut slug = String::with_capacity(name.len());
+
+        for c in name.trim().chars() {
+            if c.is_alphanumeric() {
+                slug.extend(c.to_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+
+        slug.trim_end_matches('-').to_string()
+    }
+
+    /// Finds the tag with the same slug as the name or creates it.
+    pub async fn find_or_create(name: &str, db: &mut SqliteConnection) -> Result<Option<Self>> {
+        let slug = Self::slugify(name);
+
+        if slug.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(tag) = Self::find_one_by_slug(&slug, db).await? {
+            return Ok(Some(tag));
+        }
+
+        Ok(Some(
+            NewTagModel {
+                slug,
+                name: name.trim().to_string(),
+            }
+            .insert(db)
+            .await?,
+        ))
+    }
+
+    pub async fn find_one_by_slug(slug: &str, db: &mut SqliteConnection) -> Result<Option<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, slug, name, is_category, created_at FROM tag WHERE slug = $1",
+        )
+        .bind(slug)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    pub async fn find_categories(db: &mut SqliteConnection) -> Result<Vec<Self>> {
+        Ok(sqlx::query_as(
+            "SELECT id, slug, name, is_category, created_at FROM tag WHERE is_category = TRUE ORDER BY name",
+        )
+        .fetch_all(db)
+        .await?)
+    }
+}